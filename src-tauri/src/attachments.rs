@@ -0,0 +1,324 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use rand::{rngs::OsRng, RngCore};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::encryption::{
+    decrypt_file_stream, encrypt_file_stream, CompositeKey, KdfParams, SaveOptions,
+};
+
+/// The suffix of the blob store directory created next to the vault file.
+const BLOB_DIRECTORY_SUFFIX: &str = ".attachments";
+
+/// The file extension of an encrypted attachment blob.
+const BLOB_EXTENSION: &str = "pkdblob";
+
+/// The file in the blob store directory listing the unreferenced blobs kept for the backups of the vault.
+const RETENTION_FILE: &str = "retained.json";
+
+/// The non-secret information of an attachment of an entry.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    /// The identifier of the attachment within the entry.
+    pub id: i64,
+    /// The file name of the attachment.
+    pub name: String,
+    /// The size of the attachment in bytes.
+    pub size: u64,
+    /// The time the attachment was added as seconds since the Unix epoch.
+    pub added: u64,
+}
+
+/// Gets the directory of the encrypted attachment blobs of the specified vault file.
+/// # Arguments
+///
+/// * `vault_path` - the vault file.
+pub fn blob_directory(vault_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", vault_path, BLOB_DIRECTORY_SUFFIX))
+}
+
+/// Gets the file of the specified attachment blob.
+fn blob_path(vault_path: &str, blob: &str) -> Result<String, anyhow::Error> {
+    if blob.is_empty() || !blob.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("Invalid attachment blob name: {}", blob));
+    }
+
+    let mut path = blob_directory(vault_path);
+    path.push(format!("{}.{}", blob, BLOB_EXTENSION));
+
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Invalid attachment path."))
+}
+
+/// Generates random bytes formatted as a lower-case hexadecimal string.
+/// # Arguments
+///
+/// * `length` - the amount of random bytes.
+pub fn random_hex(length: usize) -> Zeroizing<String> {
+    let mut bytes = Zeroizing::new(vec![0u8; length]);
+    OsRng.fill_bytes(&mut bytes);
+
+    Zeroizing::new(to_hex(&bytes))
+}
+
+/// Computes the SHA-256 hash of the specified contents as a lower-case hexadecimal string.
+pub fn content_hash(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Formats the specified bytes as a lower-case hexadecimal string.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|f| format!("{:02x}", f)).collect()
+}
+
+/// A reader computing the SHA-256 hash and the size of the contents read through it.
+struct HashingReader<'a> {
+    /// The reader over the contents.
+    inner: &'a mut dyn Read,
+    /// The hash of the contents read so far.
+    hasher: Sha256,
+    /// The amount of bytes read so far.
+    size: u64,
+}
+
+impl Read for HashingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.hasher.update(&buf[..count]);
+        self.size += count as u64;
+        Ok(count)
+    }
+}
+
+/// Encrypts the specified contents into an attachment blob.
+/// # Arguments
+///
+/// * `vault_path` - the vault file the blob belongs to.
+/// * `blob` - the name of the blob.
+/// * `key` - the random key of the blob stored in the vault.
+/// * `input` - the reader over the contents of the attachment.
+///
+/// # Returns
+/// The SHA-256 hash of the contents, see [`content_hash`], and the size of the contents in bytes.
+///
+/// # Remarks
+/// * The blob is encrypted in chunks while the contents are read so they are never held in memory as a whole.
+///   The [`KdfParams::default`] key derivation cost is enough as the key is random.
+/// * A partially written blob is removed if the encryption fails.
+pub fn store_blob(
+    vault_path: &str,
+    blob: &str,
+    key: &str,
+    input: &mut dyn Read,
+) -> Result<(String, u64), anyhow::Error> {
+    fs::create_dir_all(blob_directory(vault_path))?;
+
+    let options = SaveOptions {
+        kdf: Some(KdfParams::default()),
+        chunked: Some(true),
+        ..Default::default()
+    };
+
+    let mut reader = HashingReader {
+        inner: input,
+        hasher: Sha256::new(),
+        size: 0,
+    };
+
+    let path = blob_path(vault_path, blob)?;
+    let result = encrypt_file_stream(
+        &path,
+        &CompositeKey::new(key, None)?,
+        &mut reader,
+        0,
+        &options,
+    );
+
+    if result.is_err() {
+        let _ = fs::remove_file(&path);
+    }
+    result?;
+
+    Ok((to_hex(&reader.hasher.finalize()), reader.size))
+}
+
+/// Removes the specified attachment blob, e.g. a duplicate of an existing blob.
+/// # Arguments
+///
+/// * `vault_path` - the vault file the blob belongs to.
+/// * `blob` - the name of the blob.
+pub fn remove_blob(vault_path: &str, blob: &str) -> Result<(), anyhow::Error> {
+    Ok(fs::remove_file(blob_path(vault_path, blob)?)?)
+}
+
+/// Decrypts an attachment blob into the specified file.
+/// # Arguments
+///
+/// * `vault_path` - the vault file the blob belongs to.
+/// * `blob` - the name of the blob.
+/// * `key` - the key of the blob stored in the vault.
+/// * `target_file_name` - the file to write the contents into. The file is removed if the decryption fails.
+pub fn export_blob(
+    vault_path: &str,
+    blob: &str,
+    key: &str,
+    target_file_name: &str,
+) -> Result<(), anyhow::Error> {
+    let source = blob_path(vault_path, blob)?;
+    let key = CompositeKey::new(key, None)?;
+
+    let mut target = File::create(target_file_name)?;
+    let result = decrypt_file_stream(&source, &key, &mut target);
+
+    if result.is_err() {
+        drop(target);
+        let _ = fs::remove_file(target_file_name);
+    }
+
+    result
+}
+
+/// Removes the attachment blobs no longer referenced by the vault or its backups.
+/// # Arguments
+///
+/// * `vault_path` - the vault file the blobs belong to.
+/// * `referenced` - the names of the blobs referenced by the vault.
+/// * `backup_count` - the amount of rotating backups kept of the vault file.
+///
+/// # Remarks
+/// * Call this after each save of the vault. A blob no longer referenced by the vault is still referenced by the backup of
+///   the previous version, so it is kept for `backup_count` more saves until that backup is rotated out.
+/// * The remaining saves of the kept blobs are stored in the `retained.json` file of the blob store directory.
+pub fn remove_unreferenced_blobs(
+    vault_path: &str,
+    referenced: &HashSet<&str>,
+    backup_count: u32,
+) -> Result<(), anyhow::Error> {
+    let directory = blob_directory(vault_path);
+    if !directory.is_dir() {
+        return Ok(());
+    }
+
+    let retention_path = directory.join(RETENTION_FILE);
+    let previous: HashMap<String, u32> = match fs::read_to_string(&retention_path) {
+        Ok(json) => serde_json::from_str(&json)?,
+        Err(_) => HashMap::new(),
+    };
+    let mut retained = HashMap::new();
+
+    for item in fs::read_dir(&directory)? {
+        let path = item?.path();
+        if path.extension().and_then(|f| f.to_str()) != Some(BLOB_EXTENSION) {
+            continue;
+        }
+
+        let blob = path
+            .file_stem()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default();
+        if referenced.contains(blob) {
+            continue;
+        }
+
+        let remaining = match previous.get(blob) {
+            Some(remaining) => remaining.saturating_sub(1).min(backup_count),
+            None => backup_count,
+        };
+
+        if remaining == 0 {
+            fs::remove_file(&path)?;
+        } else {
+            retained.insert(blob.to_string(), remaining);
+        }
+    }
+
+    if retained.is_empty() {
+        if retention_path.exists() {
+            fs::remove_file(&retention_path)?;
+        }
+    } else {
+        fs::write(&retention_path, serde_json::to_string(&retained)?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty blob file for the specified vault.
+    fn write_blob(vault_path: &str, blob: &str) {
+        fs::write(blob_path(vault_path, blob).unwrap(), b"").unwrap();
+    }
+
+    /// Gets a value indicating whether the blob file of the specified vault exists.
+    fn blob_exists(vault_path: &str, blob: &str) -> bool {
+        std::path::Path::new(&blob_path(vault_path, blob).unwrap()).exists()
+    }
+
+    #[test]
+    fn unreferenced_blobs_are_kept_for_backups() {
+        let directory = std::env::temp_dir().join(format!(
+            "password_keeper_blobs_{}_{}",
+            std::process::id(),
+            random_hex(4).as_str()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        let vault_path = directory.join("vault.pkd").to_string_lossy().into_owned();
+        fs::create_dir_all(blob_directory(&vault_path)).unwrap();
+        write_blob(&vault_path, "aa");
+        write_blob(&vault_path, "bb");
+        let referenced = HashSet::from(["aa"]);
+
+        // The blob is referenced by the first and the second backup after the first and the second save.
+        for _ in 0..2 {
+            remove_unreferenced_blobs(&vault_path, &referenced, 2).unwrap();
+            assert!(blob_exists(&vault_path, "aa"));
+            assert!(blob_exists(&vault_path, "bb"));
+        }
+
+        remove_unreferenced_blobs(&vault_path, &referenced, 2).unwrap();
+        assert!(blob_exists(&vault_path, "aa"));
+        assert!(!blob_exists(&vault_path, "bb"));
+        assert!(!blob_directory(&vault_path).join(RETENTION_FILE).exists());
+
+        // Without backups the blob is removed at once.
+        write_blob(&vault_path, "cc");
+        remove_unreferenced_blobs(&vault_path, &referenced, 0).unwrap();
+        assert!(!blob_exists(&vault_path, "cc"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
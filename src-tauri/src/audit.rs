@@ -0,0 +1,359 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::attachments::{content_hash, random_hex};
+use crate::file_data::DataEntry;
+use crate::strength::{estimate_strength, Warning};
+
+/// The default minimum strength score of a password not reported as weak.
+pub const MIN_STRENGTH_SCORE: u8 = 3;
+
+/// The domains of the well-known services supporting the time-based one-time passwords.
+const TWO_FACTOR_DOMAINS: &[&str] = &[
+    "google.com",
+    "gmail.com",
+    "microsoft.com",
+    "live.com",
+    "outlook.com",
+    "apple.com",
+    "icloud.com",
+    "amazon.com",
+    "aws.amazon.com",
+    "facebook.com",
+    "instagram.com",
+    "twitter.com",
+    "x.com",
+    "linkedin.com",
+    "github.com",
+    "gitlab.com",
+    "bitbucket.org",
+    "dropbox.com",
+    "paypal.com",
+    "reddit.com",
+    "discord.com",
+    "slack.com",
+    "zoom.us",
+    "steampowered.com",
+    "epicgames.com",
+    "twitch.tv",
+    "coinbase.com",
+    "binance.com",
+    "cloudflare.com",
+    "digitalocean.com",
+    "heroku.com",
+    "npmjs.com",
+    "crates.io",
+    "proton.me",
+    "protonmail.com",
+    "wordpress.com",
+    "atlassian.com",
+    "adobe.com",
+    "ebay.com",
+    "yahoo.com",
+];
+
+/// An entry reported by the audit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// The identifier of the entry.
+    pub id: i64,
+    /// The name of the entry.
+    pub name: String,
+}
+
+/// An entry with a weak password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeakPassword {
+    /// The identifier of the entry.
+    pub id: i64,
+    /// The name of the entry.
+    pub name: String,
+    /// The strength score of the password from 0 to 4.
+    pub score: u8,
+    /// The main weakness of the password if any.
+    pub warning: Option<Warning>,
+}
+
+/// The entries sharing the same password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReusedPassword {
+    /// The salted SHA-256 hash identifying the password within the report.
+    pub hash: String,
+    /// The entries using the password.
+    pub entries: Vec<AuditEntry>,
+}
+
+/// The security audit of the entries of a vault.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditReport {
+    /// The amount of audited entries.
+    pub entry_count: usize,
+    /// The entries with a weak password, the weakest first.
+    pub weak_passwords: Vec<WeakPassword>,
+    /// The passwords used in more than one entry, the most reused first.
+    pub reused_passwords: Vec<ReusedPassword>,
+    /// The entries of the services supporting two-factor authentication without an OTP key.
+    pub missing_two_factor: Vec<AuditEntry>,
+    /// The entries without a password.
+    pub empty_passwords: Vec<AuditEntry>,
+    /// The groups of entries with the same site and the same user name.
+    pub duplicate_entries: Vec<Vec<AuditEntry>>,
+}
+
+/// Audits the security of the specified entries.
+/// # Arguments
+///
+/// * `entries` - the entries and the categories of the vault.
+/// * `min_score` - the minimum strength score of a password not reported as weak.
+///
+/// # Returns
+/// The report of the weak, reused and empty passwords, the missing two-factor authentication and the duplicate entries.
+///
+/// # Remarks
+/// * The reused passwords are grouped by a SHA-256 hash salted with a random value for each audit, so the hashes can't be
+///   compared against the hashes of known passwords or between the audits.
+/// * The name, the user name and the domain of an entry make its password easier to guess in the strength estimate.
+/// * The strength estimate uses reduced dictionaries, so a weak password made of less common words may not be reported; see [`estimate_strength`].
+pub fn audit_entries(entries: &[DataEntry], min_score: u8) -> AuditReport {
+    let salt = random_hex(16);
+    let mut report = AuditReport::default();
+    let mut reused: HashMap<String, Vec<AuditEntry>> = HashMap::new();
+    let mut duplicates: HashMap<(String, String), Vec<AuditEntry>> = HashMap::new();
+
+    for entry in entries.iter().filter(|f| !f.is_category()) {
+        report.entry_count += 1;
+        let info = AuditEntry {
+            id: entry.id,
+            name: entry.name.clone(),
+        };
+
+        let site = entry_host(entry).unwrap_or_else(|| entry.name.trim().to_lowercase());
+        let user_name = entry
+            .user_name
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        duplicates
+            .entry((site, user_name))
+            .or_default()
+            .push(info.clone());
+
+        if entry
+            .otp_auth_key
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .is_empty()
+            && entry_host(entry).is_some_and(|host| supports_two_factor(&host))
+        {
+            report.missing_two_factor.push(info.clone());
+        }
+
+        let password = entry.password.as_deref().unwrap_or_default();
+        if password.is_empty() {
+            report.empty_passwords.push(info);
+            continue;
+        }
+
+        let user_inputs: Vec<String> = [
+            Some(entry.name.as_str()),
+            entry.user_name.as_deref(),
+            entry.domain.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+
+        let estimate = estimate_strength(password, &user_inputs);
+        if estimate.score < min_score {
+            report.weak_passwords.push(WeakPassword {
+                id: entry.id,
+                name: entry.name.clone(),
+                score: estimate.score,
+                warning: estimate.feedback.warning,
+            });
+        }
+
+        let mut salted = Zeroizing::new(salt.as_bytes().to_vec());
+        salted.extend_from_slice(password.as_bytes());
+        reused.entry(content_hash(&salted)).or_default().push(info);
+    }
+
+    report.weak_passwords.sort_by_key(|f| f.score);
+
+    report.reused_passwords = reused
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(hash, entries)| ReusedPassword { hash, entries })
+        .collect();
+    report.reused_passwords.sort_by(|a, b| {
+        b.entries
+            .len()
+            .cmp(&a.entries.len())
+            .then(a.hash.cmp(&b.hash))
+    });
+
+    report.duplicate_entries = duplicates
+        .into_values()
+        .filter(|entries| entries.len() > 1)
+        .collect();
+    report
+        .duplicate_entries
+        .sort_by_key(|entries| entries.first().map(|f| f.id));
+
+    report
+}
+
+/// Gets the host name of the site of an entry in lower case without the `www.` prefix.
+/// # Returns
+/// The host name of the address or the domain of the entry; `None` if neither is set.
+fn entry_host(entry: &DataEntry) -> Option<String> {
+    [entry.address.as_deref(), entry.domain.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(|value| {
+            let value = value.trim().to_lowercase();
+            let without_scheme = value
+                .split_once("://")
+                .map_or(value.as_str(), |(_, rest)| rest);
+            let authority = without_scheme
+                .split(['/', '?', '#'])
+                .next()
+                .unwrap_or_default();
+            let host = authority.rsplit('@').next().unwrap_or_default();
+            let host = host.split(':').next().unwrap_or_default();
+            host.strip_prefix("www.").unwrap_or(host).to_string()
+        })
+        .find(|host| !host.is_empty())
+}
+
+/// Checks whether the specified host belongs to a well-known service supporting two-factor authentication.
+fn supports_two_factor(host: &str) -> bool {
+    TWO_FACTOR_DOMAINS
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_data::FileData;
+
+    /// Creates the entries of a vault from the JSON data.
+    fn entries(json: &str) -> Vec<DataEntry> {
+        FileData::from_json(json).unwrap().entries
+    }
+
+    /// Gets the sorted identifiers of the audit entries.
+    fn ids(entries: &[AuditEntry]) -> Vec<i64> {
+        let mut result: Vec<i64> = entries.iter().map(|f| f.id).collect();
+        result.sort_unstable();
+        result
+    }
+
+    const JSON: &str = r#"{
+        "entries": [
+            { "id": 1, "parentId": -1, "name": "Category", "password": "shared password 1" },
+            { "id": 2, "parentId": 1, "name": "Mail", "password": "shared password 1" },
+            { "id": 3, "parentId": 1, "name": "Shop", "password": "shared password 1" },
+            { "id": 4, "parentId": 1, "name": "Forum", "password": "shared password 1" },
+            { "id": 5, "parentId": 1, "name": "Bank", "password": "another one 2" },
+            { "id": 6, "parentId": 1, "name": "Bank again", "password": "another one 2" },
+            { "id": 7, "parentId": 1, "name": "Unique", "password": "c0rrect-h0rse-battery-staple-9" },
+            { "id": 8, "parentId": 1, "name": "Empty", "password": "" },
+            { "id": 9, "parentId": 1, "name": "Missing" },
+            { "id": 10, "parentId": 1, "name": "Also missing" }
+        ],
+        "version": 1
+    }"#;
+
+    #[test]
+    fn reused_passwords_are_grouped() {
+        let report = audit_entries(&entries(JSON), MIN_STRENGTH_SCORE);
+
+        assert_eq!(report.entry_count, 9);
+        assert_eq!(report.reused_passwords.len(), 2);
+        assert_eq!(ids(&report.reused_passwords[0].entries), [2, 3, 4]);
+        assert_eq!(ids(&report.reused_passwords[1].entries), [5, 6]);
+        assert_ne!(
+            report.reused_passwords[0].hash,
+            report.reused_passwords[1].hash
+        );
+    }
+
+    #[test]
+    fn reuse_hashes_differ_between_audits() {
+        let first = audit_entries(&entries(JSON), MIN_STRENGTH_SCORE);
+        let second = audit_entries(&entries(JSON), MIN_STRENGTH_SCORE);
+
+        assert_ne!(
+            first.reused_passwords[0].hash,
+            second.reused_passwords[0].hash
+        );
+    }
+
+    #[test]
+    fn empty_passwords_are_not_reused() {
+        let report = audit_entries(&entries(JSON), MIN_STRENGTH_SCORE);
+
+        assert_eq!(ids(&report.empty_passwords), [8, 9, 10]);
+        assert!(report
+            .reused_passwords
+            .iter()
+            .flat_map(|f| &f.entries)
+            .all(|f| f.id < 8));
+        assert!(report.weak_passwords.iter().all(|f| f.id < 8));
+    }
+
+    #[test]
+    fn duplicates_and_missing_two_factor_are_reported() {
+        let json = r#"{
+            "entries": [
+                { "id": 1, "parentId": -1, "name": "Category" },
+                { "id": 2, "parentId": 1, "name": "GitHub", "address": "https://www.github.com/login", "userName": "User", "password": "x" },
+                { "id": 3, "parentId": 1, "name": "Code", "domain": "github.com", "userName": "user ", "password": "y", "otpAuthKey": "otpauth://totp/x?secret=ABC" },
+                { "id": 4, "parentId": 1, "name": "Local", "address": "http://localhost:8080", "userName": "user", "password": "z" }
+            ],
+            "version": 1
+        }"#;
+
+        let report = audit_entries(&entries(json), 0);
+
+        assert_eq!(report.duplicate_entries.len(), 1);
+        assert_eq!(ids(&report.duplicate_entries[0]), [2, 3]);
+        assert_eq!(ids(&report.missing_two_factor), [2]);
+        assert!(report.weak_passwords.is_empty());
+    }
+}
@@ -0,0 +1,375 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde_derive::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::file_data::DataEntry;
+
+/// The length of a SHA-1 hash as hexadecimal characters.
+const SHA1_HEX_LENGTH: usize = 40;
+
+/// The SHA-1 hash of the password of an entry.
+#[derive(Debug, Clone)]
+pub struct EntryHash {
+    /// The identifier of the entry.
+    pub id: i64,
+    /// The name of the entry.
+    pub name: String,
+    /// The upper-case hexadecimal SHA-1 hash of the password.
+    pub hash: String,
+}
+
+/// An entry with a password found in the breach data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachInfo {
+    /// The identifier of the entry.
+    pub id: i64,
+    /// The name of the entry.
+    pub name: String,
+    /// The amount of times the password appears in the breach data.
+    pub count: u64,
+}
+
+/// The entries of a vault with breached passwords.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachReport {
+    /// The amount of entries with a password checked.
+    pub checked: usize,
+    /// The entries with a breached password, the most common password first.
+    pub breached: Vec<BreachInfo>,
+}
+
+/// A locally downloaded Have I Been Pwned password hash file.
+///
+/// # Remarks
+/// * The file is the SHA-1 version ordered by hash, a `HASH:COUNT` line per password, as produced by the official
+///   downloader. The hashes are searched with a binary search over the byte offsets, so the multi-gigabyte file is
+///   neither loaded into memory nor indexed.
+pub struct HibpFile {
+    reader: BufReader<File>,
+    length: u64,
+}
+
+impl HibpFile {
+    /// Opens the specified hash file.
+    /// # Arguments
+    ///
+    /// * `path` - the path of the hash file.
+    ///
+    /// # Returns
+    /// The opened file or an error if the file can't be read or it is not an ordered SHA-1 hash file.
+    pub fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        let file = File::open(path).map_err(|e| anyhow!("{}: '{}'", e, path.display()))?;
+        let length = file.metadata()?.len();
+        let mut result = Self {
+            reader: BufReader::new(file),
+            length,
+        };
+
+        if result.read_line_at(0)?.is_none() {
+            return Err(anyhow!("The hash file is empty: '{}'", path.display()));
+        }
+
+        Ok(result)
+    }
+
+    /// Finds the breach count of a password hash.
+    /// # Arguments
+    ///
+    /// * `hash` - the hexadecimal SHA-1 hash of the password.
+    ///
+    /// # Returns
+    /// The amount of times the password appears in the breach data; 0 if it doesn't.
+    pub fn count(&mut self, hash: &str) -> Result<u64, anyhow::Error> {
+        let hash = hash.to_ascii_uppercase();
+
+        // The lines starting within [low, high) are the remaining candidates; `low` is always a line start.
+        let mut low = 0;
+        let mut high = self.length;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let start = self.next_line_start(middle)?;
+            if start >= high {
+                high = middle;
+                continue;
+            }
+
+            let Some((line_hash, count, next)) = self.read_line_at(start)? else {
+                high = middle;
+                continue;
+            };
+
+            match line_hash.cmp(&hash) {
+                Ordering::Less => low = next,
+                Ordering::Equal => return Ok(count),
+                Ordering::Greater => high = start,
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// Finds the start of the first line at or after the specified offset.
+    fn next_line_start(&mut self, offset: u64) -> Result<u64, anyhow::Error> {
+        if offset == 0 {
+            return Ok(0);
+        }
+
+        self.reader.seek(SeekFrom::Start(offset - 1))?;
+        let mut skipped = Vec::new();
+        let read = self.reader.read_until(b'\n', &mut skipped)?;
+        Ok(offset - 1 + read as u64)
+    }
+
+    /// Reads the line starting at the specified offset.
+    ///
+    /// # Returns
+    /// The upper-case hash, the count and the start of the next line; `None` at the end of the file.
+    fn read_line_at(&mut self, offset: u64) -> Result<Option<(String, u64, u64)>, anyhow::Error> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)?;
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            return Ok(None);
+        }
+
+        let (hash, count) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+        let count = count.trim().parse::<u64>().ok();
+        match count {
+            Some(count)
+                if hash.len() == SHA1_HEX_LENGTH && hash.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Ok(Some((
+                    hash.to_ascii_uppercase(),
+                    count,
+                    offset + read as u64,
+                )))
+            }
+            _ => Err(anyhow!(
+                "The file is not an ordered SHA-1 hash file; invalid line at offset {}.",
+                offset
+            )),
+        }
+    }
+}
+
+/// Computes the SHA-1 hashes of the passwords of the specified entries.
+/// # Arguments
+///
+/// * `entries` - the entries and the categories of the vault.
+///
+/// # Returns
+/// The hashes of the entries with a password.
+pub fn entry_hashes(entries: &[DataEntry]) -> Vec<EntryHash> {
+    entries
+        .iter()
+        .filter(|f| !f.is_category())
+        .filter_map(|entry| {
+            let password = entry.password.as_deref().filter(|f| !f.is_empty())?;
+            let hash = Sha1::digest(password.as_bytes())
+                .iter()
+                .map(|f| format!("{:02X}", f))
+                .collect();
+
+            Some(EntryHash {
+                id: entry.id,
+                name: entry.name.clone(),
+                hash,
+            })
+        })
+        .collect()
+}
+
+/// Checks the password hashes of the entries against the breach data.
+/// # Arguments
+///
+/// * `file` - the hash file of the breach data.
+/// * `hashes` - the password hashes of the entries.
+///
+/// # Returns
+/// The entries with a breached password or an error if the hash file can't be read.
+///
+/// # Remarks
+/// * The same password shared by several entries is looked up once.
+pub fn check_breaches(
+    file: &mut HibpFile,
+    hashes: &[EntryHash],
+) -> Result<BreachReport, anyhow::Error> {
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+    let mut report = BreachReport {
+        checked: hashes.len(),
+        breached: Vec::new(),
+    };
+
+    for entry in hashes {
+        let count = match counts.get(entry.hash.as_str()) {
+            Some(count) => *count,
+            None => {
+                let count = file.count(&entry.hash)?;
+                counts.insert(&entry.hash, count);
+                count
+            }
+        };
+
+        if count > 0 {
+            report.breached.push(BreachInfo {
+                id: entry.id,
+                name: entry.name.clone(),
+                count,
+            });
+        }
+    }
+
+    report
+        .breached
+        .sort_by(|a, b| b.count.cmp(&a.count).then(a.id.cmp(&b.id)));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// The ordered hash lines of the test file.
+    const LINES: [&str; 4] = [
+        "0000000000000000000000000000000000000001:5",
+        "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824",
+        "7C4A8D09CA3762AF61E59520943DC26494F8941B:37359195",
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF:2",
+    ];
+
+    /// A hash file removed on drop.
+    struct HashFile(PathBuf);
+
+    impl HashFile {
+        /// Writes the lines with the specified line ending into a file unique to the test.
+        fn new(name: &str, line_ending: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "password_keeper_{}_{}.txt",
+                name,
+                std::process::id()
+            ));
+            std::fs::write(&path, LINES.join(line_ending) + line_ending).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for HashFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Checks the counts of the hashes in the file, the ones around and between the lines included.
+    fn assert_counts(file: &mut HibpFile) {
+        assert_eq!(
+            file.count("0000000000000000000000000000000000000001")
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            file.count("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8")
+                .unwrap(),
+            9545824
+        );
+        assert_eq!(
+            file.count("7C4A8D09CA3762AF61E59520943DC26494F8941B")
+                .unwrap(),
+            37359195
+        );
+        assert_eq!(
+            file.count("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF")
+                .unwrap(),
+            2
+        );
+
+        for missing in [
+            "0000000000000000000000000000000000000000",
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD9",
+            "A000000000000000000000000000000000000000",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE",
+        ] {
+            assert_eq!(file.count(missing).unwrap(), 0, "{}", missing);
+        }
+    }
+
+    #[test]
+    fn hashes_are_found() {
+        let path = HashFile::new("breach_lf", "\n");
+        assert_counts(&mut HibpFile::open(&path.0).unwrap());
+    }
+
+    #[test]
+    fn hashes_are_found_with_crlf() {
+        let path = HashFile::new("breach_crlf", "\r\n");
+        assert_counts(&mut HibpFile::open(&path.0).unwrap());
+    }
+
+    #[test]
+    fn invalid_file_is_rejected() {
+        let path = HashFile::new("breach_invalid", "\n");
+        std::fs::write(&path.0, "not a hash file\n").unwrap();
+        assert!(HibpFile::open(&path.0).is_err());
+
+        std::fs::write(&path.0, "").unwrap();
+        assert!(HibpFile::open(&path.0).is_err());
+    }
+
+    #[test]
+    fn shared_passwords_are_reported() {
+        let path = HashFile::new("breach_entries", "\n");
+        let json = r#"{
+            "entries": [
+                { "id": 1, "parentId": -1, "name": "Category", "password": "password" },
+                { "id": 2, "parentId": 1, "name": "A", "password": "password" },
+                { "id": 3, "parentId": 1, "name": "B", "password": "123456" },
+                { "id": 4, "parentId": 1, "name": "C", "password": "password" },
+                { "id": 5, "parentId": 1, "name": "D", "password": "not breached" },
+                { "id": 6, "parentId": 1, "name": "E", "password": "" }
+            ],
+            "version": 1
+        }"#;
+        let entries = crate::file_data::FileData::from_json(json).unwrap().entries;
+
+        let hashes = entry_hashes(&entries);
+        let report = check_breaches(&mut HibpFile::open(&path.0).unwrap(), &hashes).unwrap();
+
+        assert_eq!(report.checked, 4);
+        let breached: Vec<(i64, u64)> = report.breached.iter().map(|f| (f.id, f.count)).collect();
+        assert_eq!(breached, [(3, 37359195), (2, 9545824), (4, 9545824)]);
+    }
+}
@@ -4,6 +4,8 @@ use serde_derive::{Deserialize, Serialize};
 use tauri::Manager;
 use tokio::fs;
 
+use crate::generator::PasswordProfile;
+
 /// The software settings.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -17,12 +19,29 @@ pub struct AppConfig {
     save_window_state: bool,
     /// A value indicating whether to use dark mode with the application.
     dark_mode: bool,
+    /// An amount of rotating encrypted backups to keep next to a saved file. 0 is disabled.
+    #[serde(default = "default_backup_count")]
+    backup_count: u32,
+    /// The named password generator policies.
+    #[serde(default)]
+    password_profiles: Vec<PasswordProfile>,
+    /// The locally downloaded Have I Been Pwned SHA-1 password hash file ordered by hash.
+    #[serde(default)]
+    hibp_file: Option<String>,
     /// A value indicating whether a load error occurred.
     error: bool,
     /// An error message if one occurred.
     error_message: String,
 }
 
+/// The default amount of rotating file backups.
+fn default_backup_count() -> u32 {
+    3
+}
+
+/// The maximum amount of rotating file backups.
+const MAX_BACKUP_COUNT: u32 = 20;
+
 // The default value for the application configuration.
 impl ::std::default::Default for AppConfig {
     fn default() -> Self {
@@ -34,6 +53,9 @@ impl ::std::default::Default for AppConfig {
             lock_timeout: 10,
             failed_unlock_attempts: 10,
             dark_mode: false,
+            backup_count: default_backup_count(),
+            password_profiles: Vec::new(),
+            hibp_file: None,
         }
     }
 }
@@ -56,8 +78,36 @@ impl AppConfig {
             dark_mode: false,
             lock_timeout: 10,
             failed_unlock_attempts: 10,
+            backup_count: default_backup_count(),
+            password_profiles: Vec::new(),
+            hibp_file: None,
         }
     }
+
+    /// Gets the lock timeout in minutes. 0 means disabled.
+    pub fn lock_timeout(&self) -> u32 {
+        self.lock_timeout
+    }
+
+    /// Gets the amount of failed unlock attempts allowed before the application closes. 0 means disabled.
+    pub fn failed_unlock_attempts(&self) -> u32 {
+        self.failed_unlock_attempts
+    }
+
+    /// Gets the amount of rotating encrypted backups to keep next to a saved file, at most 20.
+    pub fn backup_count(&self) -> u32 {
+        self.backup_count.min(MAX_BACKUP_COUNT)
+    }
+
+    /// Gets the named password generator policies.
+    pub fn password_profiles(&self) -> &[PasswordProfile] {
+        &self.password_profiles
+    }
+
+    /// Gets the locally downloaded Have I Been Pwned SHA-1 password hash file.
+    pub fn hibp_file(&self) -> Option<&str> {
+        self.hibp_file.as_deref()
+    }
 }
 
 /// Gets the application config from a file or default if one doesn't exist.
@@ -74,10 +124,10 @@ pub async fn get_app_config(cfg_path: &str) -> AppConfig {
         }
     }
 
-    let result = match fs::read(cfg_path).await {
+    match fs::read(cfg_path).await {
         Ok(v) => {
             // The vector to string
-            let result = match String::from_utf8(v) {
+            match String::from_utf8(v) {
                 Ok(v) => {
                     let config: AppConfig = match serde_json::from_str(v.as_str()) {
                         Ok(v) => v,
@@ -86,16 +136,10 @@ pub async fn get_app_config(cfg_path: &str) -> AppConfig {
                     config
                 }
                 Err(e) => AppConfig::error(&e.to_string(), cfg_path),
-            };
-            result
-        }
-        Err(e) => {
-            let result = AppConfig::error(&e.to_string(), cfg_path);
-            result
+            }
         }
-    };
-
-    result
+        Err(e) => AppConfig::error(&e.to_string(), cfg_path),
+    }
 }
 
 /// Saves the application config to a settings file using serde. The file format is JSON.
@@ -107,17 +151,10 @@ pub async fn get_app_config(cfg_path: &str) -> AppConfig {
 /// # Returns
 /// `true` if the config was successfully saved; `false` otherwise.
 pub async fn set_app_config(cfg_path: &str, config: AppConfig) -> bool {
-    let result = match serde_json::to_string(&config) {
-        Ok(v) => {
-            let result = match fs::write(cfg_path, v).await {
-                Ok(_) => true,
-                Err(_) => false,
-            };
-            result
-        }
+    match serde_json::to_string(&config) {
+        Ok(v) => fs::write(cfg_path, v).await.is_ok(),
         Err(_) => false,
-    };
-    result
+    }
 }
 
 /// Gets the application config path.
@@ -132,10 +169,7 @@ pub async fn get_config_path(app_handle: &tauri::AppHandle) -> String {
     let binding = match app_handle.path().app_config_dir() {
         Ok(mut v) => {
             if !v.exists() {
-                match fs::create_dir_all(&v).await {
-                    Ok(_) => {}
-                    Err(_) => {}
-                }
+                let _ = fs::create_dir_all(&v).await;
             }
             v.push("config.json");
             v
@@ -0,0 +1,312 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use rand::{rngs::OsRng, Rng};
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// The file of the bundled [EFF large wordlist](https://www.eff.org/dice) in the application resources.
+pub const EFF_WORDLIST_RESOURCE: &str = "wordlists/eff_large_wordlist.txt";
+
+/// The amount of words in the EFF large wordlist, one for each roll of five dice.
+pub const EFF_WORDLIST_WORD_COUNT: usize = 7776;
+
+/// The maximum amount of words in a passphrase.
+const MAX_WORD_COUNT: u32 = 64;
+
+/// The capitalization of the passphrase words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capitalization {
+    /// The words are kept as in the wordlist.
+    #[default]
+    None,
+    /// The first letter of each word is capitalized.
+    FirstLetter,
+    /// The words are in upper case.
+    Upper,
+    /// The first letter of each word is capitalized at random.
+    Random,
+}
+
+/// The options for generating a passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PassphraseOptions {
+    /// The amount of words in the passphrase.
+    pub word_count: u32,
+    /// The separator between the words.
+    pub separator: String,
+    /// The capitalization of the words.
+    pub capitalization: Capitalization,
+    /// A value indicating whether to insert a random digit into a random position of a random word.
+    pub insert_digit: bool,
+}
+
+/// The default passphrase options: six words separated with a dash.
+impl ::std::default::Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            word_count: 6,
+            separator: "-".to_string(),
+            capitalization: Capitalization::None,
+            insert_digit: false,
+        }
+    }
+}
+
+/// A generated passphrase.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Passphrase {
+    /// The passphrase.
+    pub passphrase: String,
+    /// The estimated entropy of the passphrase in bits assuming the wordlist and the options are known.
+    pub entropy_bits: f64,
+}
+
+impl Drop for Passphrase {
+    fn drop(&mut self) {
+        self.passphrase.zeroize();
+    }
+}
+
+/// Parses a wordlist.
+/// # Arguments
+///
+/// * `text` - the wordlist with a word per line. A line may start with the dice roll number of the word like in the EFF wordlists.
+///
+/// # Returns
+/// The distinct words of the wordlist or an error if the list has less than two distinct words.
+pub fn parse_wordlist(text: &str) -> Result<Vec<String>, anyhow::Error> {
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let word = match (parts.next(), parts.next()) {
+            (Some(number), Some(word)) if number.chars().all(|c| c.is_ascii_digit()) => word,
+            (Some(word), None) => word,
+            (None, _) => continue,
+            _ => return Err(anyhow!("Invalid wordlist line: {}", line.trim())),
+        };
+
+        if seen.insert(word.to_string()) {
+            words.push(word.to_string());
+        }
+    }
+
+    if words.len() < 2 {
+        return Err(anyhow!(
+            "The wordlist must have at least two distinct words."
+        ));
+    }
+
+    Ok(words)
+}
+
+/// Generates a random passphrase from the specified wordlist.
+/// # Arguments
+///
+/// * `words` - the distinct words to pick from.
+/// * `options` - the options for the passphrase.
+///
+/// # Returns
+/// The passphrase with its entropy estimate or an error if the options are invalid.
+///
+/// # Remarks
+/// * The words are picked with the operating system random number generator.
+/// * The entropy counts the word choices, the random capitalization and the inserted digit with its word. The position of the digit within the word is not counted.
+pub fn generate_passphrase(
+    words: &[String],
+    options: &PassphraseOptions,
+) -> Result<Passphrase, anyhow::Error> {
+    if options.word_count == 0 || options.word_count > MAX_WORD_COUNT {
+        return Err(anyhow!(
+            "The word count must be between 1 and {}.",
+            MAX_WORD_COUNT
+        ));
+    }
+
+    if words.len() < 2 {
+        return Err(anyhow!(
+            "The wordlist must have at least two distinct words."
+        ));
+    }
+
+    let word_count = options.word_count as usize;
+    let mut entropy_bits = word_count as f64 * (words.len() as f64).log2();
+
+    let mut picked: Vec<String> = (0..word_count)
+        .map(|_| {
+            let word = &words[OsRng.gen_range(0..words.len())];
+            match options.capitalization {
+                Capitalization::None => word.clone(),
+                Capitalization::FirstLetter => capitalize(word),
+                Capitalization::Upper => word.to_uppercase(),
+                Capitalization::Random => {
+                    if OsRng.gen_bool(0.5) {
+                        capitalize(word)
+                    } else {
+                        word.clone()
+                    }
+                }
+            }
+        })
+        .collect();
+
+    if options.capitalization == Capitalization::Random {
+        entropy_bits += word_count as f64;
+    }
+
+    if options.insert_digit {
+        let word = &mut picked[OsRng.gen_range(0..word_count)];
+        let positions: Vec<usize> = word
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain(std::iter::once(word.len()))
+            .collect();
+        let position = positions[OsRng.gen_range(0..positions.len())];
+        word.insert(position, char::from(b'0' + OsRng.gen_range(0..10u8)));
+
+        entropy_bits += (10.0 * word_count as f64).log2();
+    }
+
+    let passphrase = picked.join(&options.separator);
+    picked.iter_mut().for_each(Zeroize::zeroize);
+
+    Ok(Passphrase {
+        passphrase,
+        entropy_bits,
+    })
+}
+
+/// Capitalizes the first letter of the specified word.
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a wordlist of eight words.
+    fn eight_words() -> Vec<String> {
+        parse_wordlist("alpha\nbravo\ncharlie\ndelta\necho\nfoxtrot\ngolf\nhotel").unwrap()
+    }
+
+    #[test]
+    fn numbered_wordlist_is_parsed() {
+        let words = parse_wordlist("11111\tabacus\n11112 abdomen\n\n11113\tabacus\r\n").unwrap();
+
+        assert_eq!(words, ["abacus", "abdomen"]);
+        assert!(parse_wordlist("11111 abacus extra").is_err());
+        assert!(parse_wordlist("11111 abacus\n11112 abacus").is_err());
+    }
+
+    #[test]
+    fn entropy_counts_the_choices() {
+        let words = eight_words();
+        let options = PassphraseOptions {
+            word_count: 6,
+            ..PassphraseOptions::default()
+        };
+
+        let passphrase = generate_passphrase(&words, &options).unwrap();
+
+        assert_eq!(passphrase.entropy_bits, 18.0);
+        assert_eq!(passphrase.passphrase.split('-').count(), 6);
+        assert!(passphrase
+            .passphrase
+            .split('-')
+            .all(|f| words.iter().any(|w| w == f)));
+    }
+
+    #[test]
+    fn entropy_counts_random_capitalization_and_digit() {
+        let words = eight_words();
+        let options = PassphraseOptions {
+            word_count: 4,
+            separator: " ".to_string(),
+            capitalization: Capitalization::Random,
+            insert_digit: true,
+        };
+
+        let passphrase = generate_passphrase(&words, &options).unwrap();
+
+        let expected = 4.0 * 3.0 + 4.0 + 40f64.log2();
+        assert!((passphrase.entropy_bits - expected).abs() < 1e-9);
+        assert_eq!(
+            passphrase
+                .passphrase
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn upper_case_adds_no_entropy() {
+        let options = PassphraseOptions {
+            word_count: 2,
+            capitalization: Capitalization::Upper,
+            ..PassphraseOptions::default()
+        };
+
+        let passphrase = generate_passphrase(&eight_words(), &options).unwrap();
+
+        assert_eq!(passphrase.entropy_bits, 6.0);
+        assert_eq!(passphrase.passphrase, passphrase.passphrase.to_uppercase());
+        assert!(generate_passphrase(
+            &eight_words(),
+            &PassphraseOptions {
+                word_count: 0,
+                ..PassphraseOptions::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn bundled_wordlist_has_a_word_for_each_roll() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(EFF_WORDLIST_RESOURCE);
+        // The wordlist is downloaded separately; see wordlists/README.md.
+        let text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("The wordlist '{}' is missing: {}", path.display(), e));
+
+        let words = parse_wordlist(&text).unwrap();
+
+        assert_eq!(words.len(), EFF_WORDLIST_WORD_COUNT);
+        assert_eq!(words.first().map(String::as_str), Some("abacus"));
+        assert_eq!(words.last().map(String::as_str), Some("zoom"));
+    }
+}
@@ -23,137 +23,3350 @@ SOFTWARE.
 */
 
 use aes_gcm_siv::{
-    aead::{generic_array::GenericArray, Aead, KeyInit},
+    aead::{generic_array::GenericArray, Aead, KeyInit, Payload},
     Aes256GcmSiv,
 };
 use anyhow::anyhow;
-use rand::{rngs::OsRng, RngCore};
-use std::str;
-use std::{
-    fs::File,
-    io::{Read, Write},
-};
-use zeroize::Zeroize;
+use chacha20poly1305::XChaCha20Poly1305;
+use rand::{rngs::OsRng, Rng, RngCore};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{fmt, str};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::{Zeroize, Zeroizing};
+
+// Based on a code by Sylvain Kerkour: https://github.com/skerkour/kerkour.com/tree/main/blog/2021/rust_file_encryption
+// Based on a code by Sylvain Kerkour: https://github.com/skerkour/kerkour.com/tree/main/blog/2022/rust_file_encryption_with_password
+
+/// The signature at the beginning of a versioned container file.
+const MAGIC: [u8; 8] = *b"PKDVAULT";
+
+/// The container format version written by [`encrypt_small_file`].
+const FORMAT_VERSION: u16 = 1;
+
+/// The size of the plaintext chunks in the chunked encryption (64 KiB).
+const CHUNK_SIZE: u32 = 64 * 1024;
+
+/// The smallest chunk size accepted from a file header.
+const MIN_CHUNK_SIZE: u32 = 1024;
+
+/// The largest chunk size accepted from a file header.
+const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
+/// The amount of nonce bytes holding the chunk counter and the final chunk flag in the chunked encryption.
+const CHUNK_NONCE_SUFFIX_LENGTH: usize = 5;
+
+/// The maximum size of a file header read when streaming a file.
+const MAX_HEADER_LENGTH: u64 = 64 * 1024;
+
+/// The maximum size of decompressed data accepted from a file (1 GiB).
+const MAX_DECOMPRESSED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// The compression level used with [zstd](https://en.wikipedia.org/wiki/Zstd).
+const ZSTD_LEVEL: i32 = 9;
+
+/// The length of the salt used with the key derivation function.
+const SALT_LENGTH: usize = 32;
+
+/// The length of the nonce used with the [Aes256GcmSiv](https://en.wikipedia.org/wiki/AES-GCM-SIV) cipher.
+const NONCE_LENGTH: usize = 12;
+
+/// The length of the nonce used with the [XChaCha20Poly1305](https://en.wikipedia.org/wiki/ChaCha20-Poly1305#XChaCha20-Poly1305_%E2%80%93_extended_nonce_variant) cipher.
+const EXTENDED_NONCE_LENGTH: usize = 24;
+
+/// The length of the derived encryption key.
+const KEY_LENGTH: u32 = 32;
+
+/// The length of the random data encryption key of a file with key slots.
+const DATA_KEY_LENGTH: usize = 32;
+
+/// The length of the authentication tag appended to the encrypted data by the cipher.
+const TAG_LENGTH: usize = 16;
+
+/// The length of a SHA-256 digest used for the header checksum.
+const DIGEST_LENGTH: usize = 32;
+
+/// The length of an [X25519](https://en.wikipedia.org/wiki/Curve25519) public key.
+const PUBLIC_KEY_LENGTH: usize = 32;
+
+/// The domain separation prefix for the key sealing the data encryption key to a key slot.
+const SEALING_CONTEXT: &[u8] = b"PasswordKeeper sealed key";
+
+/// The amount of random bytes in a generated key file.
+const KEY_FILE_LENGTH: usize = 64;
+
+/// The maximum amount of key slots in a file.
+const MAX_KEY_SLOTS: usize = 16;
+
+/// The alphabet of the recovery keys ([Crockford's Base32](https://www.crockford.com/base32.html)).
+const RECOVERY_KEY_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The amount of characters in a recovery key excluding the group separators.
+const RECOVERY_KEY_LENGTH: usize = 32;
+
+/// The amount of characters in a recovery key group.
+const RECOVERY_KEY_GROUP_LENGTH: usize = 4;
+
+/// The maximum accepted Argon2 memory cost in KiB (4 GiB).
+const MAX_MEM_COST: u32 = 4 * 1024 * 1024;
+
+/// The maximum accepted Argon2 time cost.
+const MAX_TIME_COST: u32 = 1024;
+
+/// The maximum accepted Argon2 degree of parallelism.
+const MAX_LANES: u32 = 64;
+
+/// The shortest unlock time accepted by the key derivation calibration in milliseconds.
+const MIN_CALIBRATION_TARGET_MS: u64 = 100;
+
+/// The longest unlock time accepted by the key derivation calibration in milliseconds.
+const MAX_CALIBRATION_TARGET_MS: u64 = 10_000;
+
+/// The time cost used by the key derivation calibration before the memory cost reaches its limit.
+const CALIBRATION_TIME_COST: u32 = 3;
+
+/// The largest memory cost in KiB proposed by the key derivation calibration (1 GiB).
+const MAX_CALIBRATION_MEM_COST: u32 = 1024 * 1024;
+
+/// The decryption failures which the caller may need to tell apart from other errors.
+///
+/// # Remarks
+/// * The header checksum is an unkeyed SHA-256 digest, so it detects only corruption and modifications made without
+///   recomputing it. The header is authenticated as the associated data of the data and the key slots instead.
+/// * A key slot can't be verified without its key, so a key slot with modified key derivation parameters, salt or wrapped
+///   key and a recomputed checksum fails to unlock like an invalid password and is reported as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptionError {
+    /// The key didn't unlock any key slot, didn't match the key check value or, for a file with neither, didn't authenticate the data.
+    InvalidPassword,
+    /// The header checksum didn't match, or the header or the data failed the authentication after the key was verified.
+    HeaderTampered,
+}
+
+impl fmt::Display for DecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptionError::InvalidPassword => write!(f, "Invalid password."),
+            DecryptionError::HeaderTampered => {
+                write!(f, "The file has been tampered with or is corrupted.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecryptionError {}
+
+/// The cipher suites which can be recorded into the container file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CipherSuite {
+    /// The [Aes256GcmSiv](https://en.wikipedia.org/wiki/AES-GCM-SIV) cipher with a 12-byte nonce.
+    #[default]
+    #[serde(rename = "aes-256-gcm-siv")]
+    Aes256GcmSiv = 1,
+    /// The [XChaCha20Poly1305](https://en.wikipedia.org/wiki/ChaCha20-Poly1305#XChaCha20-Poly1305_%E2%80%93_extended_nonce_variant) cipher with a 24-byte nonce.
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305 = 2,
+}
+
+impl CipherSuite {
+    /// Gets the cipher suite matching the specified header value.
+    fn from_u8(value: u8) -> Result<Self, anyhow::Error> {
+        match value {
+            1 => Ok(CipherSuite::Aes256GcmSiv),
+            2 => Ok(CipherSuite::XChaCha20Poly1305),
+            _ => Err(anyhow!("Unsupported cipher: {}", value)),
+        }
+    }
+
+    /// Gets the length of the nonce used with the cipher suite.
+    fn nonce_length(self) -> usize {
+        match self {
+            CipherSuite::Aes256GcmSiv => NONCE_LENGTH,
+            CipherSuite::XChaCha20Poly1305 => EXTENDED_NONCE_LENGTH,
+        }
+    }
+
+    /// Creates a new random nonce for the cipher suite.
+    fn new_nonce(self) -> Vec<u8> {
+        let mut result = vec![0u8; self.nonce_length()];
+        OsRng.fill_bytes(&mut result);
+        result
+    }
+
+    /// Encrypts the specified payload with the cipher suite.
+    /// # Arguments
+    ///
+    /// * `key` - the 32-byte encryption key.
+    /// * `nonce` - the nonce of the length the cipher suite requires.
+    /// * `payload` - the data to encrypt along with the associated data.
+    ///
+    /// # Returns
+    /// The encrypted data with the authentication tag appended.
+    fn encrypt(self, key: &[u8], nonce: &[u8], payload: Payload) -> Result<Vec<u8>, anyhow::Error> {
+        let result = match self {
+            CipherSuite::Aes256GcmSiv => Aes256GcmSiv::new(GenericArray::from_slice(key))
+                .encrypt(GenericArray::from_slice(nonce), payload),
+            CipherSuite::XChaCha20Poly1305 => XChaCha20Poly1305::new(GenericArray::from_slice(key))
+                .encrypt(GenericArray::from_slice(nonce), payload),
+        };
+
+        result.map_err(|err| anyhow!("Encrypting small file: {}", err))
+    }
+
+    /// Decrypts the specified payload with the cipher suite.
+    /// # Arguments
+    ///
+    /// * `key` - the 32-byte encryption key.
+    /// * `nonce` - the nonce the data was encrypted with.
+    /// * `payload` - the encrypted data along with the associated data.
+    ///
+    /// # Returns
+    /// The decrypted data or `None` if the authentication failed.
+    fn decrypt(self, key: &[u8], nonce: &[u8], payload: Payload) -> Option<Zeroizing<Vec<u8>>> {
+        let result = match self {
+            CipherSuite::Aes256GcmSiv => Aes256GcmSiv::new(GenericArray::from_slice(key))
+                .decrypt(GenericArray::from_slice(nonce), payload),
+            CipherSuite::XChaCha20Poly1305 => XChaCha20Poly1305::new(GenericArray::from_slice(key))
+                .decrypt(GenericArray::from_slice(nonce), payload),
+        };
+
+        result.ok().map(Zeroizing::new)
+    }
+}
+
+/// The compression methods applied to the data before the encryption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// The data is not compressed.
+    #[default]
+    None = 0,
+    /// The data is compressed with [zstd](https://en.wikipedia.org/wiki/Zstd).
+    Zstd = 1,
+    /// The data is compressed with [deflate](https://en.wikipedia.org/wiki/Deflate).
+    Deflate = 2,
+}
+
+impl Compression {
+    /// Gets the compression method matching the specified header value.
+    fn from_u8(value: u8) -> Result<Self, anyhow::Error> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            2 => Ok(Compression::Deflate),
+            _ => Err(anyhow!("Unsupported compression: {}", value)),
+        }
+    }
+
+    /// Creates a reader decompressing the data of the specified reader.
+    /// # Arguments
+    ///
+    /// * `reader` - the reader over the compressed data.
+    ///
+    /// # Returns
+    /// The reader over the decompressed data.
+    fn decoder<'a>(self, reader: Box<dyn Read + 'a>) -> Result<Box<dyn Read + 'a>, anyhow::Error> {
+        Ok(match self {
+            Compression::None => reader,
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Compression::Deflate => Box::new(flate2::read::DeflateDecoder::new(reader)),
+        })
+    }
+}
+
+/// A writer compressing the written data with a [`Compression`] method.
+enum CompressWriter<W: Write> {
+    /// The data is written uncompressed.
+    None(W),
+    /// The data is compressed with zstd.
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    /// The data is compressed with deflate.
+    Deflate(flate2::write::DeflateEncoder<W>),
+}
+
+impl<W: Write> CompressWriter<W> {
+    /// Creates a new writer compressing the data into the specified writer.
+    fn new(compression: Compression, inner: W) -> Result<Self, anyhow::Error> {
+        Ok(match compression {
+            Compression::None => CompressWriter::None(inner),
+            Compression::Zstd => {
+                CompressWriter::Zstd(zstd::stream::write::Encoder::new(inner, ZSTD_LEVEL)?)
+            }
+            Compression::Deflate => CompressWriter::Deflate(flate2::write::DeflateEncoder::new(
+                inner,
+                flate2::Compression::default(),
+            )),
+        })
+    }
+
+    /// Writes the remaining compressed data and returns the inner writer.
+    fn finish(self) -> io::Result<W> {
+        match self {
+            CompressWriter::None(inner) => Ok(inner),
+            CompressWriter::Zstd(encoder) => encoder.finish(),
+            CompressWriter::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressWriter::None(inner) => inner.write(data),
+            CompressWriter::Zstd(encoder) => encoder.write(data),
+            CompressWriter::Deflate(encoder) => encoder.write(data),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressWriter::None(inner) => inner.flush(),
+            CompressWriter::Zstd(encoder) => encoder.flush(),
+            CompressWriter::Deflate(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Creates the nonce of a chunk in the chunked encryption.
+/// # Arguments
+///
+/// * `prefix` - the random nonce prefix stored in the file header.
+/// * `counter` - the zero-based index of the chunk.
+/// * `last` - a value indicating whether the chunk is the final one.
+///
+/// # Returns
+/// The nonce in format `prefix | counter | final flag` with the counter in little-endian byte order.
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut result = Vec::with_capacity(prefix.len() + CHUNK_NONCE_SUFFIX_LENGTH);
+    result.extend_from_slice(prefix);
+    result.extend_from_slice(&counter.to_le_bytes());
+    result.push(last as u8);
+    result
+}
+
+/// A writer encrypting the written data in chunks of a fixed size.
+///
+/// # Remarks
+/// * Each chunk is encrypted with its own nonce built of the nonce prefix, the chunk index and a final chunk flag,
+///   so reordered, removed or appended chunks and a truncated file fail the authentication.
+/// * [`ChunkWriter::finish`] must be called to write the final chunk.
+struct ChunkWriter<W: Write> {
+    /// The writer the encrypted chunks are written into.
+    inner: W,
+    /// The cipher suite to encrypt the chunks with.
+    cipher: CipherSuite,
+    /// The data encryption key.
+    key: Zeroizing<[u8; DATA_KEY_LENGTH]>,
+    /// The nonce prefix of the chunks.
+    nonce_prefix: Vec<u8>,
+    /// The associated data authenticated with every chunk.
+    associated_data: Vec<u8>,
+    /// The size of the plaintext chunks.
+    chunk_size: usize,
+    /// The plaintext of the current chunk.
+    buffer: Zeroizing<Vec<u8>>,
+    /// The index of the current chunk.
+    counter: u32,
+}
+
+impl<W: Write> ChunkWriter<W> {
+    /// Creates a new chunk writer.
+    /// # Arguments
+    ///
+    /// * `inner` - the writer to write the encrypted chunks into.
+    /// * `header` - the header of the file with the cipher suite, the nonce prefix and the chunk size.
+    /// * `header_bytes` - the serialized header authenticated with every chunk.
+    /// * `key` - the data encryption key.
+    fn new(
+        inner: W,
+        header: &FileHeader,
+        header_bytes: &[u8],
+        key: &[u8; DATA_KEY_LENGTH],
+    ) -> Self {
+        Self {
+            inner,
+            cipher: header.cipher,
+            key: Zeroizing::new(*key),
+            nonce_prefix: header.nonce.clone(),
+            associated_data: header_bytes.to_vec(),
+            chunk_size: header.chunk_size as usize,
+            buffer: Zeroizing::new(Vec::with_capacity(header.chunk_size as usize)),
+            counter: 0,
+        }
+    }
+
+    /// Encrypts the buffered chunk into the inner writer.
+    fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let payload = Payload {
+            msg: &self.buffer,
+            aad: &self.associated_data,
+        };
+
+        let encrypted = self
+            .cipher
+            .encrypt(self.key.as_ref(), &nonce, payload)
+            .map_err(io::Error::other)?;
+
+        self.inner.write_all(&encrypted)?;
+        self.buffer.clear();
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other("Too many chunks."))?;
+
+        Ok(())
+    }
+
+    /// Writes the final chunk and returns the inner writer.
+    fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A full chunk is written only once more data follows as the final chunk must be flagged.
+        if self.buffer.len() == self.chunk_size && !data.is_empty() {
+            self.write_chunk(false)?;
+        }
+
+        let length = data.len().min(self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..length]);
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader decrypting the data written with a [`ChunkWriter`].
+struct ChunkReader<R: Read> {
+    /// The reader over the encrypted chunks.
+    inner: R,
+    /// The cipher suite the chunks are encrypted with.
+    cipher: CipherSuite,
+    /// The data encryption key.
+    key: Zeroizing<[u8; DATA_KEY_LENGTH]>,
+    /// The nonce prefix of the chunks.
+    nonce_prefix: Vec<u8>,
+    /// The associated data authenticated with every chunk.
+    associated_data: Vec<u8>,
+    /// The size of the plaintext chunks.
+    chunk_size: usize,
+    /// The plaintext of the current chunk.
+    buffer: Zeroizing<Vec<u8>>,
+    /// The read position within the current chunk.
+    position: usize,
+    /// The index of the next chunk.
+    counter: u32,
+    /// A byte read past the current chunk to tell whether the chunk was the final one.
+    lookahead: Option<u8>,
+    /// A value indicating whether the final chunk has been read.
+    finished: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    /// Creates a new chunk reader.
+    /// # Arguments
+    ///
+    /// * `inner` - the reader over the encrypted chunks.
+    /// * `header` - the header of the file with the cipher suite, the nonce prefix and the chunk size.
+    /// * `header_bytes` - the serialized header authenticated with every chunk.
+    /// * `key` - the data encryption key.
+    fn new(
+        inner: R,
+        header: &FileHeader,
+        header_bytes: &[u8],
+        key: &[u8; DATA_KEY_LENGTH],
+    ) -> Self {
+        Self {
+            inner,
+            cipher: header.cipher,
+            key: Zeroizing::new(*key),
+            nonce_prefix: header.nonce.clone(),
+            associated_data: header_bytes.to_vec(),
+            chunk_size: header.chunk_size as usize,
+            buffer: Zeroizing::new(Vec::new()),
+            position: 0,
+            counter: 0,
+            lookahead: None,
+            finished: false,
+        }
+    }
+
+    /// Reads and decrypts the next chunk into the buffer.
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let full_length = self.chunk_size + TAG_LENGTH;
+        let mut encrypted = Vec::with_capacity(full_length + 1);
+        encrypted.extend(self.lookahead.take());
+
+        let remaining = full_length + 1 - encrypted.len();
+        (&mut self.inner)
+            .take(remaining as u64)
+            .read_to_end(&mut encrypted)?;
+
+        let last = encrypted.len() <= full_length;
+        if !last {
+            self.lookahead = encrypted.pop();
+        }
+
+        if encrypted.len() < TAG_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The encrypted data is truncated.",
+            ));
+        }
+
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let payload = Payload {
+            msg: &encrypted,
+            aad: &self.associated_data,
+        };
+
+        self.buffer = self
+            .cipher
+            .decrypt(self.key.as_ref(), &nonce, payload)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, DecryptionError::HeaderTampered)
+            })?;
+        self.position = 0;
+        self.finished = last;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other("Too many chunks."))?;
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ChunkReader<R> {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.buffer.len() {
+            if self.finished {
+                return Ok(0);
+            }
+
+            self.read_chunk()?;
+        }
+
+        let length = data.len().min(self.buffer.len() - self.position);
+        data[..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// Reads all the data of the specified reader without leaving copies of it in the freed memory.
+/// # Arguments
+///
+/// * `reader` - the reader to read.
+/// * `limit` - the maximum amount of bytes to accept.
+///
+/// # Returns
+/// The data which is zeroed on drop or an error if the reading failed or the data exceeded the limit.
+fn read_to_end_zeroizing(reader: &mut dyn Read, limit: u64) -> io::Result<Zeroizing<Vec<u8>>> {
+    let mut result = Zeroizing::new(Vec::new());
+    let mut buffer = Zeroizing::new([0u8; 8192]);
+
+    loop {
+        let length = match reader.read(buffer.as_mut()) {
+            Ok(0) => return Ok(result),
+            Ok(length) => length,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        if (result.len() + length) as u64 > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The decompressed data is too large.",
+            ));
+        }
+
+        extend_zeroizing(&mut result, &buffer[..length]);
+    }
+}
+
+/// Appends the specified data into a buffer without leaving copies of the buffer in the freed memory.
+fn extend_zeroizing(buffer: &mut Zeroizing<Vec<u8>>, data: &[u8]) {
+    // Grow into a new buffer so the previous one is zeroed instead of being reallocated.
+    if buffer.len() + data.len() > buffer.capacity() {
+        let capacity = (buffer.len() + data.len()).max(buffer.capacity() * 2);
+        let mut grown = Zeroizing::new(Vec::with_capacity(capacity));
+        grown.extend_from_slice(buffer);
+        *buffer = grown;
+    }
+
+    buffer.extend_from_slice(data);
+}
+
+/// A writer keeping the written data in memory up to a limit and discarding the rest.
+struct BoundedBuffer {
+    /// The written data or `None` if the data exceeded the limit.
+    data: Option<Zeroizing<Vec<u8>>>,
+    /// The maximum amount of bytes to keep.
+    limit: u64,
+}
+
+impl Write for BoundedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let fits = self
+            .data
+            .as_ref()
+            .is_some_and(|data| (data.len() + buf.len()) as u64 <= self.limit);
+
+        match (&mut self.data, fits) {
+            (Some(data), true) => extend_zeroizing(data, buf),
+            _ => self.data = None,
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Copies the data of the specified reader into the specified writer without leaving copies of it in the freed memory.
+/// # Arguments
+///
+/// * `reader` - the reader to read.
+/// * `writer` - the writer to write the data into.
+fn copy_zeroizing(reader: &mut dyn Read, writer: &mut dyn Write) -> Result<(), anyhow::Error> {
+    let mut buffer = Zeroizing::new([0u8; 8192]);
+
+    loop {
+        let length = match reader.read(buffer.as_mut()) {
+            Ok(0) => return Ok(()),
+            Ok(length) => length,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(decryption_error(e)),
+        };
+
+        writer.write_all(&buffer[..length])?;
+    }
+}
+
+/// Converts an I/O error of the decryption into an error preserving a wrapped [`DecryptionError`].
+fn decryption_error(error: io::Error) -> anyhow::Error {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<DecryptionError>())
+    {
+        Some(decryption_error) => (*decryption_error).into(),
+        None => error.into(),
+    }
+}
+
+/// The [Argon2](https://en.wikipedia.org/wiki/Argon2) variants supported by the key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfVariant {
+    /// The data-dependent Argon2d variant.
+    Argon2d,
+    /// The data-independent Argon2i variant.
+    Argon2i,
+    /// The hybrid Argon2id variant.
+    Argon2id,
+}
+
+impl KdfVariant {
+    /// Gets the header value of the variant.
+    fn as_u8(self) -> u8 {
+        match self {
+            KdfVariant::Argon2d => 0,
+            KdfVariant::Argon2i => 1,
+            KdfVariant::Argon2id => 2,
+        }
+    }
+
+    /// Gets the variant matching the specified header value.
+    fn from_u8(value: u8) -> Result<Self, anyhow::Error> {
+        match value {
+            0 => Ok(KdfVariant::Argon2d),
+            1 => Ok(KdfVariant::Argon2i),
+            2 => Ok(KdfVariant::Argon2id),
+            _ => Err(anyhow!("Unsupported key derivation variant: {}", value)),
+        }
+    }
+
+    /// Gets the matching `argon2` crate variant.
+    fn as_argon2(self) -> argon2::Variant {
+        match self {
+            KdfVariant::Argon2d => argon2::Variant::Argon2d,
+            KdfVariant::Argon2i => argon2::Variant::Argon2i,
+            KdfVariant::Argon2id => argon2::Variant::Argon2id,
+        }
+    }
+}
+
+/// The [Argon2](https://en.wikipedia.org/wiki/Argon2) key derivation function parameters stored in the container file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// The Argon2 variant.
+    pub variant: KdfVariant,
+    /// The memory cost in KiB.
+    pub mem_cost: u32,
+    /// The amount of passes over the memory.
+    pub time_cost: u32,
+    /// The degree of parallelism.
+    pub lanes: u32,
+}
+
+/// The default key derivation parameters. These are also the parameters of the legacy file format.
+impl ::std::default::Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            variant: KdfVariant::Argon2id,
+            mem_cost: 16 * 1024,
+            time_cost: 8,
+            lanes: 8,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Validates the parameters so a crafted file header can't request an unreasonable amount of resources.
+    ///
+    /// # Returns
+    /// `Ok(())` if the parameters are within the accepted limits; an error otherwise.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.lanes == 0 || self.lanes > MAX_LANES {
+            return Err(anyhow!("Invalid key derivation lanes: {}", self.lanes));
+        }
+
+        if self.time_cost == 0 || self.time_cost > MAX_TIME_COST {
+            return Err(anyhow!(
+                "Invalid key derivation time cost: {}",
+                self.time_cost
+            ));
+        }
+
+        if self.mem_cost < 8 * self.lanes || self.mem_cost > MAX_MEM_COST {
+            return Err(anyhow!(
+                "Invalid key derivation memory cost: {}",
+                self.mem_cost
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Benchmarks the key derivation on the local machine and proposes Argon2id parameters matching the specified unlock time.
+    /// # Arguments
+    ///
+    /// * `target_ms` - the desired time to derive a key in milliseconds; clamped between 100 and 10 000.
+    ///
+    /// # Returns
+    /// The proposed parameters along with the measured key derivation time.
+    ///
+    /// # Remarks
+    /// * The memory cost is scaled first with a time cost of 3 as memory is the costlier resource for an attacker. The time cost is raised only after the memory cost reaches 1 GiB.
+    /// * The proposed parameters are never weaker than the [`KdfParams::default`] parameters.
+    pub fn calibrate(target_ms: u64) -> Result<KdfCalibration, anyhow::Error> {
+        let target = target_ms.clamp(MIN_CALIBRATION_TARGET_MS, MAX_CALIBRATION_TARGET_MS) as f64;
+        let default = Self::default();
+
+        let mut params = Self {
+            variant: KdfVariant::Argon2id,
+            time_cost: CALIBRATION_TIME_COST,
+            ..default
+        };
+
+        let elapsed = params.benchmark()?.as_secs_f64() * 1000.0;
+        let scale = target / elapsed.max(1.0);
+
+        let mem_cost = (params.mem_cost as f64 * scale) as u32;
+        params.mem_cost = mem_cost.clamp(default.mem_cost, MAX_CALIBRATION_MEM_COST) / 1024 * 1024;
+
+        if mem_cost > MAX_CALIBRATION_MEM_COST {
+            let scale = mem_cost as f64 / MAX_CALIBRATION_MEM_COST as f64;
+            params.time_cost = ((params.time_cost as f64 * scale) as u32).min(MAX_TIME_COST);
+        }
+
+        params.time_cost = params.time_cost.max(default.time_cost);
+
+        let duration = params.benchmark()?;
+
+        Ok(KdfCalibration {
+            params,
+            duration_ms: duration.as_millis().try_into()?,
+        })
+    }
+
+    /// Measures the time it takes to derive a key with the parameters.
+    fn benchmark(&self) -> Result<Duration, anyhow::Error> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+
+        let start = Instant::now();
+        self.derive_key(b"PasswordKeeper calibration", &salt)?;
+        Ok(start.elapsed())
+    }
+
+    /// Creates an [Argon2](https://en.wikipedia.org/wiki/Argon2) key derivation function configuration of the parameters.
+    /// # Returns
+    /// An [Argon2](https://en.wikipedia.org/wiki/Argon2) key derivation function configuration.
+    fn argon2_config<'a>(&self) -> argon2::Config<'a> {
+        argon2::Config {
+            variant: self.variant.as_argon2(),
+            hash_length: KEY_LENGTH,
+            lanes: self.lanes,
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            ..Default::default()
+        }
+    }
+
+    /// Derives an encryption key from the specified password and salt.
+    /// # Arguments
+    ///
+    /// * `password` - the password to derive the key from.
+    /// * `salt` - the salt to use with the key derivation.
+    ///
+    /// # Returns
+    /// The derived key which is zeroed on drop.
+    fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, anyhow::Error> {
+        self.validate()?;
+        let key = argon2::hash_raw(password, salt, &self.argon2_config())?;
+        Ok(Zeroizing::new(key))
+    }
+}
+
+/// The result of the key derivation parameter calibration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KdfCalibration {
+    /// The proposed key derivation parameters.
+    pub params: KdfParams,
+    /// The measured time to derive a key with the proposed parameters in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// The options for saving a file. The values not specified are kept from the existing file or defaulted for a new file.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SaveOptions {
+    /// The key derivation parameters for the password slot of the key.
+    pub kdf: Option<KdfParams>,
+    /// The cipher suite to encrypt the data with.
+    pub cipher: Option<CipherSuite>,
+    /// The compression to apply to the data before the encryption.
+    pub compression: Option<Compression>,
+    /// A value indicating whether to encrypt the data in chunks so it can be streamed with a bounded memory use.
+    pub chunked: Option<bool>,
+    /// A value indicating whether to replace an existing file the key doesn't unlock or which isn't a readable container file, e.g. with "save as". Such a file is kept and an error is returned otherwise.
+    pub overwrite: bool,
+}
+
+/// The master key of a file: a password, a key file or both combined.
+///
+/// # Remarks
+/// * With a password only the password itself is used as the key derivation input so the files saved before the key file support stay compatible.
+/// * With a key file only (an empty password) the SHA-256 digest of the key file contents is used as the key derivation input.
+/// * With both the key derivation input is `SHA-256(SHA-256(password) | SHA-256(key file contents))`.
+#[derive(Clone)]
+pub struct CompositeKey {
+    /// The password of the key.
+    password: Zeroizing<Vec<u8>>,
+    /// The SHA-256 digest of the key file contents if a key file is used.
+    key_file_hash: Option<Zeroizing<[u8; DIGEST_LENGTH]>>,
+}
+
+impl CompositeKey {
+    /// Creates a new composite key of the specified password and an optional key file.
+    /// # Arguments
+    ///
+    /// * `password` - the password; may be empty if a key file is specified.
+    /// * `key_file_path` - an optional key file whose contents are hashed into the key.
+    ///
+    /// # Returns
+    /// The composite key or an error if the key file couldn't be read.
+    pub fn new(password: &str, key_file_path: Option<&str>) -> Result<Self, anyhow::Error> {
+        let key_file_hash = match key_file_path {
+            Some(key_file_path) => {
+                let mut file = File::open(key_file_path)?;
+                let mut hasher = Sha256::new();
+                io::copy(&mut file, &mut hasher)?;
+                Some(Zeroizing::new(hasher.finalize().into()))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            password: Zeroizing::new(password.as_bytes().to_vec()),
+            key_file_hash,
+        })
+    }
+
+    /// Gets the input for the key derivation function of a recovery key slot.
+    ///
+    /// # Returns
+    /// The normalized recovery key if the password is formatted as a recovery key and no key file is used; `None` otherwise.
+    fn recovery_input(&self) -> Option<Zeroizing<Vec<u8>>> {
+        if self.key_file_hash.is_some() {
+            return None;
+        }
+
+        normalize_recovery_key(&self.password)
+    }
+
+    /// Gets the input for the key derivation function.
+    fn kdf_input(&self) -> Zeroizing<Vec<u8>> {
+        match &self.key_file_hash {
+            None => self.password.clone(),
+            Some(key_file_hash) if self.password.is_empty() => {
+                Zeroizing::new(key_file_hash.to_vec())
+            }
+            Some(key_file_hash) => {
+                let mut hasher = Sha256::new();
+                hasher.update(Sha256::digest(self.password.as_slice()));
+                hasher.update(key_file_hash.as_slice());
+                Zeroizing::new(hasher.finalize().to_vec())
+            }
+        }
+    }
+}
+
+/// Generates a new key file with random contents.
+/// # Arguments
+///
+/// * `file_path` - the key file to create.
+///
+/// # Remarks
+/// * An existing file is not overridden as that would make the files encrypted with it inaccessible.
+pub fn write_key_file(file_path: &str) -> Result<(), anyhow::Error> {
+    let mut key = Zeroizing::new([0u8; KEY_FILE_LENGTH]);
+    OsRng.fill_bytes(key.as_mut());
+
+    write_synced(Path::new(file_path), key.as_ref())
+}
+
+/// Generates a new random recovery key.
+///
+/// # Returns
+/// The recovery key formatted into dash-separated groups, e.g. `ABCD-EFGH-...`.
+fn generate_recovery_key() -> Zeroizing<String> {
+    let mut result = Zeroizing::new(String::new());
+
+    for index in 0..RECOVERY_KEY_LENGTH {
+        if index > 0 && index % RECOVERY_KEY_GROUP_LENGTH == 0 {
+            result.push('-');
+        }
+
+        let character = RECOVERY_KEY_ALPHABET[OsRng.gen_range(0..RECOVERY_KEY_ALPHABET.len())];
+        result.push(character as char);
+    }
+
+    result
+}
+
+/// Normalizes a user-typed recovery key into the form used as the key derivation input.
+/// # Arguments
+///
+/// * `value` - the recovery key as typed by the user.
+///
+/// # Returns
+/// The upper-case recovery key without separators or `None` if the value is not a valid recovery key.
+///
+/// # Remarks
+/// * The commonly confused characters `O`, `I` and `L` are read as `0`, `1` and `1`.
+fn normalize_recovery_key(value: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    let mut result = Zeroizing::new(Vec::with_capacity(RECOVERY_KEY_LENGTH));
+
+    for character in value {
+        let character = match character.to_ascii_uppercase() {
+            b'-' | b' ' => continue,
+            b'O' => b'0',
+            b'I' | b'L' => b'1',
+            c => c,
+        };
+
+        if !RECOVERY_KEY_ALPHABET.contains(&character) {
+            return None;
+        }
+
+        result.push(character);
+    }
+
+    if result.len() == RECOVERY_KEY_LENGTH {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// The kinds of the key slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySlotKind {
+    /// The slot is unlocked with a password and / or a key file.
+    Password,
+    /// The slot is unlocked with a generated recovery key.
+    Recovery,
+}
+
+impl KeySlotKind {
+    /// Gets the header value of the kind.
+    fn as_u8(self) -> u8 {
+        match self {
+            KeySlotKind::Password => 0,
+            KeySlotKind::Recovery => 1,
+        }
+    }
+
+    /// Gets the kind matching the specified header value.
+    fn from_u8(value: u8) -> Result<Self, anyhow::Error> {
+        match value {
+            0 => Ok(KeySlotKind::Password),
+            1 => Ok(KeySlotKind::Recovery),
+            _ => Err(anyhow!("Unsupported key slot kind: {}", value)),
+        }
+    }
+}
+
+/// The data encryption key sealed to the key pair of a key slot.
+///
+/// # Remarks
+/// * The data encryption key is encrypted with a key derived from the [X25519](https://en.wikipedia.org/wiki/Curve25519) shared secret of a one-time key pair and the key pair of the slot.
+/// * Only the public key of the slot is needed to seal a new data encryption key, so the key can be replaced without the passwords of the other key slots.
+struct SealedKey {
+    /// The public key of the key pair of the slot.
+    public_key: [u8; PUBLIC_KEY_LENGTH],
+    /// The public key of the one-time key pair the data encryption key was sealed with.
+    ephemeral_key: [u8; PUBLIC_KEY_LENGTH],
+    /// The sealed data encryption key.
+    sealed_key: [u8; DATA_KEY_LENGTH + TAG_LENGTH],
+}
+
+/// A key slot giving access to the data encryption key with a password, a key file or a recovery key.
+///
+/// # Remarks
+/// * The slot wraps the secret key of its key pair with a key derived from the password, key file or recovery key and the data encryption key is sealed to the key pair.
+struct KeySlot {
+    /// The identifier of the slot, unique within the file.
+    id: u8,
+    /// The kind of the slot.
+    kind: KeySlotKind,
+    /// A user given label of the slot, e.g. the name of the person the slot belongs to.
+    label: String,
+    /// The key derivation parameters of the slot.
+    kdf: KdfParams,
+    /// The salt used with the key derivation function.
+    salt: [u8; SALT_LENGTH],
+    /// The nonce used to wrap the secret key.
+    nonce: [u8; NONCE_LENGTH],
+    /// The wrapped secret key of the key pair of the slot.
+    wrapped_key: [u8; DATA_KEY_LENGTH + TAG_LENGTH],
+    /// The data encryption key sealed to the key pair of the slot.
+    sealed: SealedKey,
+}
+
+/// The public information of a key slot.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeySlotInfo {
+    /// The identifier of the slot.
+    pub id: u8,
+    /// The kind of the slot.
+    pub kind: KeySlotKind,
+    /// The label of the slot.
+    pub label: String,
+    /// The key derivation parameters of the slot.
+    pub kdf: KdfParams,
+}
+
+impl KeySlot {
+    /// Creates a new sealed key slot for the specified data encryption key.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the slot.
+    /// * `kind` - the kind of the slot.
+    /// * `label` - the label of the slot.
+    /// * `kdf` - the key derivation parameters of the slot.
+    /// * `kdf_input` - the password, key file or recovery key material to derive the wrapping key from.
+    /// * `data_key` - the data encryption key to seal to the slot.
+    fn new(
+        id: u8,
+        kind: KeySlotKind,
+        label: &str,
+        kdf: KdfParams,
+        kdf_input: &[u8],
+        data_key: &[u8],
+    ) -> Result<Self, anyhow::Error> {
+        if label.len() > u8::MAX as usize {
+            return Err(anyhow!("The key slot label is too long."));
+        }
+
+        let secret = StaticSecret::random_from_rng(OsRng);
+
+        let mut slot = Self {
+            id,
+            kind,
+            label: label.to_string(),
+            kdf,
+            salt: [0u8; SALT_LENGTH],
+            nonce: [0u8; NONCE_LENGTH],
+            wrapped_key: [0u8; DATA_KEY_LENGTH + TAG_LENGTH],
+            sealed: SealedKey {
+                public_key: PublicKey::from(&secret).to_bytes(),
+                ephemeral_key: [0u8; PUBLIC_KEY_LENGTH],
+                sealed_key: [0u8; DATA_KEY_LENGTH + TAG_LENGTH],
+            },
+        };
+
+        OsRng.fill_bytes(&mut slot.salt);
+        OsRng.fill_bytes(&mut slot.nonce);
+
+        let key = slot.kdf.derive_key(kdf_input, &slot.salt)?;
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key[..32]));
+        let payload = Payload {
+            msg: secret.as_bytes(),
+            aad: &slot.associated_data(),
+        };
+
+        let wrapped_key = cipher
+            .encrypt(slot.nonce.as_ref().into(), payload)
+            .map_err(|err| anyhow!("Wrapping the slot key: {}", err))?;
+        slot.wrapped_key.copy_from_slice(&wrapped_key);
+
+        slot.seal_key(data_key)?;
+
+        Ok(slot)
+    }
+
+    /// Seals the specified data encryption key to the key pair of the slot with a new one-time key pair.
+    /// # Arguments
+    ///
+    /// * `data_key` - the data encryption key to seal.
+    ///
+    /// # Remarks
+    /// * No password is needed as the data encryption key is sealed with the public key of the slot.
+    fn seal_key(&mut self, data_key: &[u8]) -> Result<(), anyhow::Error> {
+        let prefix = self.prefix_bytes();
+        let sealed = &mut self.sealed;
+
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        sealed.ephemeral_key = PublicKey::from(&ephemeral).to_bytes();
+
+        let shared_secret = ephemeral.diffie_hellman(&PublicKey::from(sealed.public_key));
+        if !shared_secret.was_contributory() {
+            return Err(anyhow!("Invalid public key in key slot {}.", self.id));
+        }
+
+        let key = sealing_key(
+            shared_secret.as_bytes(),
+            &sealed.ephemeral_key,
+            &sealed.public_key,
+        );
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(key.as_ref()));
+        let payload = Payload {
+            msg: data_key,
+            aad: &sealed.associated_data(&prefix),
+        };
+
+        // The sealing key is unique to the one-time key pair, so a fixed nonce is never reused with a key.
+        let sealed_key = cipher
+            .encrypt(&[0u8; NONCE_LENGTH].into(), payload)
+            .map_err(|err| anyhow!("Sealing the data key: {}", err))?;
+        sealed.sealed_key.copy_from_slice(&sealed_key);
+
+        Ok(())
+    }
+
+    /// Tries to unwrap the data encryption key of the slot.
+    /// # Arguments
+    ///
+    /// * `kdf_input` - the password, key file or recovery key material to derive the wrapping key from.
+    ///
+    /// # Returns
+    /// The data encryption key or `None` if the key material doesn't match the slot.
+    fn unwrap_key(
+        &self,
+        kdf_input: &[u8],
+    ) -> Result<Option<Zeroizing<[u8; DATA_KEY_LENGTH]>>, anyhow::Error> {
+        let key = self.kdf.derive_key(kdf_input, &self.salt)?;
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key[..32]));
+        let payload = Payload {
+            msg: &self.wrapped_key,
+            aad: &self.associated_data(),
+        };
+
+        let unwrapped = match cipher.decrypt(self.nonce.as_ref().into(), payload) {
+            Ok(v) => Zeroizing::new(v),
+            Err(_) => return Ok(None),
+        };
+
+        // The slot key is known to be valid, so a failing unsealing means the slot was modified.
+        let mut secret_bytes = Zeroizing::new([0u8; DATA_KEY_LENGTH]);
+        secret_bytes.copy_from_slice(&unwrapped);
+        let secret = StaticSecret::from(*secret_bytes);
+
+        let sealed = &self.sealed;
+        let shared_secret = secret.diffie_hellman(&PublicKey::from(sealed.ephemeral_key));
+        let key = sealing_key(
+            shared_secret.as_bytes(),
+            &sealed.ephemeral_key,
+            &sealed.public_key,
+        );
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(key.as_ref()));
+        let payload = Payload {
+            msg: &sealed.sealed_key,
+            aad: &sealed.associated_data(&self.prefix_bytes()),
+        };
+
+        let data_key = cipher
+            .decrypt(&[0u8; NONCE_LENGTH].into(), payload)
+            .map(Zeroizing::new)
+            .map_err(|_| DecryptionError::HeaderTampered)?;
+
+        let mut result = Zeroizing::new([0u8; DATA_KEY_LENGTH]);
+        result.copy_from_slice(&data_key);
+        Ok(Some(result))
+    }
+
+    /// Gets the slot values the wrapped key is bound to.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut result = self.prefix_bytes();
+        result.extend_from_slice(&self.sealed.public_key);
+        result
+    }
+
+    /// Serializes the slot values preceding the wrapped key.
+    fn prefix_bytes(&self) -> Vec<u8> {
+        let mut result = vec![self.id, self.kind.as_u8(), self.label.len() as u8];
+        result.extend_from_slice(self.label.as_bytes());
+        result.push(self.kdf.variant.as_u8());
+        result.extend_from_slice(&self.kdf.mem_cost.to_le_bytes());
+        result.extend_from_slice(&self.kdf.time_cost.to_le_bytes());
+        result.extend_from_slice(&self.kdf.lanes.to_le_bytes());
+        result.extend_from_slice(&self.salt);
+        result.extend_from_slice(&self.nonce);
+        result
+    }
+
+    /// Serializes the slot into bytes in the current format version.
+    ///
+    /// # Remarks
+    /// The slot is laid out as `id | kind | label length | label | kdf variant | mem cost | time cost | lanes | salt | nonce | wrapped key | public key | ephemeral key | sealed key`.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut result = self.prefix_bytes();
+        result.extend_from_slice(&self.wrapped_key);
+        result.extend_from_slice(&self.sealed.public_key);
+        result.extend_from_slice(&self.sealed.ephemeral_key);
+        result.extend_from_slice(&self.sealed.sealed_key);
+        result
+    }
+
+    /// Gets the public information of the slot.
+    fn info(&self) -> KeySlotInfo {
+        KeySlotInfo {
+            id: self.id,
+            kind: self.kind,
+            label: self.label.clone(),
+            kdf: self.kdf,
+        }
+    }
+}
+
+impl Drop for KeySlot {
+    fn drop(&mut self) {
+        self.wrapped_key.zeroize();
+    }
+}
+
+impl SealedKey {
+    /// Gets the values the sealed key is bound to.
+    /// # Arguments
+    ///
+    /// * `prefix` - the serialized slot values preceding the wrapped key.
+    fn associated_data(&self, prefix: &[u8]) -> Vec<u8> {
+        let mut result = prefix.to_vec();
+        result.extend_from_slice(&self.public_key);
+        result.extend_from_slice(&self.ephemeral_key);
+        result
+    }
+}
+
+/// Derives the key sealing a data encryption key to a key slot.
+/// # Arguments
+///
+/// * `shared_secret` - the X25519 shared secret of the one-time key pair and the key pair of the slot.
+/// * `ephemeral_key` - the public key of the one-time key pair.
+/// * `public_key` - the public key of the key pair of the slot.
+///
+/// # Returns
+/// The sealing key which is zeroed on drop.
+fn sealing_key(
+    shared_secret: &[u8],
+    ephemeral_key: &[u8; PUBLIC_KEY_LENGTH],
+    public_key: &[u8; PUBLIC_KEY_LENGTH],
+) -> Zeroizing<[u8; DATA_KEY_LENGTH]> {
+    let mut hasher = Sha256::new();
+    hasher.update(SEALING_CONTEXT);
+    hasher.update(shared_secret);
+    hasher.update(ephemeral_key);
+    hasher.update(public_key);
+    Zeroizing::new(hasher.finalize().into())
+}
+
+/// The way the data encryption key of a file is protected.
+enum KeyProtection {
+    /// The data is encrypted directly with the key derived from the password with the [`KdfParams::default`] parameters (the legacy format).
+    Legacy {
+        /// The salt used with the key derivation function.
+        salt: [u8; SALT_LENGTH],
+    },
+    /// A random data encryption key is sealed to one or more key slots.
+    Slots(Vec<KeySlot>),
+}
+
+/// The header of a versioned container file.
+///
+/// # Remarks
+/// The header is laid out as `magic | version | cipher | compression | chunk size | slot count | slots | nonce | length | checksum`
+/// with the numeric values in little-endian byte order. The encrypted data follows the header.
+/// * With a zero chunk size the data is encrypted at once with the nonce and the length is the size of the encrypted data.
+/// * With a non-zero chunk size the data is encrypted in chunks, the nonce field holds the nonce prefix of the chunks and the length is zero.
+/// * A legacy file has no versioned header, see [`FileHeader::parse_legacy`].
+struct FileHeader {
+    /// The container format version; 0 for a legacy file.
+    version: u16,
+    /// The cipher suite used to encrypt the data.
+    cipher: CipherSuite,
+    /// The compression applied to the data before the encryption.
+    compression: Compression,
+    /// The size of the plaintext chunks or 0 if the data is encrypted at once.
+    chunk_size: u32,
+    /// The protection of the data encryption key.
+    protection: KeyProtection,
+    /// The nonce used with the cipher or the nonce prefix of the chunks; the length depends on the cipher suite.
+    nonce: Vec<u8>,
+    /// The length of the encrypted data in bytes; 0 with the chunked encryption.
+    data_length: u64,
+}
+
+impl FileHeader {
+    /// Serializes the header into bytes in the current format version.
+    fn to_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let slots = match &self.protection {
+            KeyProtection::Slots(slots) => slots,
+            KeyProtection::Legacy { .. } => {
+                return Err(anyhow!("Only files with key slots can be written."));
+            }
+        };
+
+        let mut result = Vec::new();
+        result.extend_from_slice(&MAGIC);
+        result.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        result.push(self.cipher as u8);
+        result.push(self.compression as u8);
+        result.extend_from_slice(&self.chunk_size.to_le_bytes());
+        result.push(slots.len().try_into()?);
+        for slot in slots {
+            result.extend_from_slice(&slot.to_bytes());
+        }
+        result.extend_from_slice(&self.nonce);
+        result.extend_from_slice(&self.data_length.to_le_bytes());
+        let checksum = Sha256::digest(&result);
+        result.extend_from_slice(&checksum);
+        Ok(result)
+    }
+
+    /// Parses a versioned header from the beginning of the specified data.
+    /// # Arguments
+    ///
+    /// * `reader` - the reader over the file contents.
+    ///
+    /// # Returns
+    /// The parsed header or an error if the data doesn't contain a valid header.
+    fn parse(reader: &mut ByteReader) -> Result<Self, anyhow::Error> {
+        reader.bytes(MAGIC.len(), "signature")?;
+
+        let version = reader.u16("format version")?;
+        if version != FORMAT_VERSION {
+            return Err(anyhow!("Unsupported file format version: {}", version));
+        }
+
+        let cipher = reader.u8("cipher")?;
+        let compression = reader.u8("compression")?;
+        let chunk_size = reader.u32("chunk size")?;
+
+        // The enumerated values are read raw and interpreted only after the checksum
+        // has been verified so a corrupted header is reported as such instead of as an invalid value.
+        let mut kdf_values = Vec::new();
+        let mut slot_kinds = Vec::new();
+        let mut slots = Vec::new();
+
+        let slot_count = reader.u8("key slot count")? as usize;
+        for _ in 0..slot_count {
+            let id = reader.u8("key slot id")?;
+            slot_kinds.push(reader.u8("key slot kind")?);
+            let label_length = reader.u8("key slot label length")? as usize;
+            let label = String::from_utf8(reader.bytes(label_length, "key slot label")?.to_vec())
+                .map_err(|_| DecryptionError::HeaderTampered)?;
+            kdf_values.push(reader.kdf_values()?);
+            let salt = reader.array("salt")?;
+            let slot_nonce = reader.array("nonce")?;
+            let wrapped_key = reader.array("wrapped key")?;
+            let sealed = SealedKey {
+                public_key: reader.array("public key")?,
+                ephemeral_key: reader.array("ephemeral key")?,
+                sealed_key: reader.array("sealed key")?,
+            };
+            slots.push((id, label, salt, slot_nonce, wrapped_key, sealed));
+        }
+
+        // The nonce length depends on the cipher, so an unknown cipher can't be read past.
+        let mut nonce_length = CipherSuite::from_u8(cipher)?.nonce_length();
+        if chunk_size > 0 {
+            nonce_length -= CHUNK_NONCE_SUFFIX_LENGTH;
+        }
+        let nonce = reader.bytes(nonce_length, "nonce")?.to_vec();
+        let data_length = reader.u64("saved data size")?;
+
+        let expected = Sha256::digest(reader.consumed());
+        let checksum: [u8; DIGEST_LENGTH] = reader.array("header checksum")?;
+        if checksum[..] != expected[..] {
+            return Err(DecryptionError::HeaderTampered.into());
+        }
+
+        let cipher = CipherSuite::from_u8(cipher)?;
+        let compression = Compression::from_u8(compression)?;
+        if chunk_size != 0 && !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(anyhow!("Invalid chunk size: {}", chunk_size));
+        }
+
+        let mut result = Vec::new();
+        for (((id, label, salt, slot_nonce, wrapped_key, sealed), kind), kdf_values) in
+            slots.into_iter().zip(slot_kinds).zip(kdf_values)
+        {
+            let (variant, mem_cost, time_cost, lanes) = kdf_values;
+            let kdf = KdfParams {
+                variant: KdfVariant::from_u8(variant)?,
+                mem_cost,
+                time_cost,
+                lanes,
+            };
+            kdf.validate()?;
+
+            result.push(KeySlot {
+                id,
+                kind: KeySlotKind::from_u8(kind)?,
+                label,
+                kdf,
+                salt,
+                nonce: slot_nonce,
+                wrapped_key,
+                sealed,
+            });
+        }
+        let protection = KeyProtection::Slots(result);
+
+        Ok(Self {
+            version,
+            cipher,
+            compression,
+            chunk_size,
+            protection,
+            nonce,
+            data_length,
+        })
+    }
+
+    /// Parses the header of a legacy file written before the versioned container format.
+    ///
+    /// # Remarks
+    /// The legacy layout is `salt | nonce | length` with the [`KdfParams::default`] key derivation parameters.
+    fn parse_legacy(reader: &mut ByteReader) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            version: 0,
+            cipher: CipherSuite::Aes256GcmSiv,
+            compression: Compression::None,
+            chunk_size: 0,
+            protection: KeyProtection::Legacy {
+                salt: reader.array("salt")?,
+            },
+            nonce: reader.bytes(NONCE_LENGTH, "nonce")?.to_vec(),
+            data_length: reader.u64("saved data size")?,
+        })
+    }
+
+    /// Gets the associated data the cipher authenticates along with the encrypted data.
+    /// # Arguments
+    ///
+    /// * `header_bytes` - the header as it was read from or written into the file.
+    fn associated_data<'a>(&self, header_bytes: &'a [u8]) -> &'a [u8] {
+        if self.version > 0 {
+            header_bytes
+        } else {
+            &[]
+        }
+    }
+}
+
+impl Drop for FileHeader {
+    fn drop(&mut self) {
+        if let KeyProtection::Legacy { salt } = &mut self.protection {
+            salt.zeroize();
+        }
+        self.nonce.zeroize();
+    }
+}
+
+/// A reader taking values from the beginning of a byte slice.
+struct ByteReader<'a> {
+    /// The data the reader was created with.
+    start: &'a [u8],
+    /// The data not yet read.
+    data: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    /// Creates a new reader over the specified data.
+    fn new(data: &'a [u8]) -> Self {
+        Self { start: data, data }
+    }
+
+    /// Gets the data read so far.
+    fn consumed(&self) -> &'a [u8] {
+        &self.start[..self.start.len() - self.data.len()]
+    }
+
+    /// Takes the specified amount of bytes.
+    /// # Arguments
+    ///
+    /// * `length` - the amount of bytes to take.
+    /// * `name` - the name of the value being read for the error message.
+    ///
+    /// # Returns
+    /// The taken bytes or an error if there is not enough data.
+    fn bytes(&mut self, length: usize, name: &str) -> Result<&'a [u8], anyhow::Error> {
+        if self.data.len() < length {
+            return Err(anyhow!("Error reading {}.", name));
+        }
+
+        let (result, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(result)
+    }
+
+    /// Takes a fixed size array.
+    fn array<const N: usize>(&mut self, name: &str) -> Result<[u8; N], anyhow::Error> {
+        let mut result = [0u8; N];
+        result.copy_from_slice(self.bytes(N, name)?);
+        Ok(result)
+    }
+
+    /// Takes a byte.
+    fn u8(&mut self, name: &str) -> Result<u8, anyhow::Error> {
+        Ok(self.array::<1>(name)?[0])
+    }
+
+    /// Takes a little-endian 16-bit unsigned integer.
+    fn u16(&mut self, name: &str) -> Result<u16, anyhow::Error> {
+        Ok(u16::from_le_bytes(self.array(name)?))
+    }
+
+    /// Takes a little-endian 32-bit unsigned integer.
+    fn u32(&mut self, name: &str) -> Result<u32, anyhow::Error> {
+        Ok(u32::from_le_bytes(self.array(name)?))
+    }
+
+    /// Takes a little-endian 64-bit unsigned integer.
+    fn u64(&mut self, name: &str) -> Result<u64, anyhow::Error> {
+        Ok(u64::from_le_bytes(self.array(name)?))
+    }
+
+    /// Takes the raw key derivation values `variant | mem cost | time cost | lanes`.
+    fn kdf_values(&mut self) -> Result<(u8, u32, u32, u32), anyhow::Error> {
+        Ok((
+            self.u8("key derivation variant")?,
+            self.u32("key derivation memory cost")?,
+            self.u32("key derivation time cost")?,
+            self.u32("key derivation lanes")?,
+        ))
+    }
+}
+
+/// The data encryption key of a file unlocked with a key.
+struct UnlockedKey {
+    /// The key the data is encrypted with.
+    data_key: Zeroizing<[u8; DATA_KEY_LENGTH]>,
+    /// The identifier of the key slot which was unlocked; `None` for a legacy file.
+    unlocked_slot: Option<u8>,
+    /// A value indicating whether the key is known to be valid so a failing authentication means the header or the data was modified.
+    verified: bool,
+}
+
+/// Unlocks the data encryption key of a file with the specified key.
+/// # Arguments
+///
+/// * `header` - the header of the file.
+/// * `key` - the password and / or the key file or a recovery key of the file.
+///
+/// # Returns
+/// The data encryption key or an error if the key is known to be invalid.
+///
+/// # Remarks
+/// * With key slots each password slot is tried with the key; the recovery slots only if the password is formatted as a recovery key.
+/// * A legacy file can't be verified before its data is decrypted.
+fn unlock_key(header: &FileHeader, key: &CompositeKey) -> Result<UnlockedKey, anyhow::Error> {
+    match &header.protection {
+        KeyProtection::Legacy { salt } => {
+            let derived_key = KdfParams::default().derive_key(&key.kdf_input(), salt)?;
+
+            let mut data_key = Zeroizing::new([0u8; DATA_KEY_LENGTH]);
+            data_key.copy_from_slice(&derived_key[..DATA_KEY_LENGTH]);
+
+            Ok(UnlockedKey {
+                data_key,
+                unlocked_slot: None,
+                verified: false,
+            })
+        }
+        KeyProtection::Slots(slots) => {
+            let kdf_input = key.kdf_input();
+            let recovery_input = key.recovery_input();
+
+            for slot in slots {
+                let input = match slot.kind {
+                    KeySlotKind::Password => Some(&kdf_input),
+                    KeySlotKind::Recovery => recovery_input.as_ref(),
+                };
+
+                if let Some(input) = input {
+                    if let Some(data_key) = slot.unwrap_key(input)? {
+                        return Ok(UnlockedKey {
+                            data_key,
+                            unlocked_slot: Some(slot.id),
+                            verified: true,
+                        });
+                    }
+                }
+            }
+
+            Err(DecryptionError::InvalidPassword.into())
+        }
+    }
+}
+
+/// Creates a reader decrypting and decompressing the data following the header of a container file.
+/// # Arguments
+///
+/// * `header` - the header of the file.
+/// * `header_bytes` - the header as it was read from the file.
+/// * `unlocked` - the data encryption key of the file.
+/// * `reader` - the reader positioned right after the header.
+///
+/// # Returns
+/// The reader over the decrypted contents or an error.
+///
+/// # Remarks
+/// * Data encrypted at once is read and authenticated before the reader is returned.
+/// * Chunked data is authenticated chunk by chunk as it is read, so the contents read before an error must be discarded.
+fn open_payload<'a>(
+    header: &FileHeader,
+    header_bytes: &[u8],
+    unlocked: &UnlockedKey,
+    mut reader: Box<dyn Read + 'a>,
+) -> Result<Box<dyn Read + 'a>, anyhow::Error> {
+    let associated_data = header.associated_data(header_bytes);
+
+    let decrypted: Box<dyn Read + 'a> = if header.chunk_size > 0 {
+        Box::new(ChunkReader::new(
+            reader,
+            header,
+            associated_data,
+            &unlocked.data_key,
+        ))
+    } else {
+        let mut data_buff = Vec::new();
+        (&mut reader)
+            .take(header.data_length.saturating_add(1))
+            .read_to_end(&mut data_buff)?;
+
+        let length = data_buff.len() as u64;
+        if length < header.data_length {
+            return Err(anyhow!("Error reading saved data."));
+        }
+
+        if length > header.data_length {
+            let unexpected = 1 + io::copy(&mut reader, &mut io::sink())?;
+            return Err(anyhow!(
+                "The file has {} unexpected bytes after the encrypted data.",
+                unexpected
+            ));
+        }
+
+        let payload = Payload {
+            msg: &data_buff,
+            aad: associated_data,
+        };
+
+        let content = header
+            .cipher
+            .decrypt(unlocked.data_key.as_ref(), &header.nonce, payload)
+            .ok_or(if unlocked.verified {
+                DecryptionError::HeaderTampered
+            } else {
+                DecryptionError::InvalidPassword
+            })?;
+
+        Box::new(io::Cursor::new(content))
+    };
+
+    header.compression.decoder(decrypted)
+}
+
+/// Opens the specified container file for reading its decrypted contents.
+/// # Arguments
+///
+/// * `file_path` - the file to open.
+/// * `key` - the password and / or the key file or a recovery key to use for decryption.
+///
+/// # Returns
+/// The reader over the decrypted contents or an error.
+fn open_file(file_path: &str, key: &CompositeKey) -> Result<Box<dyn Read>, anyhow::Error> {
+    let mut file = File::open(file_path)?;
+
+    let (header, header_bytes) = read_file_header(&mut file)?;
+
+    let unlocked = unlock_key(&header, key)?;
+
+    open_payload(
+        &header,
+        &header_bytes,
+        &unlocked,
+        Box::new(BufReader::new(file)),
+    )
+}
+
+/// Checks the amount of key slots a file can be written with.
+fn validate_slot_count(slots: &[KeySlot]) -> Result<(), anyhow::Error> {
+    if slots.is_empty() {
+        return Err(anyhow!("A file must have at least one key slot."));
+    }
+
+    if slots.len() > MAX_KEY_SLOTS {
+        return Err(anyhow!(
+            "A file can have at most {} key slots.",
+            MAX_KEY_SLOTS
+        ));
+    }
+
+    Ok(())
+}
+
+/// Encrypts the specified content at once into the current container format.
+/// # Arguments
+///
+/// * `slots` - the key slots wrapping the data encryption key.
+/// * `data_key` - the data encryption key.
+/// * `cipher` - the cipher suite to encrypt the contents with.
+/// * `compression` - the compression to apply to the contents before the encryption.
+/// * `content` - the contents to encrypt.
+///
+/// # Returns
+/// The container file contents or an error.
+///
+/// # Remarks
+/// * A fresh nonce is generated for every call.
+fn seal(
+    slots: Vec<KeySlot>,
+    data_key: &[u8; DATA_KEY_LENGTH],
+    cipher: CipherSuite,
+    compression: Compression,
+    content: &[u8],
+) -> Result<Vec<u8>, anyhow::Error> {
+    validate_slot_count(&slots)?;
+
+    let mut compressed = Zeroizing::new(Vec::new());
+    let mut writer = CompressWriter::new(compression, &mut *compressed)?;
+    writer.write_all(content)?;
+    writer.finish()?;
+
+    let header = FileHeader {
+        version: FORMAT_VERSION,
+        cipher,
+        compression,
+        chunk_size: 0,
+        protection: KeyProtection::Slots(slots),
+        nonce: cipher.new_nonce(),
+        data_length: (compressed.len() + TAG_LENGTH).try_into()?,
+    };
+
+    let mut result = header.to_bytes()?;
+    let payload = Payload {
+        msg: &compressed,
+        aad: header.associated_data(&result),
+    };
+
+    let encrypted_file = cipher.encrypt(data_key, &header.nonce, payload)?;
+
+    result.extend_from_slice(&encrypted_file);
+
+    Ok(result)
+}
+
+/// Encrypts the contents of the specified reader into the current container format.
+/// # Arguments
+///
+/// * `output` - the writer to write the container file into.
+/// * `slots` - the key slots wrapping the data encryption key.
+/// * `data_key` - the data encryption key.
+/// * `cipher` - the cipher suite to encrypt the contents with.
+/// * `compression` - the compression to apply to the contents before the encryption.
+/// * `chunked` - a value indicating whether to encrypt the contents in chunks instead of at once.
+/// * `input` - the reader over the contents to encrypt.
+///
+/// # Remarks
+/// * The chunked encryption streams the contents with a memory use bounded by the chunk size.
+fn write_container(
+    output: &mut dyn Write,
+    slots: Vec<KeySlot>,
+    data_key: &[u8; DATA_KEY_LENGTH],
+    cipher: CipherSuite,
+    compression: Compression,
+    chunked: bool,
+    input: &mut dyn Read,
+) -> Result<(), anyhow::Error> {
+    if !chunked {
+        let content = read_to_end_zeroizing(input, u64::MAX).map_err(decryption_error)?;
+        output.write_all(&seal(slots, data_key, cipher, compression, &content)?)?;
+        return Ok(());
+    }
+
+    validate_slot_count(&slots)?;
+
+    let mut nonce = cipher.new_nonce();
+    nonce.truncate(nonce.len() - CHUNK_NONCE_SUFFIX_LENGTH);
+
+    let header = FileHeader {
+        version: FORMAT_VERSION,
+        cipher,
+        compression,
+        chunk_size: CHUNK_SIZE,
+        protection: KeyProtection::Slots(slots),
+        nonce,
+        data_length: 0,
+    };
+
+    let header_bytes = header.to_bytes()?;
+    output.write_all(&header_bytes)?;
+
+    let chunk_writer = ChunkWriter::new(&mut *output, &header, &header_bytes, data_key);
+    let mut writer = CompressWriter::new(compression, chunk_writer)?;
+    copy_zeroizing(input, &mut writer)?;
+    writer.finish()?.finish()?;
+
+    Ok(())
+}
+
+/// Creates a new random data encryption key.
+fn new_data_key() -> Zeroizing<[u8; DATA_KEY_LENGTH]> {
+    let mut result = Zeroizing::new([0u8; DATA_KEY_LENGTH]);
+    OsRng.fill_bytes(result.as_mut());
+    result
+}
+
+/// Gets the smallest key slot identifier not used by the specified slots.
+fn free_slot_id(slots: &[KeySlot]) -> Result<u8, anyhow::Error> {
+    if slots.len() >= MAX_KEY_SLOTS {
+        return Err(anyhow!(
+            "A file can have at most {} key slots.",
+            MAX_KEY_SLOTS
+        ));
+    }
+
+    (0..=u8::MAX)
+        .find(|id| slots.iter().all(|slot| slot.id != *id))
+        .ok_or_else(|| anyhow!("No free key slot identifier."))
+}
+
+/// Replaces the data encryption key of the specified key slots with a new random key.
+/// # Arguments
+///
+/// * `slots` - the key slots to seal the new data encryption key to.
+///
+/// # Returns
+/// The new data encryption key.
+fn rotate_data_key(
+    slots: &mut [KeySlot],
+) -> Result<Zeroizing<[u8; DATA_KEY_LENGTH]>, anyhow::Error> {
+    let data_key = new_data_key();
+    for slot in slots.iter_mut() {
+        slot.seal_key(data_key.as_ref())?;
+    }
+
+    Ok(data_key)
+}
+
+/// The key slots of an unlocked file, its data encryption key and the identifier of the unlocked key slot.
+type SlotKeys = (Vec<KeySlot>, Zeroizing<[u8; DATA_KEY_LENGTH]>, Option<u8>);
+
+/// Takes the key slots of an unlocked file, converting a legacy file into one with a single password slot.
+/// # Arguments
+///
+/// * `header` - the header of the file.
+/// * `unlocked` - the data encryption key the file was unlocked with.
+/// * `key` - the key the file was unlocked with.
+///
+/// # Returns
+/// The key slots, the data encryption key and the identifier of the unlocked key slot.
+fn take_slots(
+    header: &mut FileHeader,
+    unlocked: &UnlockedKey,
+    key: &CompositeKey,
+) -> Result<SlotKeys, anyhow::Error> {
+    match std::mem::replace(&mut header.protection, KeyProtection::Slots(Vec::new())) {
+        KeyProtection::Slots(slots) => {
+            Ok((slots, unlocked.data_key.clone(), unlocked.unlocked_slot))
+        }
+        KeyProtection::Legacy { .. } => {
+            let data_key = new_data_key();
+            let slot = KeySlot::new(
+                0,
+                KeySlotKind::Password,
+                "",
+                KdfParams::default(),
+                &key.kdf_input(),
+                data_key.as_ref(),
+            )?;
+            Ok((vec![slot], data_key, Some(0)))
+        }
+    }
+}
+
+/// Rewrites the specified file with modified key slots keeping its contents.
+/// # Arguments
+///
+/// * `file_path` - the file to rewrite.
+/// * `key` - a password and / or key file or a recovery key of the file.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+/// * `rotate_key` - a value indicating whether to re-encrypt the contents with a new data encryption key after the key slots are modified.
+/// * `modify` - the function modifying the key slots; it gets the key slots, the data encryption key and the identifier of the unlocked key slot.
+///
+/// # Returns
+/// The value returned by the `modify` function.
+///
+/// # Remarks
+/// * A legacy file is converted into a file with key slots.
+/// * The contents are streamed from the file into a temporary file which then replaces the file atomically.
+/// * The previous version of the file is backed up like when saving, see [`encrypt_file_stream`].
+fn rewrite_slots<T>(
+    file_path: &str,
+    key: &CompositeKey,
+    backup_count: u32,
+    rotate_key: bool,
+    modify: impl FnOnce(
+        &mut Vec<KeySlot>,
+        &[u8; DATA_KEY_LENGTH],
+        Option<u8>,
+    ) -> Result<T, anyhow::Error>,
+) -> Result<T, anyhow::Error> {
+    let mut file = File::open(file_path)?;
+    let (mut header, header_bytes) = read_file_header(&mut file)?;
+
+    let unlocked = unlock_key(&header, key)?;
+    let content = open_payload(
+        &header,
+        &header_bytes,
+        &unlocked,
+        Box::new(BufReader::new(file)),
+    )?;
+
+    let (mut slots, mut data_key, unlocked_slot) = take_slots(&mut header, &unlocked, key)?;
+    let result = modify(&mut slots, &data_key, unlocked_slot)?;
+
+    if rotate_key {
+        data_key = rotate_data_key(&mut slots)?;
+    }
+
+    rotate_backups(file_path, backup_count)?;
+
+    replace_file_atomic(file_path, move |output| {
+        // The source file is closed before the rename as an open file can't be replaced on all platforms.
+        let mut content = content;
+        write_container(
+            output,
+            slots,
+            &data_key,
+            header.cipher,
+            header.compression,
+            header.chunk_size > 0,
+            &mut content,
+        )
+    })?;
+
+    Ok(result)
+}
+
+/// Replaces the specified file with new contents so that the file is either fully replaced or left untouched.
+/// # Arguments
+///
+/// * `file_path` - the file to replace.
+/// * `write` - the function writing the new contents of the file.
+///
+/// # Remarks
+/// * The contents are written into a temporary file in the same directory which is flushed to the disk and then renamed over the target file.
+pub fn replace_file_atomic(
+    file_path: &str,
+    write: impl FnOnce(&mut File) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    let path = Path::new(file_path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file name: {}", file_path))?;
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{:016x}.tmp", OsRng.next_u64()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = create_synced(&temp_path, write).and_then(|_| Ok(fs::rename(&temp_path, path)?));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    sync_parent_directory(path);
+
+    result
+}
+
+/// Flushes the directory entry changes of the specified file's directory to the disk.
+///
+/// # Remarks
+/// * This is done on a best effort basis as not all platforms support opening a directory as a file.
+fn sync_parent_directory(path: &Path) {
+    #[cfg(unix)]
+    if let Some(directory) = path.parent() {
+        let directory = if directory.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            directory
+        };
+
+        if let Ok(directory) = File::open(directory) {
+            let _ = directory.sync_all();
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Gets the path of the specified backup of a file.
+/// # Arguments
+///
+/// * `file_path` - the file the backup is for.
+/// * `index` - the one-based index of the backup; 1 is the most recent one.
+///
+/// # Returns
+/// The backup file path in format `<file_path>.bak.<index>`.
+fn backup_path(file_path: &str, index: u32) -> String {
+    format!("{}.bak.{}", file_path, index)
+}
+
+/// Copies the current version of a file into a backup and rotates the older backups.
+/// # Arguments
+///
+/// * `file_path` - the file to back up.
+/// * `backup_count` - the maximum amount of backups to keep. 0 disables the backups.
+///
+/// # Remarks
+/// * The most recent backup is `<file_path>.bak.1` and the oldest one `<file_path>.bak.<backup_count>`.
+/// * The backups are copies of the encrypted file so they stay encrypted.
+fn rotate_backups(file_path: &str, backup_count: u32) -> Result<(), anyhow::Error> {
+    if backup_count == 0 || !Path::new(file_path).exists() {
+        return Ok(());
+    }
 
-// Based on a code by Sylvain Kerkour: https://github.com/skerkour/kerkour.com/tree/main/blog/2021/rust_file_encryption
-// Based on a code by Sylvain Kerkour: https://github.com/skerkour/kerkour.com/tree/main/blog/2022/rust_file_encryption_with_password
+    let oldest = backup_path(file_path, backup_count);
+    if Path::new(&oldest).exists() {
+        fs::remove_file(&oldest)?;
+    }
+
+    for index in (1..backup_count).rev() {
+        let backup = backup_path(file_path, index);
+        if Path::new(&backup).exists() {
+            fs::rename(&backup, backup_path(file_path, index + 1))?;
+        }
+    }
+
+    fs::copy(file_path, backup_path(file_path, 1))?;
+
+    Ok(())
+}
+
+/// Writes the specified data into a new file and flushes it to the disk.
+/// # Arguments
+///
+/// * `path` - the file to create; the file must not exist.
+/// * `data` - the contents of the file.
+fn write_synced(path: &Path, data: &[u8]) -> Result<(), anyhow::Error> {
+    create_synced(path, |file| Ok(file.write_all(data)?))
+}
+
+/// Creates a new file, writes its contents and flushes it to the disk.
+/// # Arguments
+///
+/// * `path` - the file to create; the file must not exist.
+/// * `write` - the function writing the contents of the file.
+fn create_synced(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    write(&mut file)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Reads the header of the specified container file contents.
+/// # Arguments
+///
+/// * `reader` - the reader over the file contents.
+///
+/// # Returns
+/// The parsed header of either a versioned or a legacy container file.
+fn read_header(reader: &mut ByteReader) -> Result<FileHeader, anyhow::Error> {
+    if reader.data.starts_with(&MAGIC) {
+        FileHeader::parse(reader)
+    } else {
+        FileHeader::parse_legacy(reader)
+    }
+}
+
+/// Reads the header of the specified container file.
+/// # Arguments
+///
+/// * `file` - the file to read; the file is left positioned right after the header.
+///
+/// # Returns
+/// The parsed header of either a versioned or a legacy container file along with the header bytes.
+fn read_file_header(file: &mut File) -> Result<(FileHeader, Vec<u8>), anyhow::Error> {
+    let mut data = Vec::new();
+    (&mut *file)
+        .take(MAX_HEADER_LENGTH)
+        .read_to_end(&mut data)?;
+
+    let mut reader = ByteReader::new(&data);
+    let header = read_header(&mut reader)?;
+    let header_bytes = reader.consumed().to_vec();
+
+    file.seek(SeekFrom::Start(header_bytes.len() as u64))?;
+
+    Ok((header, header_bytes))
+}
+
+/// The result of checking the container structure of a file.
+pub struct ContainerCheck {
+    /// The container format version of the file or `None` if the header couldn't be read.
+    pub format_version: Option<u16>,
+    /// A value indicating whether the header was read and its checksum matched.
+    pub structure_valid: bool,
+    /// A value indicating whether the length of the encrypted data in the header matches the file size.
+    pub length_consistent: bool,
+    /// A value indicating whether the key unlocked the file and the authentication tag of the data matched.
+    pub authenticated: bool,
+    /// A value indicating whether the key was rejected as an invalid password.
+    pub invalid_password: bool,
+    /// The problems found in the file.
+    pub errors: Vec<String>,
+    /// The decrypted contents of the file if the file was authenticated and the contents didn't exceed the limit.
+    pub content: Option<Zeroizing<Vec<u8>>>,
+}
+
+/// Checks the container structure, the length consistency and the authentication of the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to check.
+/// * `key` - the password and / or the key file or a recovery key of the file.
+/// * `content_limit` - the maximum size of the decrypted contents to return in the result.
+///
+/// # Returns
+/// The result of the check. The problems are reported in the result instead of an error.
+///
+/// # Remarks
+/// * The authentication is checked only if the structure and the length of the file are valid.
+/// * The file is streamed, so a chunked file is authenticated chunk by chunk with a memory use bounded by the chunk size.
+///   The decrypted contents are kept in memory only up to the `content_limit`.
+pub fn check_file(file_path: &str, key: &CompositeKey, content_limit: u64) -> ContainerCheck {
+    let mut result = ContainerCheck {
+        format_version: None,
+        structure_valid: false,
+        length_consistent: false,
+        authenticated: false,
+        invalid_password: false,
+        errors: Vec::new(),
+        content: None,
+    };
+
+    let header = File::open(file_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut file| {
+            let file_length = file.metadata()?.len();
+            let (header, header_bytes) = read_file_header(&mut file)?;
+            Ok((file, file_length, header, header_bytes))
+        });
+
+    let (file, file_length, header, header_bytes) = match header {
+        Ok(v) => v,
+        Err(e) => {
+            result.errors.push(e.to_string());
+            return result;
+        }
+    };
+
+    result.format_version = Some(header.version);
+    result.structure_valid = true;
+
+    let remaining = file_length.saturating_sub(header_bytes.len() as u64);
+    if header.chunk_size > 0 {
+        // Every chunk but the last one is full; the last one has at least the authentication tag.
+        let chunk_length = header.chunk_size as u64 + TAG_LENGTH as u64;
+        if remaining > 0
+            && (remaining.is_multiple_of(chunk_length)
+                || remaining % chunk_length >= TAG_LENGTH as u64)
+        {
+            result.length_consistent = true;
+        } else {
+            result.errors.push(format!(
+                "The file is truncated: {} bytes of chunked encrypted data is not a valid length.",
+                remaining
+            ));
+        }
+    } else {
+        match remaining.cmp(&header.data_length) {
+            std::cmp::Ordering::Less => result.errors.push(format!(
+                "The file is truncated: {} bytes of encrypted data expected, {} found.",
+                header.data_length, remaining
+            )),
+            std::cmp::Ordering::Greater => result.errors.push(format!(
+                "The file has {} unexpected bytes after the encrypted data.",
+                remaining - header.data_length
+            )),
+            std::cmp::Ordering::Equal => result.length_consistent = true,
+        }
+    }
+
+    if !result.length_consistent {
+        return result;
+    }
+
+    let mut content = BoundedBuffer {
+        data: Some(Zeroizing::new(Vec::new())),
+        limit: content_limit,
+    };
+
+    let checked = unlock_key(&header, key).and_then(|unlocked| {
+        let mut reader = open_payload(
+            &header,
+            &header_bytes,
+            &unlocked,
+            Box::new(BufReader::new(file)),
+        )?;
+        copy_zeroizing(&mut reader, &mut content)
+    });
+
+    match checked {
+        Ok(()) => {
+            result.authenticated = true;
+            result.content = content.data;
+        }
+        Err(e) => {
+            result.invalid_password =
+                e.downcast_ref::<DecryptionError>() == Some(&DecryptionError::InvalidPassword);
+            result.errors.push(e.to_string());
+        }
+    }
+
+    result
+}
 
-/// Encrypts a specified file content string to a specified file name with specified password.
+/// Encrypts a specified file content string to a specified file name with specified key.
 /// # Arguments
 ///
 /// * `file_name` - the file name to save the data in encrypted form.
-/// * `password` - the password to use for encryption.
+/// * `key` - the password and / or the key file to use for encryption.
 /// * `content` - the contents to save into the file in encrypted form.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+/// * `options` - the key derivation parameters, the cipher suite, the compression and the chunked encryption to use.
 ///
 /// # Remarks
-/// * The data is encrypted using [Aes256GcmSiv](https://en.wikipedia.org/wiki/AES-GCM-SIV) algorithm with [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function.
-/// * If the file already exists it is overridden.
+/// * See [`encrypt_file_stream`] for the details of the encryption.
 pub fn encrypt_small_file(
     file_path: &str,
-    password: &str,
+    key: &CompositeKey,
     content: &str,
+    backup_count: u32,
+    options: &SaveOptions,
 ) -> Result<(), anyhow::Error> {
-    let argon2_config = argon2_config();
+    encrypt_file_stream(
+        file_path,
+        key,
+        &mut content.as_bytes(),
+        backup_count,
+        options,
+    )
+}
 
-    let mut salt = [0u8; 32];
-    let mut nonce = [0u8; 12];
-    OsRng.fill_bytes(&mut salt);
-    OsRng.fill_bytes(&mut nonce);
+/// Encrypts the contents of a specified reader to a specified file name with specified key.
+/// # Arguments
+///
+/// * `file_name` - the file name to save the data in encrypted form.
+/// * `key` - the password and / or the key file to use for encryption.
+/// * `input` - the reader over the contents to save into the file in encrypted form.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+/// * `options` - the key derivation parameters, the cipher suite, the compression and the chunked encryption to use.
+///
+/// # Remarks
+/// * The data is encrypted using the selected cipher suite, [Aes256GcmSiv](https://en.wikipedia.org/wiki/AES-GCM-SIV) by default, with a random data encryption key wrapped into key slots with [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function.
+/// * If the file already exists and the key unlocks it, the data encryption key and the key slots of the file are kept. If the key doesn't unlock it or its header can't be read, an error is returned unless [`SaveOptions::overwrite`] is set.
+/// * A new file or a replaced one gets a single password slot.
+/// * The key derivation parameters are stored per key slot in the file header; a new file uses the [`KdfParams::default`] parameters unless specified.
+/// * The header is authenticated as associated data of the cipher.
+/// * With the chunked encryption the data is encrypted in 64 KiB chunks so it is never held in memory as a whole; a new file is encrypted at once unless specified.
+/// * If the file already exists it is replaced atomically after the previous version is copied into a backup.
+pub fn encrypt_file_stream(
+    file_path: &str,
+    key: &CompositeKey,
+    input: &mut dyn Read,
+    backup_count: u32,
+    options: &SaveOptions,
+) -> Result<(), anyhow::Error> {
+    let SaveOptions {
+        kdf,
+        cipher,
+        compression,
+        chunked,
+        overwrite,
+    } = *options;
 
-    let mut key = argon2::hash_raw(password.as_bytes(), &salt, &argon2_config)?;
+    let header = match File::open(file_path) {
+        Ok(mut file) => match read_file_header(&mut file) {
+            Ok(header) => Some(header),
+            Err(_) if overwrite => None,
+            Err(e) => {
+                return Err(e.context("The existing file is not a readable container file."));
+            }
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
 
-    let pass = GenericArray::clone_from_slice(&key[..32]);
+    let existing = match header {
+        Some((mut header, _)) => match unlock_key(&header, key) {
+            Ok(unlocked) => {
+                let slot_keys = take_slots(&mut header, &unlocked, key)?;
+                Some((
+                    slot_keys,
+                    header.cipher,
+                    header.compression,
+                    header.chunk_size > 0,
+                ))
+            }
+            Err(_) if overwrite => None,
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
 
-    let cipher = Aes256GcmSiv::new(&pass);
+    let (slots, data_key, cipher, compression, chunked) = match existing {
+        Some((
+            (mut slots, data_key, unlocked_slot),
+            file_cipher,
+            file_compression,
+            file_chunked,
+        )) => {
+            // Re-wrap the data key of the password slot the file was unlocked with if the parameters changed.
+            if let Some(kdf) = kdf {
+                let index = slots.iter().position(|slot| {
+                    Some(slot.id) == unlocked_slot
+                        && slot.kind == KeySlotKind::Password
+                        && slot.kdf != kdf
+                });
 
-    let encrypted_file = cipher
-        .encrypt(nonce.as_ref().into(), content.as_bytes())
-        .map_err(|err| anyhow!("Encrypting small file: {}", err))?;
-    let len: u64 = encrypted_file.len().try_into().unwrap();
+                if let Some(index) = index {
+                    let slot = &slots[index];
+                    slots[index] = KeySlot::new(
+                        slot.id,
+                        slot.kind,
+                        &slot.label.clone(),
+                        kdf,
+                        &key.kdf_input(),
+                        data_key.as_ref(),
+                    )?;
+                }
+            }
 
-    let mut file = File::create(file_path)?;
-    file.write(&salt)?;
-    file.write(&nonce)?;
-    file.write(&len.to_le_bytes())?;
-    file.write(&encrypted_file)?;
+            (
+                slots,
+                data_key,
+                cipher.unwrap_or(file_cipher),
+                compression.unwrap_or(file_compression),
+                chunked.unwrap_or(file_chunked),
+            )
+        }
+        None => {
+            let data_key = new_data_key();
+            let slot = KeySlot::new(
+                0,
+                KeySlotKind::Password,
+                "",
+                kdf.unwrap_or_default(),
+                &key.kdf_input(),
+                data_key.as_ref(),
+            )?;
+            (
+                vec![slot],
+                data_key,
+                cipher.unwrap_or_default(),
+                compression.unwrap_or_default(),
+                chunked.unwrap_or_default(),
+            )
+        }
+    };
 
-    salt.zeroize();
-    nonce.zeroize();
-    key.zeroize();
+    rotate_backups(file_path, backup_count)?;
 
-    Ok(())
+    replace_file_atomic(file_path, |output| {
+        write_container(
+            output,
+            slots,
+            &data_key,
+            cipher,
+            compression,
+            chunked,
+            input,
+        )
+    })
 }
 
-/// Decrypts a specified file content to a string using the specified password.
+/// Decrypts a specified file content to a string using the specified key.
 /// # Arguments
 ///
 /// * `file_name` - the file to decrypt the data from.
-/// * `password` - the password to use for encryption.
+/// * `key` - the password and / or the key file or a recovery key to use for decryption.
 ///
 /// # Returns
 /// A string with the file contents decrypted or an error.
 ///
 /// # Remarks
-/// * The data is decrypted using [Aes256GcmSiv](https://en.wikipedia.org/wiki/AES-GCM-SIV) algorithm with [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function.
-pub fn decrypt_small_file(file_path: &str, password: &str) -> Result<String, anyhow::Error> {
-    let mut salt = [0u8; 32];
-    let mut nonce = [0u8; 12];
-    let mut size_data = [0u8; 8];
+/// * The data is decrypted using the cipher suite recorded in the file header with [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function.
+/// * Both the versioned container format and the legacy `salt | nonce | length | data` layout are supported.
+/// * An invalid password and a modified or corrupted file are reported with the matching [`DecryptionError`]; see its remarks for what can be told apart.
+pub fn decrypt_small_file(file_path: &str, key: &CompositeKey) -> Result<String, anyhow::Error> {
+    let mut reader = open_file(file_path, key)?;
 
-    let mut file = File::open(file_path)?;
+    let content =
+        read_to_end_zeroizing(&mut reader, MAX_DECOMPRESSED_SIZE).map_err(decryption_error)?;
+
+    let result = str::from_utf8(&content)?;
+
+    Ok(result.to_string())
+}
+
+/// Decrypts a specified file content into a specified writer using the specified key.
+/// # Arguments
+///
+/// * `file_name` - the file to decrypt the data from.
+/// * `key` - the password and / or the key file or a recovery key to use for decryption.
+/// * `output` - the writer to write the decrypted contents into.
+///
+/// # Remarks
+/// * A chunked file is decrypted chunk by chunk with a memory use bounded by the chunk size.
+/// * Each chunk is authenticated before it is written, but a truncated or modified file is detected only when the affected chunk is reached, so the output must be discarded on an error.
+pub fn decrypt_file_stream(
+    file_path: &str,
+    key: &CompositeKey,
+    output: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let mut reader = open_file(file_path, key)?;
+
+    copy_zeroizing(&mut reader, output)?;
+
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Changes the password of the specified file without the decrypted contents leaving the backend.
+/// # Arguments
+///
+/// * `file_path` - the file to change the password of.
+/// * `old_key` - the current password and / or key file of the file or a recovery key.
+/// * `new_key` - the new password and / or key file for the file.
+/// * `new_kdf` - the new key derivation parameters or `None` to keep the current ones.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+///
+/// # Remarks
+/// * Only the key slot unlocked with the old key is replaced; the other key slots keep working.
+/// * A recovery key slot used as the old key is replaced with a password slot so the recovery key can be used only once.
+/// * The contents are re-encrypted with a new data encryption key sealed to the remaining key slots, so a copy of the old key slot can't decrypt the new version of the file.
+/// * The previous version of the file is backed up and the existing backups are not rewritten; they can still be opened with the old password until they are rotated out or deleted.
+/// * The file is replaced atomically so an interrupted operation leaves the original file intact.
+pub fn change_file_password(
+    file_path: &str,
+    old_key: &CompositeKey,
+    new_key: &CompositeKey,
+    new_kdf: Option<KdfParams>,
+    backup_count: u32,
+) -> Result<(), anyhow::Error> {
+    rewrite_slots(
+        file_path,
+        old_key,
+        backup_count,
+        true,
+        |slots, data_key, unlocked_slot| {
+            let index = slots
+                .iter()
+                .position(|slot| Some(slot.id) == unlocked_slot)
+                .ok_or_else(|| anyhow!("The unlocked key slot was not found."))?;
+
+            let slot = &slots[index];
+            slots[index] = KeySlot::new(
+                slot.id,
+                KeySlotKind::Password,
+                &slot.label.clone(),
+                new_kdf.unwrap_or(slot.kdf),
+                &new_key.kdf_input(),
+                data_key,
+            )?;
+
+            Ok(())
+        },
+    )
+}
+
+/// Adds a new password key slot into the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to add the key slot into.
+/// * `key` - an existing password and / or key file of the file.
+/// * `new_key` - the password and / or key file for the new key slot.
+/// * `label` - a label for the key slot, e.g. the name of the person the slot belongs to.
+/// * `kdf` - the key derivation parameters of the new key slot or `None` for the defaults.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+///
+/// # Returns
+/// The identifier of the new key slot.
+///
+/// # Remarks
+/// * A legacy file is converted into a file with key slots.
+pub fn add_key_slot(
+    file_path: &str,
+    key: &CompositeKey,
+    new_key: &CompositeKey,
+    label: &str,
+    kdf: Option<KdfParams>,
+    backup_count: u32,
+) -> Result<u8, anyhow::Error> {
+    add_slot(
+        file_path,
+        key,
+        KeySlotKind::Password,
+        label,
+        kdf.unwrap_or_default(),
+        &new_key.kdf_input(),
+        backup_count,
+    )
+}
+
+/// Adds a new recovery key slot into the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to add the key slot into.
+/// * `key` - an existing password and / or key file of the file.
+/// * `label` - a label for the key slot.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+///
+/// # Returns
+/// The generated recovery key. The key is not stored anywhere so it must be printed or written down by the user.
+///
+/// # Remarks
+/// * The recovery key is entered as the password to open the file. Changing the password with the recovery key replaces the recovery key slot.
+pub fn add_recovery_key(
+    file_path: &str,
+    key: &CompositeKey,
+    label: &str,
+    backup_count: u32,
+) -> Result<Zeroizing<String>, anyhow::Error> {
+    let recovery_key = generate_recovery_key();
+    let kdf_input = normalize_recovery_key(recovery_key.as_bytes())
+        .ok_or_else(|| anyhow!("Invalid recovery key generated."))?;
+
+    add_slot(
+        file_path,
+        key,
+        KeySlotKind::Recovery,
+        label,
+        KdfParams::default(),
+        &kdf_input,
+        backup_count,
+    )?;
+
+    Ok(recovery_key)
+}
+
+/// Adds a new key slot into the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to add the key slot into.
+/// * `key` - an existing password and / or key file of the file.
+/// * `kind` - the kind of the new key slot.
+/// * `label` - a label for the key slot.
+/// * `kdf` - the key derivation parameters of the new key slot.
+/// * `kdf_input` - the key derivation input of the new key slot.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+///
+/// # Returns
+/// The identifier of the new key slot.
+fn add_slot(
+    file_path: &str,
+    key: &CompositeKey,
+    kind: KeySlotKind,
+    label: &str,
+    kdf: KdfParams,
+    kdf_input: &[u8],
+    backup_count: u32,
+) -> Result<u8, anyhow::Error> {
+    rewrite_slots(file_path, key, backup_count, false, |slots, data_key, _| {
+        let id = free_slot_id(slots)?;
+        slots.push(KeySlot::new(id, kind, label, kdf, kdf_input, data_key)?);
+        Ok(id)
+    })
+}
+
+/// Lists the key slots of the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to list the key slots of.
+///
+/// # Returns
+/// The public information of the key slots.
+///
+/// # Remarks
+/// * No key is needed as the key slot information is stored unencrypted in the file header.
+/// * A legacy file is reported as having a single password slot.
+pub fn list_key_slots(file_path: &str) -> Result<Vec<KeySlotInfo>, anyhow::Error> {
+    let file_data = fs::read(file_path)?;
+    let header = read_header(&mut ByteReader::new(&file_data))?;
+
+    match &header.protection {
+        KeyProtection::Slots(slots) => Ok(slots.iter().map(KeySlot::info).collect()),
+        KeyProtection::Legacy { .. } => Ok(vec![KeySlotInfo {
+            id: 0,
+            kind: KeySlotKind::Password,
+            label: String::new(),
+            kdf: KdfParams::default(),
+        }]),
+    }
+}
+
+/// Revokes a key slot of the specified file.
+/// # Arguments
+///
+/// * `file_path` - the file to revoke the key slot from.
+/// * `key` - a password and / or key file of any key slot of the file.
+/// * `slot_id` - the identifier of the key slot to revoke.
+/// * `backup_count` - the amount of rotating backups of the previous file versions to keep. 0 disables the backups.
+///
+/// # Remarks
+/// * The last key slot of a file can't be revoked.
+/// * The contents are re-encrypted with a new data encryption key sealed to the remaining key slots, so the revoked key can't decrypt the new version of the file even with a saved copy of its key slot.
+/// * The previous version of the file is backed up and the existing backups are not rewritten; the revoked key can still open them until they are rotated out or deleted.
+pub fn revoke_key_slot(
+    file_path: &str,
+    key: &CompositeKey,
+    slot_id: u8,
+    backup_count: u32,
+) -> Result<(), anyhow::Error> {
+    rewrite_slots(file_path, key, backup_count, true, |slots, _, _| {
+        let index = slots
+            .iter()
+            .position(|slot| slot.id == slot_id)
+            .ok_or_else(|| anyhow!("Key slot {} was not found.", slot_id))?;
+
+        if slots.len() == 1 {
+            return Err(anyhow!("The last key slot of a file can't be revoked."));
+        }
+
+        slots.remove(index);
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary directory removed with its contents on drop.
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        /// Creates a new temporary directory unique to the test.
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "password_keeper_{}_{}_{:08x}",
+                name,
+                std::process::id(),
+                OsRng.next_u32()
+            ));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        /// Gets the path of the specified file in the directory.
+        fn file(&self, name: &str) -> String {
+            self.0.join(name).to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
 
-    let mut read_count = file.read(&mut salt)?;
-    if read_count != salt.len() {
-        return Err(anyhow!("Error reading salt."));
+    /// Gets key derivation parameters fast enough for the tests.
+    fn fast_kdf() -> KdfParams {
+        KdfParams {
+            variant: KdfVariant::Argon2id,
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        }
     }
 
-    read_count = file.read(&mut nonce)?;
-    if read_count != nonce.len() {
-        return Err(anyhow!("Error reading nonce."));
+    /// Gets save options with the fast key derivation parameters.
+    fn fast_options() -> SaveOptions {
+        SaveOptions {
+            kdf: Some(fast_kdf()),
+            ..SaveOptions::default()
+        }
     }
 
-    read_count = file.read(&mut size_data)?;
-    if read_count != size_data.len() {
-        return Err(anyhow!("Error reading saved data size."));
+    /// Creates a composite key of the specified password.
+    fn key(password: &str) -> CompositeKey {
+        CompositeKey::new(password, None).unwrap()
     }
 
-    let len: usize = u64::from_le_bytes(size_data).try_into().unwrap();
+    /// Reads the header of the specified file and unlocks its data encryption key.
+    fn data_key(file_path: &str, key: &CompositeKey) -> [u8; DATA_KEY_LENGTH] {
+        let (header, _) = read_file_header(&mut File::open(file_path).unwrap()).unwrap();
+        *unlock_key(&header, key).unwrap().data_key
+    }
 
-    let argon2_config = argon2_config();
+    /// Encrypts the specified contents into a legacy file without a versioned header.
+    fn legacy_file(password: &str, content: &[u8]) -> Vec<u8> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = CipherSuite::Aes256GcmSiv;
+        let nonce = cipher.new_nonce();
+        let data_key = KdfParams::default()
+            .derive_key(&key(password).kdf_input(), &salt)
+            .unwrap();
 
-    let mut key = argon2::hash_raw(password.as_bytes(), &salt, &argon2_config)?;
+        let mut result = Vec::new();
+        result.extend_from_slice(&salt);
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&((content.len() + TAG_LENGTH) as u64).to_le_bytes());
 
-    let mut data_buff = vec![0u8; len];
+        let payload = Payload {
+            msg: content,
+            aad: &[],
+        };
+        let encrypted = cipher.encrypt(&data_key, &nonce, payload).unwrap();
+        result.extend_from_slice(&encrypted);
+        result
+    }
 
-    let pass = GenericArray::clone_from_slice(&key[..32]);
+    /// Gets the decryption error of the specified file.
+    fn decryption_failure(file_path: &str, password: &str) -> Option<DecryptionError> {
+        decrypt_small_file(file_path, &key(password))
+            .unwrap_err()
+            .downcast_ref::<DecryptionError>()
+            .copied()
+    }
 
-    let cipher = Aes256GcmSiv::new(&pass);
+    /// Gets the length of the header of the specified file.
+    fn header_length(file_path: &str) -> usize {
+        read_file_header(&mut File::open(file_path).unwrap())
+            .unwrap()
+            .1
+            .len()
+    }
 
-    file.read(&mut data_buff)?;
+    /// Creates test contents of the specified length.
+    fn test_data(length: usize) -> Vec<u8> {
+        (0..length).map(|f| (f * 7 % 251) as u8).collect()
+    }
 
-    let decrypted_data = cipher
-        .decrypt(nonce.as_ref().into(), data_buff.as_slice())
-        .map_err(|err| anyhow!("Decrypting small file: {}", err))?;
+    /// Decrypts the specified file with the streaming decryption.
+    fn decrypt_stream(file_path: &str, password: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let mut result = Vec::new();
+        decrypt_file_stream(file_path, &key(password), &mut result)?;
+        Ok(result)
+    }
 
-    let result = str::from_utf8(&decrypted_data)?;
+    #[test]
+    fn changing_password_replaces_data_key() {
+        let dir = TempDir::new("change");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+        add_key_slot(&file_path, &key("a"), &key("b"), "b", Some(fast_kdf()), 0).unwrap();
+        let old_data_key = data_key(&file_path, &key("a"));
 
-    salt.zeroize();
-    nonce.zeroize();
-    key.zeroize();
+        change_file_password(&file_path, &key("a"), &key("c"), None, 0).unwrap();
 
-    Ok(result.to_string())
-}
+        let new_data_key = data_key(&file_path, &key("c"));
+        assert_ne!(new_data_key, old_data_key);
+        assert_eq!(data_key(&file_path, &key("b")), new_data_key);
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("b")).unwrap(),
+            "content"
+        );
+        let error = decrypt_small_file(&file_path, &key("a")).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<DecryptionError>(),
+            Some(&DecryptionError::InvalidPassword)
+        );
+    }
 
-/// Creates an [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function configuration.
-/// # Returns
-/// An [Argon2](https://en.wikipedia.org/wiki/Argon2) / Argon2id key derivation function configuration.
-fn argon2_config<'a>() -> argon2::Config<'a> {
-    return argon2::Config {
-        variant: argon2::Variant::Argon2id,
-        hash_length: 32,
-        lanes: 8,
-        mem_cost: 16 * 1024,
-        time_cost: 8,
-        ..Default::default()
-    };
+    #[test]
+    fn revoking_slot_replaces_data_key() {
+        let dir = TempDir::new("revoke");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+        let slot_id =
+            add_key_slot(&file_path, &key("a"), &key("b"), "b", Some(fast_kdf()), 0).unwrap();
+        let revoked_data_key = data_key(&file_path, &key("b"));
+
+        revoke_key_slot(&file_path, &key("a"), slot_id, 0).unwrap();
+
+        assert_ne!(data_key(&file_path, &key("a")), revoked_data_key);
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("a")).unwrap(),
+            "content"
+        );
+        assert!(decrypt_small_file(&file_path, &key("b")).is_err());
+    }
+
+    #[test]
+    fn round_trip_with_each_cipher_compression_and_chunking() {
+        let dir = TempDir::new("round_trip");
+        let content = "A line of the file contents.\n".repeat(5000);
+
+        for cipher in [CipherSuite::Aes256GcmSiv, CipherSuite::XChaCha20Poly1305] {
+            for compression in [Compression::None, Compression::Zstd, Compression::Deflate] {
+                for chunked in [false, true] {
+                    let file_path =
+                        dir.file(&format!("{:?}_{:?}_{}.pkd", cipher, compression, chunked));
+                    let options = SaveOptions {
+                        cipher: Some(cipher),
+                        compression: Some(compression),
+                        chunked: Some(chunked),
+                        ..fast_options()
+                    };
+
+                    encrypt_small_file(&file_path, &key("a"), &content, 0, &options).unwrap();
+
+                    assert_eq!(decrypt_small_file(&file_path, &key("a")).unwrap(), content);
+                    let check = check_file(&file_path, &key("a"), u64::MAX);
+                    assert!(check.authenticated, "{:?}", check.errors);
+                    assert_eq!(check.format_version, Some(FORMAT_VERSION));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_round_trip_at_chunk_boundaries() {
+        let dir = TempDir::new("chunk_boundaries");
+        let file_path = dir.file("vault.pkd");
+        let chunk_size = CHUNK_SIZE as usize;
+
+        for length in [0, 1, chunk_size, 2 * chunk_size, 2 * chunk_size + 1] {
+            let data = test_data(length);
+            let options = SaveOptions {
+                chunked: Some(true),
+                ..fast_options()
+            };
+
+            encrypt_file_stream(&file_path, &key("a"), &mut data.as_slice(), 0, &options).unwrap();
+
+            assert_eq!(decrypt_stream(&file_path, "a").unwrap(), data);
+            let check = check_file(&file_path, &key("a"), u64::MAX);
+            assert!(
+                check.length_consistent && check.authenticated,
+                "{:?}",
+                check.errors
+            );
+        }
+    }
+
+    #[test]
+    fn sample_file_is_decrypted() {
+        let file_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../Sample/Sample File.pkd");
+
+        let json = decrypt_small_file(file_path, &key("abc")).unwrap();
+
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+        assert_eq!(
+            check_file(file_path, &key("abc"), u64::MAX).format_version,
+            Some(0)
+        );
+        assert_eq!(
+            decryption_failure(file_path, "wrong"),
+            Some(DecryptionError::InvalidPassword)
+        );
+    }
+
+    #[test]
+    fn legacy_file_is_converted_when_saved() {
+        let dir = TempDir::new("legacy");
+        let file_path = dir.file("vault.pkd");
+        let content = "The file contents.";
+        fs::write(&file_path, legacy_file("a", content.as_bytes())).unwrap();
+
+        assert_eq!(decrypt_small_file(&file_path, &key("a")).unwrap(), content);
+        assert_eq!(
+            check_file(&file_path, &key("a"), u64::MAX).format_version,
+            Some(0)
+        );
+        assert_eq!(
+            decryption_failure(&file_path, "b"),
+            Some(DecryptionError::InvalidPassword)
+        );
+
+        encrypt_small_file(&file_path, &key("a"), content, 0, &fast_options()).unwrap();
+        assert_eq!(decrypt_small_file(&file_path, &key("a")).unwrap(), content);
+        assert_eq!(
+            check_file(&file_path, &key("a"), u64::MAX).format_version,
+            Some(FORMAT_VERSION)
+        );
+    }
+
+    #[test]
+    fn tampered_header_is_reported() {
+        let dir = TempDir::new("tamper");
+        let file_path = dir.file("vault.pkd");
+        let tampered_path = dir.file("tampered.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+        add_key_slot(
+            &file_path,
+            &key("a"),
+            &key("b"),
+            "label",
+            Some(fast_kdf()),
+            0,
+        )
+        .unwrap();
+        let data = fs::read(&file_path).unwrap();
+        let header_length = header_length(&file_path);
+
+        // The chunk size, a slot id, the label, the key derivation values, the salt, the sealed key, the data length and the checksum.
+        for index in [
+            13,
+            17,
+            21,
+            27,
+            40,
+            120,
+            header_length - 40,
+            header_length - 1,
+        ] {
+            let mut tampered = data.clone();
+            tampered[index] ^= 1;
+            fs::write(&tampered_path, tampered).unwrap();
+
+            assert_eq!(
+                decryption_failure(&tampered_path, "a"),
+                Some(DecryptionError::HeaderTampered),
+                "byte {}",
+                index
+            );
+        }
+
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        fs::write(&tampered_path, tampered).unwrap();
+        assert_eq!(
+            decryption_failure(&tampered_path, "a"),
+            Some(DecryptionError::HeaderTampered)
+        );
+
+        assert_eq!(
+            decryption_failure(&file_path, "c"),
+            Some(DecryptionError::InvalidPassword)
+        );
+    }
+
+    #[test]
+    fn backups_are_rotated() {
+        let dir = TempDir::new("backups");
+        let file_path = dir.file("vault.pkd");
+
+        for content in ["first", "second", "third", "fourth"] {
+            encrypt_small_file(&file_path, &key("a"), content, 2, &fast_options()).unwrap();
+        }
+
+        assert_eq!(decrypt_small_file(&file_path, &key("a")).unwrap(), "fourth");
+        assert_eq!(
+            decrypt_small_file(&backup_path(&file_path, 1), &key("a")).unwrap(),
+            "third"
+        );
+        assert_eq!(
+            decrypt_small_file(&backup_path(&file_path, 2), &key("a")).unwrap(),
+            "second"
+        );
+        assert!(!Path::new(&backup_path(&file_path, 3)).exists());
+    }
+
+    #[test]
+    fn key_file_is_combined_with_password() {
+        let dir = TempDir::new("key_file");
+        let file_path = dir.file("vault.pkd");
+        let key_file = dir.file("vault.key");
+        write_key_file(&key_file).unwrap();
+        // An existing key file is not replaced.
+        assert!(write_key_file(&key_file).is_err());
+
+        let composite = CompositeKey::new("a", Some(&key_file)).unwrap();
+        encrypt_small_file(&file_path, &composite, "content", 0, &fast_options()).unwrap();
+
+        assert_eq!(
+            decrypt_small_file(&file_path, &composite).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::InvalidPassword)
+        );
+        let key_file_only = CompositeKey::new("", Some(&key_file)).unwrap();
+        assert!(decrypt_small_file(&file_path, &key_file_only).is_err());
+    }
+
+    #[test]
+    fn key_slots_can_be_added_and_revoked() {
+        let dir = TempDir::new("slots");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+
+        let slot_id =
+            add_key_slot(&file_path, &key("a"), &key("b"), "Bob", Some(fast_kdf()), 0).unwrap();
+
+        let slots = list_key_slots(&file_path).unwrap();
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[1].id, slot_id);
+        assert_eq!(slots[1].label, "Bob");
+        assert_eq!(slots[1].kind, KeySlotKind::Password);
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("a")).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("b")).unwrap(),
+            "content"
+        );
+
+        assert!(revoke_key_slot(&file_path, &key("b"), 42, 0).is_err());
+        revoke_key_slot(&file_path, &key("b"), slots[0].id, 0).unwrap();
+
+        assert_eq!(list_key_slots(&file_path).unwrap().len(), 1);
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("b")).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::InvalidPassword)
+        );
+
+        let error = revoke_key_slot(&file_path, &key("b"), slot_id, 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The last key slot of a file can't be revoked."
+        );
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("b")).unwrap(),
+            "content"
+        );
+    }
+
+    #[test]
+    fn recovery_key_unlocks_and_is_replaced() {
+        let dir = TempDir::new("recovery");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+
+        let recovery_key = add_recovery_key(&file_path, &key("a"), "Recovery", 0).unwrap();
+
+        assert_eq!(
+            list_key_slots(&file_path).unwrap()[1].kind,
+            KeySlotKind::Recovery
+        );
+        assert_eq!(
+            decrypt_small_file(&file_path, &key(&recovery_key)).unwrap(),
+            "content"
+        );
+        // The recovery key is accepted without the separators and in lower case.
+        let typed = recovery_key.replace('-', "").to_lowercase();
+        assert_eq!(
+            decrypt_small_file(&file_path, &key(&typed)).unwrap(),
+            "content"
+        );
+
+        change_file_password(
+            &file_path,
+            &key(&recovery_key),
+            &key("c"),
+            Some(fast_kdf()),
+            0,
+        )
+        .unwrap();
+
+        assert!(list_key_slots(&file_path)
+            .unwrap()
+            .iter()
+            .all(|f| f.kind == KeySlotKind::Password));
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("c")).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("a")).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decryption_failure(&file_path, &recovery_key),
+            Some(DecryptionError::InvalidPassword)
+        );
+    }
+
+    /// Writes a chunked file of three and a half chunks and gets its contents, the header length and the encrypted chunk length.
+    fn chunked_file(file_path: &str) -> (Vec<u8>, usize, usize) {
+        let data = test_data(CHUNK_SIZE as usize * 7 / 2);
+        let options = SaveOptions {
+            chunked: Some(true),
+            ..fast_options()
+        };
+        encrypt_file_stream(file_path, &key("a"), &mut data.as_slice(), 0, &options).unwrap();
+
+        (
+            fs::read(file_path).unwrap(),
+            header_length(file_path),
+            CHUNK_SIZE as usize + TAG_LENGTH,
+        )
+    }
+
+    #[test]
+    fn truncated_chunked_file_is_rejected() {
+        let dir = TempDir::new("truncated");
+        let file_path = dir.file("vault.pkd");
+        let (data, header_length, chunk_length) = chunked_file(&file_path);
+
+        // Without the final chunk, within the final chunk and within a full chunk.
+        for length in [
+            header_length + 3 * chunk_length,
+            data.len() - 1,
+            header_length + chunk_length + 10,
+        ] {
+            fs::write(&file_path, &data[..length]).unwrap();
+
+            assert!(
+                decrypt_stream(&file_path, "a").is_err(),
+                "length {}",
+                length
+            );
+            assert!(decrypt_small_file(&file_path, &key("a")).is_err());
+            assert!(!check_file(&file_path, &key("a"), u64::MAX).authenticated);
+        }
+    }
+
+    #[test]
+    fn reordered_chunks_are_rejected() {
+        let dir = TempDir::new("reordered");
+        let file_path = dir.file("vault.pkd");
+        let (data, header_length, chunk_length) = chunked_file(&file_path);
+
+        let mut reordered = data[..header_length].to_vec();
+        reordered.extend_from_slice(
+            &data[header_length + chunk_length..header_length + 2 * chunk_length],
+        );
+        reordered.extend_from_slice(&data[header_length..header_length + chunk_length]);
+        reordered.extend_from_slice(&data[header_length + 2 * chunk_length..]);
+        fs::write(&file_path, reordered).unwrap();
+
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::HeaderTampered)
+        );
+    }
+
+    #[test]
+    fn final_chunk_flag_is_authenticated() {
+        let dir = TempDir::new("final_chunk");
+        let file_path = dir.file("vault.pkd");
+        let (data, header_length, chunk_length) = chunked_file(&file_path);
+
+        // Data appended after the final chunk makes the final chunk read as a non-final one.
+        let mut appended = data.clone();
+        appended.extend_from_slice(&[1, 2, 3]);
+        fs::write(&file_path, appended).unwrap();
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::HeaderTampered)
+        );
+
+        // A non-final chunk can't be made the final one by dropping the chunks after it.
+        fs::write(&file_path, &data[..header_length + 2 * chunk_length]).unwrap();
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::HeaderTampered)
+        );
+    }
+
+    #[test]
+    fn existing_file_is_not_replaced_with_another_key() {
+        let dir = TempDir::new("overwrite");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+
+        let error =
+            encrypt_small_file(&file_path, &key("b"), "other", 0, &fast_options()).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<DecryptionError>(),
+            Some(&DecryptionError::InvalidPassword)
+        );
+        assert_eq!(
+            decrypt_small_file(&file_path, &key("a")).unwrap(),
+            "content"
+        );
+
+        let options = SaveOptions {
+            overwrite: true,
+            ..fast_options()
+        };
+        encrypt_small_file(&file_path, &key("b"), "other", 0, &options).unwrap();
+
+        assert_eq!(decrypt_small_file(&file_path, &key("b")).unwrap(), "other");
+        assert_eq!(list_key_slots(&file_path).unwrap().len(), 1);
+
+        // A file with an unreadable header is kept as well.
+        fs::write(&file_path, b"not a container").unwrap();
+        assert!(encrypt_small_file(&file_path, &key("b"), "other", 0, &fast_options()).is_err());
+        assert_eq!(fs::read(&file_path).unwrap(), b"not a container");
+        encrypt_small_file(&file_path, &key("b"), "other", 0, &options).unwrap();
+        assert_eq!(decrypt_small_file(&file_path, &key("b")).unwrap(), "other");
+    }
+
+    #[test]
+    fn key_slot_changes_are_backed_up() {
+        let dir = TempDir::new("slot_backups");
+        let file_path = dir.file("vault.pkd");
+        encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+
+        let slot_id =
+            add_key_slot(&file_path, &key("a"), &key("b"), "b", Some(fast_kdf()), 2).unwrap();
+        change_file_password(&file_path, &key("a"), &key("c"), None, 2).unwrap();
+        revoke_key_slot(&file_path, &key("c"), slot_id, 2).unwrap();
+
+        assert_eq!(
+            list_key_slots(&backup_path(&file_path, 1)).unwrap().len(),
+            2
+        );
+        assert_eq!(
+            decrypt_small_file(&backup_path(&file_path, 1), &key("c")).unwrap(),
+            "content"
+        );
+        assert_eq!(
+            decrypt_small_file(&backup_path(&file_path, 2), &key("a")).unwrap(),
+            "content"
+        );
+        assert!(!Path::new(&backup_path(&file_path, 3)).exists());
+        assert_eq!(list_key_slots(&file_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn check_streams_contents_over_the_limit() {
+        let dir = TempDir::new("check_limit");
+        let file_path = dir.file("vault.pkd");
+        let (data, header_length, chunk_length) = chunked_file(&file_path);
+
+        let check = check_file(&file_path, &key("a"), CHUNK_SIZE as u64);
+        assert!(check.authenticated, "{:?}", check.errors);
+        assert!(check.content.is_none());
+
+        let check = check_file(&file_path, &key("a"), u64::MAX);
+        assert_eq!(
+            check.content.unwrap().as_slice(),
+            test_data(CHUNK_SIZE as usize * 7 / 2).as_slice()
+        );
+
+        // A modified chunk after the limit is still detected.
+        let mut tampered = data.clone();
+        tampered[header_length + 2 * chunk_length + 5] ^= 1;
+        fs::write(&file_path, tampered).unwrap();
+        let check = check_file(&file_path, &key("a"), CHUNK_SIZE as u64);
+        assert!(check.length_consistent);
+        assert!(!check.authenticated);
+        assert!(!check.invalid_password);
+    }
+
+    /// Modifies a byte of the header of the specified file and recomputes the header checksum.
+    fn modify_header(file_path: &str, index: usize) {
+        let header_length = header_length(file_path);
+        let mut data = fs::read(file_path).unwrap();
+        data[index] ^= 1;
+        let checksum = Sha256::digest(&data[..header_length - DIGEST_LENGTH]);
+        data[header_length - DIGEST_LENGTH..header_length].copy_from_slice(&checksum);
+        fs::write(file_path, data).unwrap();
+    }
+
+    #[test]
+    fn modified_header_with_recomputed_checksum() {
+        let dir = TempDir::new("recomputed_checksum");
+        let file_path = dir.file("vault.pkd");
+        let write = || {
+            let _ = fs::remove_file(&file_path);
+            encrypt_small_file(&file_path, &key("a"), "content", 0, &fast_options()).unwrap();
+        };
+
+        // A modified salt of the only key slot can't be told apart from an invalid password.
+        write();
+        modify_header(&file_path, 33);
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::InvalidPassword)
+        );
+
+        // The data nonce is authenticated once the key slot is unlocked.
+        write();
+        let nonce = header_length(&file_path) - DIGEST_LENGTH - 8 - 1;
+        modify_header(&file_path, nonce);
+        assert_eq!(
+            decryption_failure(&file_path, "a"),
+            Some(DecryptionError::HeaderTampered)
+        );
+    }
 }
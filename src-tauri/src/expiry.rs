@@ -0,0 +1,158 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashSet;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::file_data::{parse_date, DataEntry};
+
+/// The default amount of days before the expiry an entry is reported as expiring soon.
+pub const EXPIRY_WARNING_DAYS: u32 = 14;
+
+/// The amount of seconds in a day.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The reasons for an entry to expire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryReason {
+    /// The expiry date of the entry.
+    Date,
+    /// The rotation interval of the password since it was last changed.
+    Rotation,
+}
+
+/// An entry expired or expiring soon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiryInfo {
+    /// The identifier of the entry.
+    pub id: i64,
+    /// The name of the entry.
+    pub name: String,
+    /// The time the entry expires as seconds since the Unix epoch.
+    pub expires: u64,
+    /// The reason for the expiry.
+    pub reason: ExpiryReason,
+}
+
+/// The entries of a vault expired or expiring soon, both the earliest expiry first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiryReport {
+    /// The expired entries.
+    pub expired: Vec<ExpiryInfo>,
+    /// The entries expiring within the warning period.
+    pub expiring_soon: Vec<ExpiryInfo>,
+}
+
+/// Gets the time the specified entry expires.
+/// # Arguments
+///
+/// * `entry` - the entry.
+///
+/// # Returns
+/// The earlier of the expiry date and the end of the rotation interval as seconds since the Unix epoch with its reason;
+/// `None` if the entry doesn't expire.
+pub fn entry_expiry(entry: &DataEntry) -> Option<(u64, ExpiryReason)> {
+    let date = entry.expiry_date.as_deref().and_then(parse_date).map(|f| {
+        let time = f.midnight().assume_utc().unix_timestamp().max(0) as u64;
+        (time, ExpiryReason::Date)
+    });
+
+    let rotation = entry
+        .rotation_days
+        .zip(entry.password_changed)
+        .map(|(days, changed)| {
+            let time = changed.saturating_add(u64::from(days) * SECONDS_PER_DAY);
+            (time, ExpiryReason::Rotation)
+        });
+
+    match (date, rotation) {
+        (Some(date), Some(rotation)) => Some(if rotation.0 < date.0 { rotation } else { date }),
+        (date, rotation) => date.or(rotation),
+    }
+}
+
+/// Reports the expired entries and the entries expiring soon.
+/// # Arguments
+///
+/// * `entries` - the entries and the categories of the vault.
+/// * `now` - the current time as seconds since the Unix epoch.
+/// * `warning_days` - the amount of days before the expiry an entry is reported as expiring soon.
+pub fn expiry_report(entries: &[DataEntry], now: u64, warning_days: u32) -> ExpiryReport {
+    let warning_end = now.saturating_add(u64::from(warning_days) * SECONDS_PER_DAY);
+    let mut report = ExpiryReport::default();
+
+    for entry in entries.iter().filter(|f| !f.is_category()) {
+        let (expires, reason) = match entry_expiry(entry) {
+            Some(expiry) => expiry,
+            None => continue,
+        };
+
+        let info = ExpiryInfo {
+            id: entry.id,
+            name: entry.name.clone(),
+            expires,
+            reason,
+        };
+
+        if expires <= now {
+            report.expired.push(info);
+        } else if expires <= warning_end {
+            report.expiring_soon.push(info);
+        }
+    }
+
+    report.expired.sort_by_key(|f| f.expires);
+    report.expiring_soon.sort_by_key(|f| f.expires);
+
+    report
+}
+
+/// Finds the entries expired since they were last checked.
+/// # Arguments
+///
+/// * `entries` - the entries and the categories of the vault.
+/// * `now` - the current time as seconds since the Unix epoch.
+/// * `notified` - the identifiers of the expired entries already reported; the newly expired entries are added into it.
+///
+/// # Returns
+/// The newly expired entries.
+pub fn newly_expired(
+    entries: &[DataEntry],
+    now: u64,
+    notified: &mut HashSet<i64>,
+) -> Vec<ExpiryInfo> {
+    let expired = expiry_report(entries, now, 0).expired;
+
+    // An entry whose expiry was postponed is reported again when it expires.
+    notified.retain(|id| expired.iter().any(|f| f.id == *id));
+
+    expired
+        .into_iter()
+        .filter(|f| notified.insert(f.id))
+        .collect()
+}
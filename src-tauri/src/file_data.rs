@@ -0,0 +1,792 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::anyhow;
+use serde_derive::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::generator::PasswordProfile;
+use crate::history::{record_revision, EntryRevision};
+
+/// The parent identifier of a category.
+pub const CATEGORY_PARENT_ID: i64 = -1;
+
+/// The identifier of the category for the entries without a category.
+pub const UNCATEGORIZED_ID: i64 = -1000;
+
+/// The name of the category for the entries without a category.
+pub const UNCATEGORIZED_NAME: &str = "#NO_CATEGORY#";
+
+/// The separator of the tags in [`DataEntry::tags`].
+pub const TAG_SEPARATOR: char = '|';
+
+/// The file data, entries, metadata and file global options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileData {
+    /// The password entries and categories.
+    pub entries: Vec<DataEntry>,
+    /// The metadata information, e.g. The tags used for the entries in the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_data: Option<Vec<GeneralEntry<String>>>,
+    /// Global file options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_options: Option<FileOptions>,
+    /// The version of the file; 0 for the files saved before the versioned format.
+    #[serde(default)]
+    pub version: u32,
+    /// The deleted entries and categories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash: Option<Vec<TrashItem>>,
+    /// The fields not known to this version, e.g. written by a newer version; these are saved back unchanged.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The entry / category data format for the program.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataEntry {
+    /// The name of the entry or a category.
+    pub name: String,
+    /// The optional domain for the login credentials.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// The host address where the login information is to be used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// The user name for the credentials.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+    /// The password for the login credentials.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Additional notes for the login information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// An unique identifier for an entry or a category.
+    pub id: i64,
+    /// In case of an entry the parent category for the entry. Otherwise -1.
+    pub parent_id: i64,
+    /// The key <--> URL for OTP authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp_auth_key: Option<String>,
+    /// The tags assigned for the item. These are separated with the `|` character.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    /// A value indicating whether to use markdown for the [`DataEntry::notes`] rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_markdown: Option<bool>,
+    /// A value indicating whether to use monospaced font for the [`DataEntry::notes`] rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_monospaced_font: Option<bool>,
+    /// The additional user-defined fields of the entry in their display order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<CustomField>>,
+    /// The date the entry expires on in the `YYYY-MM-DD` format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<String>,
+    /// The amount of days after which the password should be changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_days: Option<u32>,
+    /// The time the password was last changed as seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_changed: Option<u64>,
+    /// The files attached to the entry. The contents are stored encrypted in the blob store next to the vault file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>,
+    /// The previous versions of the entry, the oldest first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<EntryRevision>>,
+    /// The fields not known to this version; these are saved back unchanged.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A file attached to an entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// The identifier of the attachment within the entry.
+    pub id: i64,
+    /// The file name of the attachment.
+    pub name: String,
+    /// The size of the attachment in bytes.
+    pub size: u64,
+    /// The SHA-256 hash of the contents; the attachments with the same contents share a blob.
+    pub hash: String,
+    /// The name of the encrypted blob holding the contents.
+    pub blob: String,
+    /// The random key the blob is encrypted with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The time the attachment was added as seconds since the Unix epoch.
+    pub added: u64,
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// The types of the [`CustomField`] values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    /// A plain text value.
+    #[default]
+    Text,
+    /// A secret value which is hidden, excluded from the searches and from the exports unless asked.
+    Protected,
+    /// An URL.
+    Url,
+    /// An email address.
+    Email,
+    /// A date in the `YYYY-MM-DD` format.
+    Date,
+    /// A decimal number.
+    Number,
+}
+
+/// An additional user-defined field of an entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomField {
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field value.
+    #[serde(rename = "type", default)]
+    pub field_type: CustomFieldType,
+    /// The value of the field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl Drop for CustomField {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl CustomField {
+    /// Gets a value indicating whether the field is protected.
+    pub fn is_protected(&self) -> bool {
+        self.field_type == CustomFieldType::Protected
+    }
+
+    /// Validates the value of the field against the field type.
+    /// # Returns
+    /// `Ok(())` if the value is empty or valid for the type; otherwise an error.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let value = self.value.as_deref().unwrap_or_default().trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        let valid = match self.field_type {
+            CustomFieldType::Text | CustomFieldType::Protected => true,
+            CustomFieldType::Url => value.split_once("://").is_some_and(|(scheme, rest)| {
+                !scheme.is_empty()
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                    && !rest.is_empty()
+            }),
+            CustomFieldType::Email => value.split_once('@').is_some_and(|(user, domain)| {
+                !user.is_empty() && !domain.is_empty() && !domain.contains('@')
+            }),
+            CustomFieldType::Date => parse_date(value).is_some(),
+            CustomFieldType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+        };
+
+        if !valid {
+            return Err(anyhow!(
+                "The value of field {} is not a valid {:?} value.",
+                self.name,
+                self.field_type
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a date in the `YYYY-MM-DD` format.
+/// # Returns
+/// The date or `None` if the value is not a valid date.
+pub fn parse_date(value: &str) -> Option<time::Date> {
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return None;
+    }
+
+    let month = time::Month::try_from(parts[1].parse::<u8>().ok()?).ok()?;
+    time::Date::from_calendar_date(parts[0].parse().ok()?, month, parts[2].parse().ok()?).ok()
+}
+
+/// A deleted entry or category kept in the recycle bin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    /// An unique identifier for the item in the recycle bin.
+    pub id: i64,
+    /// The time of the deletion as seconds since the Unix epoch.
+    pub deleted: u64,
+    /// The deleted entry or category.
+    pub entry: DataEntry,
+    /// In case of a category the entries the category was removed from as their first tag.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_ids: Vec<i64>,
+}
+
+impl Drop for DataEntry {
+    fn drop(&mut self) {
+        self.password.zeroize();
+        self.notes.zeroize();
+        self.otp_auth_key.zeroize();
+    }
+}
+
+/// The types of the [`GeneralEntry`] metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneralEntryType {
+    /// The tags used for the entries in the file.
+    Tags,
+}
+
+/// An additional generic metadata to save along the password items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneralEntry<T> {
+    /// The type of the metadata.
+    #[serde(rename = "type")]
+    pub entry_type: GeneralEntryType,
+    /// The values in the metadata.
+    pub values: Vec<T>,
+}
+
+/// The values of the CSS `font-style` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontStyle {
+    /// The `normal` font style.
+    Normal,
+    /// The `italic` font style.
+    Italic,
+    /// The `oblique` font style.
+    Oblique,
+    /// The `initial` value of the property.
+    Initial,
+    /// The value inherited from the parent element.
+    Inherit,
+}
+
+/// CSS font properties.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CssFont {
+    /// The `font-size` CSS property value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<String>,
+    /// The `font-family` CSS property value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    /// The `font-weight` CSS property value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_weight: Option<String>,
+    /// The `font-style` CSS property value.
+    #[serde(rename = "FontStyle", default, skip_serializing_if = "Option::is_none")]
+    pub font_style: Option<FontStyle>,
+}
+
+/// File-level options for some entry styling, etc.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOptions {
+    /// A value indicating whether to use markdown by default on entry editing and rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_markdown_on_notes: Option<bool>,
+    /// An optional CSS style for the entry notes rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes_font: Option<CssFont>,
+    /// A value indicating whether to use monospaced font by default on entry editing and rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_monospaced_font: Option<bool>,
+    /// A value indicating whether to use HTML on entry editing and rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_html_on_notes: Option<bool>,
+    /// The amount of days the deleted entries are kept in the recycle bin before they are purged automatically.
+    /// Not specified keeps them until purged manually.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_retention_days: Option<u32>,
+    /// The named password generator policies of the file. These take precedence over the ones in the settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_profiles: Option<Vec<PasswordProfile>>,
+    /// The options not known to this version; these are saved back unchanged.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl DataEntry {
+    /// Creates a new category.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the category.
+    /// * `name` - the name of the category.
+    pub fn new_category(id: i64, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            domain: None,
+            address: None,
+            user_name: None,
+            password: None,
+            notes: None,
+            id,
+            parent_id: CATEGORY_PARENT_ID,
+            otp_auth_key: None,
+            tags: None,
+            use_markdown: None,
+            use_monospaced_font: None,
+            fields: None,
+            expiry_date: None,
+            rotation_days: None,
+            password_changed: None,
+            attachments: None,
+            history: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Gets a value indicating whether the entry is a category.
+    pub fn is_category(&self) -> bool {
+        self.parent_id == CATEGORY_PARENT_ID
+    }
+
+    /// Gets the text of the entry to search from in lower case.
+    /// # Arguments
+    ///
+    /// * `include_protected` - a value indicating whether to include the password, the OTP key and the protected fields.
+    pub fn search_text(&self, include_protected: bool) -> Zeroizing<String> {
+        let mut parts = vec![
+            Some(self.name.as_str()),
+            self.domain.as_deref(),
+            self.address.as_deref(),
+            self.user_name.as_deref(),
+            self.notes.as_deref(),
+            self.tags.as_deref(),
+        ];
+
+        if include_protected {
+            parts.push(self.password.as_deref());
+            parts.push(self.otp_auth_key.as_deref());
+        }
+
+        parts.extend(
+            self.fields
+                .iter()
+                .flatten()
+                .filter(|f| include_protected || !f.is_protected())
+                .map(|f| f.value.as_deref()),
+        );
+
+        Zeroizing::new(
+            parts
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase(),
+        )
+    }
+
+    /// Removes the values of the protected fields and the keys of the attachments.
+    pub fn strip_protected_fields(&mut self) {
+        for field in self
+            .fields
+            .iter_mut()
+            .flatten()
+            .filter(|f| f.is_protected())
+        {
+            field.value.zeroize();
+            field.value = None;
+        }
+
+        for attachment in self.attachments.iter_mut().flatten() {
+            attachment.key.zeroize();
+            attachment.key = None;
+        }
+    }
+
+    /// Gets the tags assigned for the entry.
+    pub fn tag_list(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .as_deref()
+            .filter(|tags| !tags.is_empty())
+            .into_iter()
+            .flat_map(|tags| tags.split(TAG_SEPARATOR))
+    }
+}
+
+impl FileData {
+    /// Parses the file data from the specified JSON.
+    /// # Arguments
+    ///
+    /// * `json` - the JSON data of the file in the `{ entries, metaData, dataOptions, version }` object format.
+    ///
+    /// # Returns
+    /// The parsed file data or an error if the JSON doesn't match the file data format.
+    ///
+    /// # Remarks
+    /// * The data is not validated; see [`FileData::validate`].
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        serde_json::from_str(json).map_err(|e| anyhow!("Invalid file data: {}", e))
+    }
+
+    /// Gets the tags metadata of the file.
+    pub fn tags(&self) -> Option<&GeneralEntry<String>> {
+        self.meta_data
+            .iter()
+            .flatten()
+            .find(|f| f.entry_type == GeneralEntryType::Tags)
+    }
+
+    /// Purges the items deleted longer ago than the retention time in [`FileOptions::trash_retention_days`] from the recycle bin.
+    /// # Arguments
+    ///
+    /// * `now` - the current time as seconds since the Unix epoch.
+    ///
+    /// # Returns
+    /// The amount of purged items.
+    pub fn purge_expired_trash(&mut self, now: u64) -> usize {
+        let retention_days = match self
+            .data_options
+            .as_ref()
+            .and_then(|f| f.trash_retention_days)
+        {
+            Some(days) => days,
+            None => return 0,
+        };
+
+        let oldest = now.saturating_sub(u64::from(retention_days) * 24 * 60 * 60);
+        let trash = match self.trash.as_mut() {
+            Some(trash) => trash,
+            None => return 0,
+        };
+
+        let count = trash.len();
+        trash.retain(|f| f.deleted >= oldest);
+        count - trash.len()
+    }
+
+    /// Keeps the recycle bin of the previously saved version of the file and moves the entries and categories missing from this version into it.
+    /// # Arguments
+    ///
+    /// * `previous` - the previously saved version of the file data.
+    /// * `now` - the current time as seconds since the Unix epoch.
+    ///
+    /// # Remarks
+    /// * This keeps the deletions made by a frontend saving the whole file data recoverable; the recycle bin of this version is replaced.
+    /// * The category for the entries without a category is not moved into the recycle bin as the UI doesn't store it.
+    pub fn merge_trash(&mut self, previous: &FileData, now: u64) {
+        let entry_ids: HashSet<i64> = self.entries.iter().map(|f| f.id).collect();
+        let mut trash = previous.trash.clone().unwrap_or_default();
+        let first_id = trash.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+        let deleted = previous
+            .entries
+            .iter()
+            .filter(|f| f.id != UNCATEGORIZED_ID && !entry_ids.contains(&f.id));
+
+        for (id, entry) in (first_id..).zip(deleted) {
+            let category_entry_ids = if entry.is_category() {
+                previous
+                    .entries
+                    .iter()
+                    .filter(|f| f.parent_id == entry.id && entry_ids.contains(&f.id))
+                    .map(|f| f.id)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            trash.push(TrashItem {
+                id,
+                deleted: now,
+                entry: entry.clone(),
+                entry_ids: category_entry_ids,
+            });
+        }
+
+        self.trash = if trash.is_empty() { None } else { Some(trash) };
+    }
+
+    /// Records the changes of the entries since the previously saved version of the file into their revision histories.
+    /// # Arguments
+    ///
+    /// * `previous` - the previously saved version of the file data.
+    ///
+    /// # Remarks
+    /// * This keeps the edits made by a frontend saving the whole file data restorable; the histories of this version are replaced
+    ///   with the histories of the previous version and the new revisions, see [`record_revision`].
+    /// * The entries not in the previous version have no history.
+    pub fn merge_history(&mut self, previous: &FileData) {
+        let previous_entries: HashMap<i64, &DataEntry> =
+            previous.entries.iter().map(|f| (f.id, f)).collect();
+
+        for entry in self.entries.iter_mut() {
+            match previous_entries.get(&entry.id) {
+                Some(previous) => record_revision(previous, entry),
+                None => entry.history = None,
+            }
+        }
+    }
+
+    /// Validates the consistency of the file data.
+    /// # Returns
+    /// `Ok(())` if the data is valid; otherwise an error describing the first problem found.
+    ///
+    /// # Remarks
+    /// * The entry and category identifiers must be unique.
+    /// * A category has the parent identifier -1 and an entry must belong to an existing category or to the uncategorized
+    ///   category [`UNCATEGORIZED_ID`], which the UI doesn't store as a category.
+    /// * The tags of an entry must not be empty and must be listed in the tags metadata if the file has one.
+    /// * The custom fields of an entry must have unique non-empty names and values valid for their types.
+    /// * The expiry date of an entry must be a valid date and the rotation interval must not be zero.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let mut ids = HashSet::new();
+        for entry in &self.entries {
+            if !ids.insert(entry.id) {
+                return Err(anyhow!("Duplicate entry id: {}", entry.id));
+            }
+        }
+
+        let category_ids: HashSet<i64> = self
+            .entries
+            .iter()
+            .filter(|f| f.is_category())
+            .map(|f| f.id)
+            .collect();
+
+        for entry in self.entries.iter().filter(|f| !f.is_category()) {
+            if entry.parent_id != UNCATEGORIZED_ID && !category_ids.contains(&entry.parent_id) {
+                return Err(anyhow!(
+                    "Entry {} has an invalid parentId: {}",
+                    entry.id,
+                    entry.parent_id
+                ));
+            }
+        }
+
+        let mut metadata_types = HashSet::new();
+        for metadata in self.meta_data.iter().flatten() {
+            if !metadata_types.insert(metadata.entry_type) {
+                return Err(anyhow!("Duplicate metadata: {:?}", metadata.entry_type));
+            }
+        }
+
+        let known_tags: Option<HashSet<&str>> = self
+            .tags()
+            .map(|tags| tags.values.iter().map(String::as_str).collect());
+
+        for entry in &self.entries {
+            for tag in entry.tag_list() {
+                if tag.is_empty() {
+                    return Err(anyhow!("Entry {} has an empty tag.", entry.id));
+                }
+
+                if known_tags
+                    .as_ref()
+                    .is_some_and(|known| !known.contains(tag))
+                {
+                    return Err(anyhow!(
+                        "Entry {} has a tag missing from the tags metadata: {}",
+                        entry.id,
+                        tag
+                    ));
+                }
+            }
+        }
+
+        for entry in &self.entries {
+            let mut names = HashSet::new();
+            for field in entry.fields.iter().flatten() {
+                if field.name.trim().is_empty() {
+                    return Err(anyhow!("Entry {} has a field without a name.", entry.id));
+                }
+
+                if !names.insert(field.name.as_str()) {
+                    return Err(anyhow!(
+                        "Entry {} has a duplicate field: {}",
+                        entry.id,
+                        field.name
+                    ));
+                }
+
+                field
+                    .validate()
+                    .map_err(|e| anyhow!("Entry {}: {}", entry.id, e))?;
+            }
+
+            if let Some(expiry_date) = &entry.expiry_date {
+                if parse_date(expiry_date).is_none() {
+                    return Err(anyhow!(
+                        "Entry {} has an invalid expiry date: {}",
+                        entry.id,
+                        expiry_date
+                    ));
+                }
+            }
+
+            if entry.rotation_days == Some(0) {
+                return Err(anyhow!("Entry {} has a zero rotation interval.", entry.id));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::migrate_file_data;
+
+    /// The data the way the UI saves it: the uncategorized entries point to the uncategorized category which is not stored.
+    const UI_JSON: &str = r#"{
+        "entries": [
+            { "id": 1, "parentId": -1, "name": "Mail" },
+            { "id": 2, "parentId": 1, "name": "Provider", "password": "secret", "userName": "user", "notes": "", "tags": "Mail" },
+            { "id": 3, "parentId": -1000, "name": "Untagged", "password": "", "userName": "", "notes": "", "tags": "" }
+        ],
+        "metaData": [{ "type": "tags", "values": ["Mail"] }],
+        "version": 1
+    }"#;
+
+    #[test]
+    fn ui_data_is_valid() {
+        FileData::from_json(UI_JSON).unwrap().validate().unwrap();
+
+        let file_data = migrate_file_data(UI_JSON).unwrap();
+        assert!(!file_data.entries.iter().any(|f| f.id == UNCATEGORIZED_ID));
+        file_data.validate().unwrap();
+    }
+
+    #[test]
+    fn ui_data_with_deleted_category_is_valid() {
+        // Deleting the "Mail" category in the UI moves its entries to the uncategorized category.
+        let json = UI_JSON
+            .replace(r#"{ "id": 1, "parentId": -1, "name": "Mail" },"#, "")
+            .replace(r#""parentId": 1,"#, r#""parentId": -1000,"#)
+            .replace(r#""tags": "Mail""#, r#""tags": """#)
+            .replace(r#""values": ["Mail"]"#, r#""values": []"#);
+
+        FileData::from_json(&json).unwrap().validate().unwrap();
+    }
+
+    #[test]
+    fn entry_without_category_is_rejected() {
+        let json = UI_JSON.replace(r#""parentId": 1,"#, r#""parentId": 42,"#);
+
+        let error = FileData::from_json(&json).unwrap().validate().unwrap_err();
+        assert_eq!(error.to_string(), "Entry 2 has an invalid parentId: 42");
+    }
+
+    #[test]
+    fn deleted_entries_are_moved_to_trash() {
+        let previous = migrate_file_data(UI_JSON).unwrap();
+        let json = UI_JSON.replace(
+            r#"{ "id": 3, "parentId": -1000, "name": "Untagged", "password": "", "userName": "", "notes": "", "tags": "" }"#,
+            r#"{ "id": 4, "parentId": -1000, "name": "New", "password": "", "userName": "", "notes": "", "tags": "" }"#,
+        );
+        let mut file_data = FileData::from_json(&json).unwrap();
+
+        file_data.merge_trash(&previous, 100);
+
+        let trash = file_data.trash.as_ref().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].entry.id, 3);
+        assert_eq!(trash[0].deleted, 100);
+
+        // The recycle bin of the previous version is kept on the next save.
+        let previous = file_data.clone();
+        let mut file_data = FileData::from_json(&json).unwrap();
+        file_data.merge_trash(&previous, 200);
+        assert_eq!(file_data.trash.as_ref().unwrap().len(), 1);
+        file_data.validate().unwrap();
+    }
+
+    #[test]
+    fn changed_entries_are_recorded_into_history() {
+        let previous = migrate_file_data(UI_JSON).unwrap();
+        let json = UI_JSON.replace(r#""password": "secret""#, r#""password": "changed""#);
+        let mut file_data = FileData::from_json(&json).unwrap();
+
+        file_data.merge_history(&previous);
+
+        let entry = file_data.entries.iter().find(|f| f.id == 2).unwrap();
+        let history = entry.history.as_ref().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].changes[0].old_value.as_deref(), Some("secret"));
+        assert!(file_data
+            .entries
+            .iter()
+            .find(|f| f.id == 3)
+            .unwrap()
+            .history
+            .is_none());
+
+        // The history of the previous version is kept on the next save without changes.
+        let previous = file_data.clone();
+        let mut file_data = FileData::from_json(&json).unwrap();
+        file_data.merge_history(&previous);
+        let entry = file_data.entries.iter().find(|f| f.id == 2).unwrap();
+        assert_eq!(entry.history.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let json = UI_JSON
+            .replace(r#""version": 1"#, r#""version": 1, "newOption": [1, 2]"#)
+            .replace(
+                r#""name": "Provider","#,
+                r#""name": "Provider", "newField": "value","#,
+            );
+
+        let file_data = FileData::from_json(&json).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&file_data).unwrap()).unwrap();
+
+        assert_eq!(saved["newOption"], serde_json::json!([1, 2]));
+        assert_eq!(saved["entries"][1]["newField"], "value");
+    }
+
+    #[test]
+    fn duplicate_id_is_rejected() {
+        let json = UI_JSON.replace(r#""id": 3,"#, r#""id": 2,"#);
+
+        assert!(FileData::from_json(&json).unwrap().validate().is_err());
+    }
+}
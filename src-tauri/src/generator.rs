@@ -0,0 +1,285 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use anyhow::anyhow;
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// The lower-case letters.
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// The upper-case letters.
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The digits.
+const DIGITS: &str = "0123456789";
+
+/// The default symbols.
+const DEFAULT_SYMBOLS: &str = "!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+/// The characters easily mistaken for each other.
+const LOOK_ALIKES: &str = "0O1lI|";
+
+/// The maximum length of a generated password.
+const MAX_LENGTH: u32 = 1024;
+
+/// The rules for generating a password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PasswordPolicy {
+    /// The length of the password.
+    pub length: u32,
+    /// A value indicating whether to use lower-case letters.
+    pub lowercase: bool,
+    /// A value indicating whether to use upper-case letters.
+    pub uppercase: bool,
+    /// A value indicating whether to use digits.
+    pub digits: bool,
+    /// A value indicating whether to use symbols.
+    pub symbols: bool,
+    /// The symbols to use instead of the default ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_set: Option<String>,
+    /// A value indicating whether to exclude the characters easily mistaken for each other, e.g. `0` and `O`.
+    pub exclude_look_alikes: bool,
+    /// The minimum amount of lower-case letters.
+    pub min_lowercase: u32,
+    /// The minimum amount of upper-case letters.
+    pub min_uppercase: u32,
+    /// The minimum amount of digits.
+    pub min_digits: u32,
+    /// The minimum amount of symbols.
+    pub min_symbols: u32,
+}
+
+/// The default password policy: 20 characters of all the character classes with at least one of each.
+impl ::std::default::Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            symbol_set: None,
+            exclude_look_alikes: false,
+            min_lowercase: 1,
+            min_uppercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+        }
+    }
+}
+
+/// A named password policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordProfile {
+    /// The name of the profile.
+    pub name: String,
+    /// The password policy of the profile.
+    pub policy: PasswordPolicy,
+}
+
+/// Finds a password profile by its name.
+/// # Arguments
+///
+/// * `profiles` - the profiles to search from.
+/// * `name` - the name of the profile.
+pub fn find_profile<'a>(
+    profiles: &'a [PasswordProfile],
+    name: &str,
+) -> Option<&'a PasswordProfile> {
+    profiles.iter().find(|f| f.name == name)
+}
+
+/// Generates a random password with the specified policy.
+/// # Arguments
+///
+/// * `policy` - the rules for the password.
+///
+/// # Returns
+/// The generated password or an error if the policy can't be satisfied.
+///
+/// # Remarks
+/// * The characters are picked with the operating system random number generator.
+/// * The minimum amounts of the character classes are picked first, the rest of the characters from all the enabled classes and the result is shuffled.
+pub fn generate_password(policy: &PasswordPolicy) -> Result<Zeroizing<String>, anyhow::Error> {
+    if policy.length == 0 || policy.length > MAX_LENGTH {
+        return Err(anyhow!(
+            "The password length must be between 1 and {}.",
+            MAX_LENGTH
+        ));
+    }
+
+    let symbols = policy.symbol_set.as_deref().unwrap_or(DEFAULT_SYMBOLS);
+
+    let classes = [
+        (policy.lowercase, LOWERCASE, policy.min_lowercase),
+        (policy.uppercase, UPPERCASE, policy.min_uppercase),
+        (policy.digits, DIGITS, policy.min_digits),
+        (policy.symbols, symbols, policy.min_symbols),
+    ];
+
+    let mut all = Vec::new();
+    let mut required = 0;
+    let mut password = Zeroizing::new(Vec::with_capacity(policy.length as usize));
+
+    for (enabled, characters, minimum) in classes {
+        if !enabled {
+            continue;
+        }
+
+        let mut characters: Vec<char> = characters
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .filter(|c| !policy.exclude_look_alikes || !LOOK_ALIKES.contains(*c))
+            .collect();
+        characters.sort_unstable();
+        characters.dedup();
+
+        if characters.is_empty() {
+            return Err(anyhow!("An enabled character class has no characters."));
+        }
+
+        required += minimum;
+        if required > policy.length {
+            return Err(anyhow!(
+                "The minimum character counts exceed the password length."
+            ));
+        }
+
+        for _ in 0..minimum {
+            password.push(characters[OsRng.gen_range(0..characters.len())]);
+        }
+
+        all.extend(characters);
+    }
+
+    all.sort_unstable();
+    all.dedup();
+
+    if all.is_empty() {
+        return Err(anyhow!("At least one character class must be enabled."));
+    }
+
+    while password.len() < policy.length as usize {
+        password.push(all[OsRng.gen_range(0..all.len())]);
+    }
+
+    password.shuffle(&mut OsRng);
+
+    Ok(Zeroizing::new(password.iter().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the characters of the password in the specified character set.
+    fn count_of(password: &str, characters: &str) -> u32 {
+        password.chars().filter(|c| characters.contains(*c)).count() as u32
+    }
+
+    #[test]
+    fn minimum_counts_are_satisfied() {
+        let policy = PasswordPolicy {
+            length: 12,
+            min_lowercase: 3,
+            min_uppercase: 3,
+            min_digits: 3,
+            min_symbols: 3,
+            ..PasswordPolicy::default()
+        };
+
+        for _ in 0..200 {
+            let password = generate_password(&policy).unwrap();
+
+            assert_eq!(password.chars().count(), 12);
+            assert_eq!(count_of(&password, LOWERCASE), 3);
+            assert_eq!(count_of(&password, UPPERCASE), 3);
+            assert_eq!(count_of(&password, DIGITS), 3);
+            assert_eq!(count_of(&password, DEFAULT_SYMBOLS), 3);
+        }
+    }
+
+    #[test]
+    fn look_alikes_are_excluded() {
+        let policy = PasswordPolicy {
+            length: MAX_LENGTH,
+            exclude_look_alikes: true,
+            ..PasswordPolicy::default()
+        };
+
+        for _ in 0..20 {
+            let password = generate_password(&policy).unwrap();
+            assert_eq!(count_of(&password, LOOK_ALIKES), 0);
+        }
+    }
+
+    #[test]
+    fn disabled_classes_and_custom_symbols() {
+        let policy = PasswordPolicy {
+            length: 100,
+            uppercase: false,
+            digits: false,
+            symbol_set: Some("@ @".to_string()),
+            ..PasswordPolicy::default()
+        };
+
+        let password = generate_password(&policy).unwrap();
+
+        assert!(password.chars().all(|c| c == '@' || LOWERCASE.contains(c)));
+        assert!(password.contains('@'));
+    }
+
+    #[test]
+    fn unsatisfiable_policy_is_rejected() {
+        let too_many = PasswordPolicy {
+            length: 3,
+            ..PasswordPolicy::default()
+        };
+        let nothing_enabled = PasswordPolicy {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..PasswordPolicy::default()
+        };
+        let no_symbols = PasswordPolicy {
+            symbol_set: Some(" ".to_string()),
+            ..PasswordPolicy::default()
+        };
+
+        for policy in [too_many, nothing_enabled, no_symbols] {
+            assert!(generate_password(&policy).is_err(), "{:?}", policy);
+        }
+        assert!(generate_password(&PasswordPolicy {
+            length: MAX_LENGTH + 1,
+            ..PasswordPolicy::default()
+        })
+        .is_err());
+    }
+}
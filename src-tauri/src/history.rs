@@ -0,0 +1,243 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::file_data::DataEntry;
+
+/// The maximum amount of revisions kept for an entry. The oldest revisions are dropped first.
+pub const MAX_REVISIONS: usize = 20;
+
+/// The fields of an entry tracked in the revision history, named as in the file data.
+pub const TRACKED_FIELDS: [&str; 8] = [
+    "name",
+    "domain",
+    "address",
+    "userName",
+    "password",
+    "notes",
+    "otpAuthKey",
+    "tags",
+];
+
+/// A change of an entry: the fields changed and their values before the change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryRevision {
+    /// The time of the change as seconds since the Unix epoch.
+    pub modified: u64,
+    /// The changed fields with their previous values.
+    pub changes: Vec<FieldChange>,
+}
+
+/// A changed field of an entry revision.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    /// The name of the field as in the file data, e.g. `userName`.
+    pub field: String,
+    /// The value of the field before the change; `None` if the field had no value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<String>,
+}
+
+impl Drop for FieldChange {
+    fn drop(&mut self) {
+        self.old_value.zeroize();
+    }
+}
+
+/// The non-secret information of an entry revision.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionInfo {
+    /// The index of the revision, 0 being the oldest one.
+    pub index: usize,
+    /// The time of the change as seconds since the Unix epoch.
+    pub modified: u64,
+    /// The names of the changed fields.
+    pub fields: Vec<String>,
+}
+
+/// A field differing between two versions of an entry.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    /// The name of the field as in the file data.
+    pub field: String,
+    /// The value of the field in the older version.
+    pub old_value: Option<String>,
+    /// The value of the field in the newer version.
+    pub new_value: Option<String>,
+}
+
+impl Drop for FieldDiff {
+    fn drop(&mut self) {
+        self.old_value.zeroize();
+        self.new_value.zeroize();
+    }
+}
+
+/// Gets the value of a tracked field of an entry.
+fn field_value(entry: &DataEntry, field: &str) -> Option<String> {
+    match field {
+        "name" => Some(entry.name.clone()),
+        "domain" => entry.domain.clone(),
+        "address" => entry.address.clone(),
+        "userName" => entry.user_name.clone(),
+        "password" => entry.password.clone(),
+        "notes" => entry.notes.clone(),
+        "otpAuthKey" => entry.otp_auth_key.clone(),
+        "tags" => entry.tags.clone(),
+        _ => None,
+    }
+}
+
+/// Sets the value of a tracked field of an entry.
+fn set_field_value(entry: &mut DataEntry, field: &str, value: Option<String>) {
+    match field {
+        "name" => entry.name = value.unwrap_or_default(),
+        "domain" => entry.domain = value,
+        "address" => entry.address = value,
+        "userName" => entry.user_name = value,
+        "password" => entry.password = value,
+        "notes" => entry.notes = value,
+        "otpAuthKey" => entry.otp_auth_key = value,
+        "tags" => entry.tags = value,
+        _ => {}
+    }
+}
+
+/// Compares the tracked fields of two versions of an entry.
+/// # Arguments
+///
+/// * `old` - the older version of the entry.
+/// * `new` - the newer version of the entry.
+///
+/// # Returns
+/// The fields differing between the versions.
+pub fn diff_entries(old: &DataEntry, new: &DataEntry) -> Vec<FieldDiff> {
+    TRACKED_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let old_value = field_value(old, field);
+            let new_value = field_value(new, field);
+            (old_value != new_value).then(|| FieldDiff {
+                field: field.to_string(),
+                old_value,
+                new_value,
+            })
+        })
+        .collect()
+}
+
+/// Records the changes of an entry into its revision history.
+/// # Arguments
+///
+/// * `previous` - the entry before the change.
+/// * `entry` - the changed entry; its history is replaced with the history of the previous entry and the new revision.
+///
+/// # Remarks
+/// * No revision is recorded if none of the tracked fields changed.
+/// * Only the [`MAX_REVISIONS`] latest revisions are kept.
+pub fn record_revision(previous: &DataEntry, entry: &mut DataEntry) {
+    let mut history = previous.history.clone().unwrap_or_default();
+
+    let changes: Vec<FieldChange> = diff_entries(previous, entry)
+        .iter_mut()
+        .map(|diff| FieldChange {
+            field: diff.field.clone(),
+            old_value: diff.old_value.take(),
+        })
+        .collect();
+
+    if !changes.is_empty() {
+        history.push(EntryRevision {
+            modified: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|f| f.as_secs())
+                .unwrap_or_default(),
+            changes,
+        });
+
+        if history.len() > MAX_REVISIONS {
+            history.drain(..history.len() - MAX_REVISIONS);
+        }
+    }
+
+    entry.history = (!history.is_empty()).then_some(history);
+}
+
+/// Lists the revisions of an entry without the previous values.
+/// # Arguments
+///
+/// * `entry` - the entry to list the revisions of.
+pub fn list_revisions(entry: &DataEntry) -> Vec<RevisionInfo> {
+    entry
+        .history
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, revision)| RevisionInfo {
+            index,
+            modified: revision.modified,
+            fields: revision.changes.iter().map(|f| f.field.clone()).collect(),
+        })
+        .collect()
+}
+
+/// Gets an entry as it was before the change of the specified revision.
+/// # Arguments
+///
+/// * `entry` - the current entry.
+/// * `index` - the index of the revision.
+///
+/// # Returns
+/// The entry without its revision history or an error if the revision doesn't exist.
+pub fn entry_at(entry: &DataEntry, index: usize) -> Result<DataEntry, anyhow::Error> {
+    let history = entry.history.as_deref().unwrap_or_default();
+    if index >= history.len() {
+        return Err(anyhow!(
+            "Revision {} of entry {} was not found.",
+            index,
+            entry.id
+        ));
+    }
+
+    let mut result = entry.clone();
+    result.history = None;
+
+    // Undo the changes from the latest one back to the requested revision.
+    for revision in history[index..].iter().rev() {
+        for change in &revision.changes {
+            set_field_value(&mut result, &change.field, change.old_value.clone());
+        }
+    }
+
+    Ok(result)
+}
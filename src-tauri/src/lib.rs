@@ -28,17 +28,54 @@ SOFTWARE.
 )]
 
 use arboard::Clipboard;
+use attachments::{export_blob, random_hex, remove_blob, store_blob, AttachmentInfo};
+use audit::AuditReport;
 use auth2fa::{gen_secret_otpauth, Auth2FAResult};
+use breach::{check_breaches, BreachReport, HibpFile};
 use config::{get_app_config, get_config_path, set_app_config, AppConfig};
-use encryption::{decrypt_small_file, encrypt_small_file};
+use diceware::{
+    parse_wordlist, Passphrase, PassphraseOptions, EFF_WORDLIST_RESOURCE, EFF_WORDLIST_WORD_COUNT,
+};
+use encryption::{
+    add_key_slot, add_recovery_key, change_file_password, decrypt_file_stream, decrypt_small_file,
+    encrypt_file_stream, encrypt_small_file, list_key_slots, replace_file_atomic, revoke_key_slot,
+    write_key_file, CompositeKey, KdfCalibration, KdfParams, KeySlotInfo, SaveOptions,
+};
+use expiry::{ExpiryInfo, ExpiryReport};
+use file_data::{Attachment, DataEntry, FileData, FileOptions};
 use fonts::get_font_families;
+use generator::{find_profile, PasswordPolicy};
+use history::{FieldDiff, RevisionInfo};
+use lock::{LockInfo, LockState};
+use migration::migrate_file_data;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
+use strength::StrengthEstimate;
+use tauri::{path::BaseDirectory, Emitter, Manager};
 use tokio::{fs::File, io::AsyncReadExt};
+use vault::{
+    unix_time, with_session, PendingSave, TrashInfo, VaultSession, VaultState, VaultSummary,
+};
+use verification::{verify_file as verify_file_integrity, VerificationReport};
+use zeroize::Zeroizing;
 
+mod attachments;
+mod audit;
 mod auth2fa;
+mod breach;
 mod config;
+mod diceware;
 mod encryption;
+mod expiry;
+mod file_data;
 mod fonts;
+mod generator;
+mod history;
+mod lock;
+mod migration;
+mod strength;
+mod vault;
+mod verification;
 
 /// Run the Tauri application.
 #[tokio::main]
@@ -51,6 +88,19 @@ pub async fn run() {
         .plugin(tauri_plugin_window_state::Builder::default().build())
         .plugin(tauri_plugin_updater::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
+        .manage(VaultState::default())
+        .setup(|app| {
+            let record_path = app.path().app_config_dir().ok().map(|mut path| {
+                let _ = std::fs::create_dir_all(&path);
+                path.push("lock_state.json");
+                path
+            });
+            app.manage(LockState::new(record_path));
+
+            tauri::async_runtime::spawn(watch_idle_lock(app.handle().clone()));
+            tauri::async_runtime::spawn(watch_expiry(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             save_file,
             load_file,
@@ -60,6 +110,48 @@ pub async fn run() {
             gen_otpauth,
             clear_clipboard,
             load_image_file,
+            change_password,
+            generate_key_file,
+            add_password_slot,
+            get_key_slots,
+            remove_key_slot,
+            generate_recovery_key,
+            calibrate_kdf,
+            verify_file,
+            encrypt_file,
+            decrypt_file,
+            open_vault,
+            close_vault,
+            list_entries,
+            get_entry,
+            get_password,
+            get_field_value,
+            search_entries,
+            export_vault,
+            update_entry,
+            delete_entry,
+            attach_file,
+            list_attachments,
+            export_attachment,
+            remove_attachment,
+            list_trash,
+            restore_trash_item,
+            purge_trash,
+            get_expiry_report,
+            audit_vault,
+            check_breached_passwords,
+            list_revisions,
+            diff_revisions,
+            restore_revision,
+            save_vault,
+            update_file_options,
+            generate_password,
+            generate_passphrase,
+            estimate_strength,
+            lock_vault,
+            unlock_vault,
+            report_activity,
+            get_lock_status,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -71,13 +163,1326 @@ pub async fn run() {
 /// * `json_data` - The JSON data to encrypt into the file contents.
 /// * `file_name` - The file name to save the data into.
 /// * `password` - The password to use for the data encryption.
+/// * `key_file` - An optional key file to combine with the password.
+/// * `options` - Optional key derivation parameters (e.g. from [`calibrate_kdf`]), cipher suite and compression. The values not specified are kept from the existing file; new files default to AES-256-GCM-SIV without compression. Set [`SaveOptions::overwrite`] to replace an existing file with "save as".
 ///
 /// # Returns
-/// * `bool` value indicating whether the save operation was successful.
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The amount of rotating backups kept next to the file is read from the application settings.
+/// * The data is rejected if it doesn't match the file data format or its entries are inconsistent, e.g. duplicate ids or entries with a non-existent category.
+/// * An existing file the key doesn't unlock is kept and an error is returned unless it is overwritten.
+/// * The recycle bin of the existing file is kept and the entries missing from the data are moved into it, see [`FileData::merge_trash`].
+/// * The changes of the entries since the existing file are recorded into their revision histories, see [`FileData::merge_history`].
+/// * The saved data replaces the vault session so the lock timeout and the expiry checks apply to the file, see [`load_file`].
+/// * Failed attempts are handled like in [`open_vault`].
 #[tauri::command]
-async fn save_file(json_data: String, file_name: String, password: String) -> bool {
-    let result = !encrypt_small_file(&file_name, &password, &json_data).is_err();
-    result
+async fn save_file(
+    json_data: String,
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    options: Option<SaveOptions>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let json_data = Zeroizing::new(json_data);
+    let password = Zeroizing::new(password);
+
+    let mut file_data = FileData::from_json(&json_data).map_err(|e| e.to_string())?;
+    file_data.validate().map_err(|e| e.to_string())?;
+
+    let cfg_path = get_config_path(&app_handle).await;
+    let config = get_app_config(&cfg_path).await;
+
+    let options = options.unwrap_or_default();
+
+    let vault_state = app_handle.state::<VaultState>();
+    let _save = vault_state.lock_save().await;
+
+    let handle = app_handle.clone();
+    password_attempt(&app_handle, move || {
+        let key = CompositeKey::new(&password, key_file.as_deref())?;
+
+        if !options.overwrite && std::path::Path::new(&file_name).exists() {
+            let previous = Zeroizing::new(decrypt_small_file(&file_name, &key)?);
+            let previous = migrate_file_data(&previous)?;
+            file_data.merge_trash(&previous, unix_time());
+            file_data.merge_history(&previous);
+            file_data.purge_expired_trash(unix_time());
+        }
+
+        let json_data = Zeroizing::new(serde_json::to_string(&file_data)?);
+
+        encrypt_small_file(
+            &file_name,
+            &key,
+            &json_data,
+            config.backup_count(),
+            &options,
+        )?;
+
+        let mut session = VaultSession::new(&file_name, key, file_data);
+        let vault_state = handle.state::<VaultState>();
+        let mut vault = vault_state.lock()?;
+        if let Some(previous) = vault.as_ref() {
+            session.continue_expiry_checks(previous);
+        }
+        *vault = Some(session);
+
+        Ok(())
+    })
+    .await
+}
+
+/// Changes the password of the specified file. The file contents are decrypted and re-encrypted in the backend.
+///
+/// # Arguments
+/// * `file_name` - The file to change the password of.
+/// * `old_password` - The current password of the file.
+/// * `new_password` - The new password for the file.
+/// * `new_kdf_params` - Optional new key derivation parameters. The current ones are kept if not specified.
+/// * `old_key_file` - An optional key file the file is currently encrypted with.
+/// * `new_key_file` - An optional key file to combine with the new password.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The previous version of the file is backed up like when saving.
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn change_password(
+    file_name: String,
+    old_password: String,
+    new_password: String,
+    new_kdf_params: Option<KdfParams>,
+    old_key_file: Option<String>,
+    new_key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let old_password = Zeroizing::new(old_password);
+    let new_password = Zeroizing::new(new_password);
+
+    let old_key =
+        CompositeKey::new(&old_password, old_key_file.as_deref()).map_err(|e| e.to_string())?;
+    let new_key =
+        CompositeKey::new(&new_password, new_key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let backup_count = get_app_config(&get_config_path(&app_handle).await)
+        .await
+        .backup_count();
+
+    password_attempt(&app_handle, move || {
+        change_file_password(&file_name, &old_key, &new_key, new_kdf_params, backup_count)
+    })
+    .await
+}
+
+/// Generates a new key file with random contents to be used along with or instead of a password.
+///
+/// # Arguments
+/// * `file_name` - The key file to create. An existing file is not overridden.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn generate_key_file(file_name: String) -> Result<(), String> {
+    write_key_file(&file_name).map_err(|e| e.to_string())
+}
+
+/// Adds a new password key slot into the specified file so another person can open the file with their own password.
+///
+/// # Arguments
+/// * `file_name` - The file to add the key slot into.
+/// * `password` - A current password of the file.
+/// * `key_file` - An optional key file combined with the current password.
+/// * `new_password` - The password for the new key slot.
+/// * `new_key_file` - An optional key file combined with the new password.
+/// * `label` - A label for the key slot, e.g. the name of the person the slot belongs to.
+/// * `kdf_params` - Optional key derivation parameters for the new key slot.
+///
+/// # Returns
+/// A `Result<u8, String>` with the identifier of the new key slot or an error message.
+///
+/// # Remarks
+/// * The previous version of the file is backed up like when saving.
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn add_password_slot(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    new_password: String,
+    new_key_file: Option<String>,
+    label: String,
+    kdf_params: Option<KdfParams>,
+    app_handle: tauri::AppHandle,
+) -> Result<u8, String> {
+    let password = Zeroizing::new(password);
+    let new_password = Zeroizing::new(new_password);
+
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+    let new_key =
+        CompositeKey::new(&new_password, new_key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let backup_count = get_app_config(&get_config_path(&app_handle).await)
+        .await
+        .backup_count();
+
+    password_attempt(&app_handle, move || {
+        add_key_slot(&file_name, &key, &new_key, &label, kdf_params, backup_count)
+    })
+    .await
+}
+
+/// Lists the key slots of the specified file. No password is needed.
+///
+/// # Arguments
+/// * `file_name` - The file to list the key slots of.
+///
+/// # Returns
+/// A `Result<Vec<KeySlotInfo>, String>` with the key slot information or an error message.
+#[tauri::command]
+async fn get_key_slots(file_name: String) -> Result<Vec<KeySlotInfo>, String> {
+    list_key_slots(&file_name).map_err(|e| e.to_string())
+}
+
+/// Revokes a key slot of the specified file. The last key slot of a file can't be revoked.
+///
+/// # Arguments
+/// * `file_name` - The file to revoke the key slot from.
+/// * `password` - A password of any key slot of the file.
+/// * `key_file` - An optional key file combined with the password.
+/// * `slot_id` - The identifier of the key slot to revoke.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The previous version of the file is backed up like when saving.
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn remove_key_slot(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    slot_id: u8,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let backup_count = get_app_config(&get_config_path(&app_handle).await)
+        .await
+        .backup_count();
+
+    password_attempt(&app_handle, move || {
+        revoke_key_slot(&file_name, &key, slot_id, backup_count)
+    })
+    .await
+}
+
+/// Generates a recovery key and adds a key slot for it into the specified file.
+///
+/// # Arguments
+/// * `file_name` - The file to add the recovery key slot into.
+/// * `password` - A current password of the file.
+/// * `key_file` - An optional key file combined with the password.
+/// * `label` - A label for the recovery key slot.
+///
+/// # Returns
+/// A `Result<String, String>` with the printable recovery key or an error message. The recovery key is shown only once.
+///
+/// # Remarks
+/// * The previous version of the file is backed up like when saving.
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn generate_recovery_key(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    label: String,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let password = Zeroizing::new(password);
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let backup_count = get_app_config(&get_config_path(&app_handle).await)
+        .await
+        .backup_count();
+
+    password_attempt(&app_handle, move || {
+        add_recovery_key(&file_name, &key, &label, backup_count)
+            .map(|recovery_key| recovery_key.to_string())
+    })
+    .await
+}
+
+/// Benchmarks the key derivation on this machine and proposes Argon2id parameters hitting the specified unlock time.
+///
+/// # Arguments
+/// * `target_ms` - The desired unlock time in milliseconds.
+///
+/// # Returns
+/// A `Result<KdfCalibration, String>` with the proposed parameters and the measured unlock time or an error message.
+#[tauri::command]
+async fn calibrate_kdf(target_ms: u64) -> Result<KdfCalibration, String> {
+    tokio::task::spawn_blocking(move || KdfParams::calibrate(target_ms))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Verifies the integrity of the specified file without passing the decrypted data to the frontend.
+///
+/// # Arguments
+/// * `file_name` - The file to verify, e.g. a backup file.
+/// * `password` - The password of the file.
+/// * `key_file` - An optional key file combined with the password.
+///
+/// # Returns
+/// A [`VerificationReport`] with the results of the container structure, length, authentication and data format checks.
+///
+/// # Remarks
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn verify_file(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> VerificationReport {
+    let password = Zeroizing::new(password);
+    let lock_state = app_handle.state::<LockState>();
+
+    let report = match lock_state.begin_attempt() {
+        Ok(attempt) => {
+            let report = match CompositeKey::new(&password, key_file.as_deref()) {
+                Ok(key) => {
+                    let file_name = file_name.clone();
+                    tokio::task::spawn_blocking(move || verify_file_integrity(&file_name, &key))
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+                Err(e) => Err(e.to_string()),
+            };
+
+            match &report {
+                Ok(report) if report.invalid_password => {
+                    let _ = attempt.fail();
+                }
+                Ok(report) if report.authenticated => {
+                    let _ = attempt.succeed();
+                }
+                _ => {}
+            }
+
+            report
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    let report = report.unwrap_or_else(|e| VerificationReport {
+        file_name,
+        format_version: None,
+        structure_valid: false,
+        length_consistent: false,
+        authenticated: false,
+        invalid_password: false,
+        schema_valid: false,
+        valid: false,
+        errors: vec![e],
+    });
+
+    if report.invalid_password {
+        exit_on_attempt_limit(&app_handle, &lock_state).await;
+    }
+
+    report
+}
+
+/// Encrypts the specified file into a container file, e.g. a large attachment. The data is streamed in chunks with a bounded memory use.
+///
+/// # Arguments
+/// * `source_file_name` - The file to encrypt.
+/// * `file_name` - The container file to write the encrypted data into.
+/// * `password` - The password to use for the data encryption.
+/// * `key_file` - An optional key file to combine with the password.
+/// * `options` - Optional encryption options. The chunked encryption is used unless disabled.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn encrypt_file(
+    source_file_name: String,
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    options: Option<SaveOptions>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let options = options.unwrap_or_default();
+    let options = SaveOptions {
+        chunked: options.chunked.or(Some(true)),
+        ..options
+    };
+
+    password_attempt(&app_handle, move || {
+        let mut source = std::fs::File::open(&source_file_name)?;
+        encrypt_file_stream(&file_name, &key, &mut source, 0, &options)
+    })
+    .await
+}
+
+/// Decrypts the specified container file into a plain file, e.g. to export a large attachment. The data is streamed in chunks with a bounded memory use.
+///
+/// # Arguments
+/// * `file_name` - The container file to decrypt.
+/// * `target_file_name` - The file to write the decrypted data into.
+/// * `password` - The password of the container file.
+/// * `key_file` - An optional key file combined with the password.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The partially written target file is removed if the decryption fails.
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn decrypt_file(
+    file_name: String,
+    target_file_name: String,
+    password: String,
+    key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    password_attempt(&app_handle, move || {
+        let mut target = std::fs::File::create(&target_file_name)?;
+        let result = decrypt_file_stream(&file_name, &key, &mut target);
+
+        if result.is_err() {
+            drop(target);
+            let _ = std::fs::remove_file(&target_file_name);
+        }
+
+        result
+    })
+    .await
+}
+
+/// Opens the specified file into a vault session kept in the backend memory. A previously open vault is closed.
+///
+/// # Arguments
+/// * `file_name` - The file to open.
+/// * `password` - The password of the file.
+/// * `key_file` - An optional key file combined with the password.
+///
+/// # Returns
+/// A `Result<VaultSummary, String>` with the non-secret information of the vault or an error message.
+///
+/// # Remarks
+/// * The entries and their secrets are fetched with [`list_entries`], [`get_entry`] and [`get_password`].
+/// * Failed attempts delay the next attempt exponentially and close the application when the limit in the settings is reached.
+/// * The file is decrypted on a blocking thread; the vault state is locked only to replace the session.
+#[tauri::command]
+async fn open_vault(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<VaultSummary, String> {
+    let password = Zeroizing::new(password);
+    let key = CompositeKey::new(&password, key_file.as_deref()).map_err(|e| e.to_string())?;
+
+    let handle = app_handle.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        lock::open_vault(
+            &handle.state::<VaultState>(),
+            &handle.state::<LockState>(),
+            &file_name,
+            key,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if result.is_err() {
+        exit_on_attempt_limit(&app_handle, &app_handle.state::<LockState>()).await;
+    }
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Locks the open vault, wiping its decrypted data and key from the memory. The vault is unlocked with [`unlock_vault`].
+///
+/// # Returns
+/// A `Result<Option<String>, String>` with the file of the locked vault (`None` if no vault was open) or an error message.
+///
+/// # Remarks
+/// * A vault with unsaved changes is saved like with [`save_vault`] before it is locked. The vault is not locked if the save fails.
+#[tauri::command]
+async fn lock_vault(app_handle: tauri::AppHandle) -> Result<Option<String>, String> {
+    save_and_lock(&app_handle, false)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Saves the unsaved changes of the open vault and locks the vault.
+///
+/// # Arguments
+/// * `app_handle` - The application handle.
+/// * `discard_unsaved` - A value indicating whether to lock the vault even if its unsaved changes can't be saved. The `vault-save-failed` event with the error is emitted to the frontend in that case.
+///
+/// # Returns
+/// A `Result<Option<String>, anyhow::Error>` with the file of the locked vault (`None` if no vault was open) or an error.
+async fn save_and_lock(
+    app_handle: &tauri::AppHandle,
+    discard_unsaved: bool,
+) -> Result<Option<String>, anyhow::Error> {
+    let vault_state = app_handle.state::<VaultState>();
+    let lock_state = app_handle.state::<LockState>();
+    let backup_count = get_app_config(&get_config_path(app_handle).await)
+        .await
+        .backup_count();
+
+    let _save = vault_state.lock_save().await;
+
+    let pending = match vault_state.lock()?.as_mut() {
+        Some(session) if session.changed() => Some(session.begin_save()?),
+        _ => None,
+    };
+
+    if let Some(pending) = pending {
+        let result =
+            write_pending(&vault_state, pending, backup_count, SaveOptions::default()).await;
+
+        if let Err(e) = result {
+            if !discard_unsaved {
+                return Err(e);
+            }
+
+            let _ = app_handle.emit("vault-save-failed", e.to_string());
+        }
+    }
+
+    lock::lock_vault(&vault_state, &lock_state)
+}
+
+/// Unlocks the vault locked with [`lock_vault`] or by the lock timeout.
+///
+/// # Arguments
+/// * `password` - The password of the vault file.
+/// * `key_file` - An optional key file combined with the password.
+///
+/// # Returns
+/// A `Result<VaultSummary, String>` with the non-secret information of the vault or an error message.
+///
+/// # Remarks
+/// * Failed attempts are handled like in [`open_vault`].
+#[tauri::command]
+async fn unlock_vault(
+    password: String,
+    key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+    lock_state: tauri::State<'_, LockState>,
+) -> Result<VaultSummary, String> {
+    let file_name = lock_state
+        .locked_file()
+        .ok_or_else(|| "No vault is locked.".to_string())?;
+
+    open_vault(file_name, password, key_file, app_handle).await
+}
+
+/// Records user activity, postponing the lock timeout.
+#[tauri::command]
+async fn report_activity(lock_state: tauri::State<'_, LockState>) -> Result<(), String> {
+    lock_state.touch();
+
+    Ok(())
+}
+
+/// Gets the lock status and the failed unlock attempts.
+///
+/// # Returns
+/// A `Result<LockInfo, String>` with the lock status or an error message.
+#[tauri::command]
+async fn get_lock_status(lock_state: tauri::State<'_, LockState>) -> Result<LockInfo, String> {
+    lock_state.info().map_err(|e| e.to_string())
+}
+
+/// Closes the application if the failed unlock attempts reached the limit in the settings.
+///
+/// # Arguments
+/// * `app_handle` - The application handle.
+/// * `lock_state` - The lock state of the application.
+async fn exit_on_attempt_limit(app_handle: &tauri::AppHandle, lock_state: &LockState) {
+    let config = get_app_config(&get_config_path(app_handle).await).await;
+    let limit = config.failed_unlock_attempts();
+
+    if let Ok(info) = lock_state.info() {
+        if limit > 0 && info.failed_attempts >= limit {
+            app_handle.exit(0);
+        }
+    }
+}
+
+/// Runs an operation checking the password of a file on a blocking thread, handling the failed attempts like [`open_vault`].
+///
+/// # Arguments
+/// * `app_handle` - The application handle.
+/// * `operation` - The operation to run.
+///
+/// # Returns
+/// A `Result<T, String>` with the result of the operation or an error message.
+///
+/// # Remarks
+/// * The operation is not run while the failed attempts delay the next attempt or another attempt is in progress.
+/// * The result of the operation is returned even if the attempt couldn't be persisted.
+async fn password_attempt<T: Send + 'static>(
+    app_handle: &tauri::AppHandle,
+    operation: impl FnOnce() -> Result<T, anyhow::Error> + Send + 'static,
+) -> Result<T, String> {
+    let lock_state = app_handle.state::<LockState>();
+    let attempt = lock_state.begin_attempt().map_err(|e| e.to_string())?;
+
+    let result = tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = attempt.finish(&result);
+    if result.is_err() {
+        exit_on_attempt_limit(app_handle, &lock_state).await;
+    }
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Checks the open vault for the entries expiring while the vault is open.
+/// The `entry-expired` event with the newly expired entries is emitted to the frontend.
+///
+/// # Remarks
+/// * A file opened with [`load_file`] or saved with [`save_file`] is the open vault as well, so the file the frontend edits is checked as last saved.
+///
+/// # Arguments
+/// * `app_handle` - The application handle.
+async fn watch_expiry(app_handle: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+
+        let vault_state = app_handle.state::<VaultState>();
+        let expired: Vec<ExpiryInfo> =
+            with_session(&vault_state, |session| Ok(session.check_expired())).unwrap_or_default();
+
+        if !expired.is_empty() {
+            let _ = app_handle.emit("entry-expired", expired);
+        }
+    }
+}
+
+/// Locks the open vault when the user has been idle for the lock timeout in the settings.
+/// The `vault-locked` event with the file of the vault is emitted to the frontend on locking.
+///
+/// # Arguments
+/// * `app_handle` - The application handle.
+///
+/// # Remarks
+/// * The unsaved changes of the vault are saved before locking. The vault is locked even if the save fails so the decrypted data doesn't stay in the memory, see [`save_and_lock`].
+async fn watch_idle_lock(app_handle: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+
+    loop {
+        interval.tick().await;
+
+        let config = get_app_config(&get_config_path(&app_handle).await).await;
+        let timeout = u64::from(config.lock_timeout()) * 60;
+        if timeout == 0 {
+            continue;
+        }
+
+        let lock_state = app_handle.state::<LockState>();
+        if lock_state.idle_time().as_secs() < timeout {
+            continue;
+        }
+
+        if let Ok(Some(file_name)) = save_and_lock(&app_handle, true).await {
+            let _ = app_handle.emit("vault-locked", file_name);
+        }
+    }
+}
+
+/// Closes the open vault session. The decrypted data and the key are wiped from the memory.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn close_vault(state: tauri::State<'_, VaultState>) -> Result<(), String> {
+    *state.lock().map_err(|e| e.to_string())? = None;
+
+    Ok(())
+}
+
+/// Lists the entries and the categories of the open vault without the passwords, the notes and the OTP keys.
+///
+/// # Returns
+/// A `Result<Vec<DataEntry>, String>` with the entries or an error message.
+#[tauri::command]
+async fn list_entries(state: tauri::State<'_, VaultState>) -> Result<Vec<DataEntry>, String> {
+    with_session(&state, |session| Ok(session.list_entries())).map_err(|e| e.to_string())
+}
+
+/// Gets an entry of the open vault without the password.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+///
+/// # Returns
+/// A `Result<DataEntry, String>` with the entry or an error message.
+#[tauri::command]
+async fn get_entry(id: i64, state: tauri::State<'_, VaultState>) -> Result<DataEntry, String> {
+    with_session(&state, |session| session.entry(id)).map_err(|e| e.to_string())
+}
+
+/// Gets the password of an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+///
+/// # Returns
+/// A `Result<String, String>` with the password or an error message.
+#[tauri::command]
+async fn get_password(id: i64, state: tauri::State<'_, VaultState>) -> Result<String, String> {
+    with_session(&state, |session| {
+        session.password(id).map(|password| password.to_string())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Gets the value of a custom field of an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `name` - The name of the field.
+///
+/// # Returns
+/// A `Result<String, String>` with the value or an error message.
+#[tauri::command]
+async fn get_field_value(
+    id: i64,
+    name: String,
+    state: tauri::State<'_, VaultState>,
+) -> Result<String, String> {
+    with_session(&state, |session| {
+        session
+            .field_value(id, &name)
+            .map(|value| value.to_string())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Searches the entries of the open vault.
+///
+/// # Arguments
+/// * `search` - The words to search for separated with spaces.
+/// * `all_words` - A value indicating whether an entry must contain all the words instead of any of them.
+/// * `include_protected` - A value indicating whether to search from the passwords, the OTP keys and the protected fields.
+///
+/// # Returns
+/// A `Result<Vec<i64>, String>` with the identifiers of the matching entries or an error message.
+#[tauri::command]
+async fn search_entries(
+    search: String,
+    all_words: bool,
+    include_protected: Option<bool>,
+    state: tauri::State<'_, VaultState>,
+) -> Result<Vec<i64>, String> {
+    with_session(&state, |session| {
+        Ok(session.search(&search, all_words, include_protected.unwrap_or(false)))
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Exports the open vault into a plain, unencrypted JSON file.
+///
+/// # Arguments
+/// * `target_file_name` - The file to write the JSON data into.
+/// * `include_protected` - A value indicating whether to include the values of the protected fields.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The file is written on a blocking thread and replaced atomically so a failed export doesn't leave a partially written file.
+#[tauri::command]
+async fn export_vault(
+    target_file_name: String,
+    include_protected: Option<bool>,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    let json = with_session(&state, |session| {
+        session.export(include_protected.unwrap_or(false))
+    })
+    .map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        replace_file_atomic(&target_file_name, |output| {
+            Ok(output.write_all(json.as_bytes())?)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Adds a new entry into the open vault or updates an existing one.
+///
+/// # Arguments
+/// * `entry` - The entry to add or update. The password of an existing entry is kept if not specified.
+///
+/// # Returns
+/// A `Result<i64, String>` with the identifier of the entry or an error message.
+///
+/// # Remarks
+/// * The entry is placed into the category named by its first tag like in the entry editor.
+#[tauri::command]
+async fn update_entry(
+    entry: DataEntry,
+    state: tauri::State<'_, VaultState>,
+) -> Result<i64, String> {
+    with_session(&state, |session| session.update_entry(entry)).map_err(|e| e.to_string())
+}
+
+/// Deletes an entry or a category from the open vault into the recycle bin.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry or the category.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn delete_entry(id: i64, state: tauri::State<'_, VaultState>) -> Result<(), String> {
+    with_session(&state, |session| session.delete_entry(id)).map_err(|e| e.to_string())
+}
+
+/// Attaches a file to an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `file_name` - The file to attach.
+///
+/// # Returns
+/// A `Result<i64, String>` with the identifier of the attachment or an error message.
+///
+/// # Remarks
+/// * The contents are streamed from the file and encrypted with a random key into a blob store directory next to the vault file. The key is kept in the vault.
+/// * The attachments with the same contents share a blob; the blob of a duplicate is removed once its contents are hashed.
+/// * The attachment is not added if another vault was opened while the file was encrypted.
+/// * The attachment is a part of the vault data once the vault is saved.
+#[tauri::command]
+async fn attach_file(
+    id: i64,
+    file_name: String,
+    state: tauri::State<'_, VaultState>,
+) -> Result<i64, String> {
+    let (vault_path, session_id) = with_session(&state, |session| {
+        Ok((session.file_path().to_string(), session.id()))
+    })
+    .map_err(|e| e.to_string())?;
+
+    let blob = random_hex(16).to_string();
+    let key = random_hex(32);
+
+    let (source, store_path, store_blob_name, store_key) = (
+        file_name.clone(),
+        vault_path.clone(),
+        blob.clone(),
+        key.clone(),
+    );
+    let (hash, size) = tokio::task::spawn_blocking(move || {
+        let mut input = std::fs::File::open(&source)?;
+        store_blob(&store_path, &store_blob_name, &store_key, &mut input)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let name = std::path::Path::new(&file_name)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or(file_name.clone());
+
+    let mut attachment = Attachment {
+        id: 0,
+        name,
+        size,
+        hash,
+        blob: blob.clone(),
+        key: Some(key.to_string()),
+        added: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|f| f.as_secs())
+            .unwrap_or_default(),
+    };
+
+    let result = with_session(&state, |session| {
+        if session.id() != session_id {
+            return Err(anyhow::anyhow!(
+                "The vault was changed while the file was attached."
+            ));
+        }
+
+        if let Some(existing) = session.find_attachment_by_hash(&attachment.hash) {
+            attachment.blob = existing.blob.clone();
+            attachment.key = existing.key.clone();
+        }
+
+        session.add_attachment(id, attachment.clone())
+    });
+
+    if result.is_err() || attachment.blob != blob {
+        let _ = remove_blob(&vault_path, &blob);
+    }
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Lists the attachments of an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+///
+/// # Returns
+/// A `Result<Vec<AttachmentInfo>, String>` with the attachments or an error message.
+#[tauri::command]
+async fn list_attachments(
+    id: i64,
+    state: tauri::State<'_, VaultState>,
+) -> Result<Vec<AttachmentInfo>, String> {
+    with_session(&state, |session| session.attachments(id)).map_err(|e| e.to_string())
+}
+
+/// Exports an attachment of an entry of the open vault into a file.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `attachment_id` - The identifier of the attachment.
+/// * `target_file_name` - The file to write the decrypted contents into.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn export_attachment(
+    id: i64,
+    attachment_id: i64,
+    target_file_name: String,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    let (vault_path, attachment) = with_session(&state, |session| {
+        Ok((
+            session.file_path().to_string(),
+            session.attachment(id, attachment_id)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?;
+
+    let key = attachment
+        .key
+        .clone()
+        .map(Zeroizing::new)
+        .ok_or_else(|| "The attachment has no key.".to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        export_blob(&vault_path, &attachment.blob, &key, &target_file_name)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Removes an attachment from an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `attachment_id` - The identifier of the attachment.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The encrypted contents are removed when the vault is saved if no other attachment shares them.
+#[tauri::command]
+async fn remove_attachment(
+    id: i64,
+    attachment_id: i64,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    with_session(&state, |session| {
+        session.remove_attachment(id, attachment_id)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Lists the items in the recycle bin of the open vault without the secrets.
+///
+/// # Returns
+/// A `Result<Vec<TrashInfo>, String>` with the items or an error message.
+#[tauri::command]
+async fn list_trash(state: tauri::State<'_, VaultState>) -> Result<Vec<TrashInfo>, String> {
+    with_session(&state, |session| Ok(session.list_trash())).map_err(|e| e.to_string())
+}
+
+/// Restores an item from the recycle bin of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the item in the recycle bin.
+///
+/// # Returns
+/// A `Result<i64, String>` with the identifier of the restored entry or category or an error message.
+#[tauri::command]
+async fn restore_trash_item(id: i64, state: tauri::State<'_, VaultState>) -> Result<i64, String> {
+    with_session(&state, |session| session.restore_trash_item(id)).map_err(|e| e.to_string())
+}
+
+/// Purges items from the recycle bin of the open vault permanently.
+///
+/// # Arguments
+/// * `id` - The identifier of the item in the recycle bin; `None` to empty the recycle bin.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn purge_trash(id: Option<i64>, state: tauri::State<'_, VaultState>) -> Result<(), String> {
+    with_session(&state, |session| session.purge_trash(id)).map_err(|e| e.to_string())
+}
+
+/// Replaces the global file options of the open vault, e.g. the recycle bin purge policy and the password profiles.
+///
+/// # Arguments
+/// * `options` - The new file options.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+#[tauri::command]
+async fn update_file_options(
+    options: FileOptions,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    with_session(&state, |session| session.set_data_options(options)).map_err(|e| e.to_string())
+}
+
+/// Generates a random password.
+///
+/// # Arguments
+/// * `policy` - The rules for the password.
+/// * `profile` - The name of a password profile to use if no policy is specified. The profiles of the open vault take precedence over the ones in the settings.
+///
+/// # Returns
+/// A `Result<String, String>` with the generated password or an error message.
+///
+/// # Remarks
+/// * The default policy is used if neither a policy nor a profile is specified.
+#[tauri::command]
+async fn generate_password(
+    policy: Option<PasswordPolicy>,
+    profile: Option<String>,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, VaultState>,
+) -> Result<String, String> {
+    let policy = match (policy, profile) {
+        (Some(policy), _) => policy,
+        (None, Some(profile)) => {
+            let vault_policy = with_session(&state, |session| {
+                Ok(session
+                    .data_options()
+                    .and_then(|f| f.password_profiles.as_deref())
+                    .and_then(|f| find_profile(f, &profile))
+                    .map(|f| f.policy.clone()))
+            })
+            .unwrap_or_default();
+
+            match vault_policy {
+                Some(policy) => policy,
+                None => {
+                    let config = get_app_config(&get_config_path(&app_handle).await).await;
+                    find_profile(config.password_profiles(), &profile)
+                        .map(|f| f.policy.clone())
+                        .ok_or_else(|| format!("Password profile {} was not found.", profile))?
+                }
+            }
+        }
+        (None, None) => PasswordPolicy::default(),
+    };
+
+    generator::generate_password(&policy)
+        .map(|password| password.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Generates a random passphrase of words.
+///
+/// # Arguments
+/// * `options` - The word count, the separator, the capitalization and the digit insertion; six words separated with a dash if not specified.
+/// * `wordlist_file` - A custom wordlist file with a word per line; the bundled EFF large wordlist if not specified.
+///
+/// # Returns
+/// A `Result<Passphrase, String>` with the passphrase and its entropy estimate or an error message. An error is returned if the bundled wordlist is incomplete.
+#[tauri::command]
+async fn generate_passphrase(
+    options: Option<PassphraseOptions>,
+    wordlist_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<Passphrase, String> {
+    let bundled = wordlist_file.is_none();
+    let wordlist_file = match wordlist_file {
+        Some(file) => std::path::PathBuf::from(file),
+        None => app_handle
+            .path()
+            .resolve(EFF_WORDLIST_RESOURCE, BaseDirectory::Resource)
+            .map_err(|e| e.to_string())?,
+    };
+
+    let text = tokio::fs::read_to_string(&wordlist_file)
+        .await
+        .map_err(|e| format!("{}: '{}'", e, wordlist_file.display()))?;
+    let words = parse_wordlist(&text).map_err(|e| e.to_string())?;
+    if bundled && words.len() != EFF_WORDLIST_WORD_COUNT {
+        return Err(format!(
+            "The bundled wordlist has {} words instead of {}: '{}'",
+            words.len(),
+            EFF_WORDLIST_WORD_COUNT,
+            wordlist_file.display()
+        ));
+    }
+
+    diceware::generate_passphrase(&words, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Estimates the strength of a password.
+///
+/// # Arguments
+/// * `password` - The password to estimate.
+/// * `user_inputs` - The words related to the user or the entry, e.g. the user name and the domain, which make the password easier to guess.
+///
+/// # Returns
+/// A `Result<StrengthEstimate, String>` with the score from 0 to 4, the estimated guesses, the crack time estimates and the feedback.
+///
+/// # Remarks
+/// * The estimator uses reduced dictionaries, so the score is an upper bound; see [`strength::estimate_strength`].
+#[tauri::command]
+async fn estimate_strength(
+    password: String,
+    user_inputs: Option<Vec<String>>,
+) -> Result<StrengthEstimate, String> {
+    let password = Zeroizing::new(password);
+    Ok(strength::estimate_strength(
+        &password,
+        &user_inputs.unwrap_or_default(),
+    ))
+}
+
+/// Reports the expired entries and the entries expiring soon in the open vault.
+///
+/// # Arguments
+/// * `warning_days` - The amount of days before the expiry an entry is reported as expiring soon; 14 days if not specified.
+///
+/// # Returns
+/// A `Result<ExpiryReport, String>` with the report or an error message.
+#[tauri::command]
+async fn get_expiry_report(
+    warning_days: Option<u32>,
+    state: tauri::State<'_, VaultState>,
+) -> Result<ExpiryReport, String> {
+    with_session(&state, |session| {
+        Ok(session.expiry_report(warning_days.unwrap_or(expiry::EXPIRY_WARNING_DAYS)))
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Audits the security of the entries of the open vault.
+///
+/// # Arguments
+/// * `min_score` - The minimum strength score from 0 to 4 of a password not reported as weak; 3 if not specified.
+///
+/// # Returns
+/// A `Result<AuditReport, String>` with the weak, reused and empty passwords, the entries missing two-factor authentication and the duplicate entries or an error message.
+#[tauri::command]
+async fn audit_vault(
+    min_score: Option<u8>,
+    state: tauri::State<'_, VaultState>,
+) -> Result<AuditReport, String> {
+    with_session(&state, |session| {
+        Ok(session.audit(min_score.unwrap_or(audit::MIN_STRENGTH_SCORE)))
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Checks the passwords of the entries of the open vault against a locally downloaded Have I Been Pwned password hash file.
+///
+/// # Arguments
+/// * `hibp_file` - The SHA-1 password hash file ordered by hash; the file in the settings if not specified.
+///
+/// # Returns
+/// A `Result<BreachReport, String>` with the breach counts of the entries with a breached password or an error message.
+///
+/// # Remarks
+/// * The check is done entirely offline; neither the passwords nor their hashes leave the computer.
+#[tauri::command]
+async fn check_breached_passwords(
+    hibp_file: Option<String>,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, VaultState>,
+) -> Result<BreachReport, String> {
+    let hibp_file = match hibp_file {
+        Some(file) => file,
+        None => get_app_config(&get_config_path(&app_handle).await)
+            .await
+            .hibp_file()
+            .map(str::to_string)
+            .ok_or("No Have I Been Pwned hash file is set in the settings.")?,
+    };
+
+    let hashes =
+        with_session(&state, |session| Ok(session.password_hashes())).map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut file = HibpFile::open(std::path::Path::new(&hibp_file))?;
+        check_breaches(&mut file, &hashes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Lists the revisions of an entry of the open vault without the previous values.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+///
+/// # Returns
+/// A `Result<Vec<RevisionInfo>, String>` with the revisions, the oldest first, or an error message.
+#[tauri::command]
+async fn list_revisions(
+    id: i64,
+    state: tauri::State<'_, VaultState>,
+) -> Result<Vec<RevisionInfo>, String> {
+    with_session(&state, |session| session.revisions(id)).map_err(|e| e.to_string())
+}
+
+/// Compares two versions of an entry of the open vault.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `from` - The index of the revision of the older version; the version is the entry before the change of the revision.
+/// * `to` - The index of the revision of the newer version; `None` for the current entry.
+///
+/// # Returns
+/// A `Result<Vec<FieldDiff>, String>` with the differing fields and their values or an error message.
+#[tauri::command]
+async fn diff_revisions(
+    id: i64,
+    from: usize,
+    to: Option<usize>,
+    state: tauri::State<'_, VaultState>,
+) -> Result<Vec<FieldDiff>, String> {
+    with_session(&state, |session| session.diff_revisions(id, from, to)).map_err(|e| e.to_string())
+}
+
+/// Restores an entry of the open vault to the version before the change of the specified revision.
+///
+/// # Arguments
+/// * `id` - The identifier of the entry.
+/// * `index` - The index of the revision.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The restore is recorded as a new revision so it can be undone.
+#[tauri::command]
+async fn restore_revision(
+    id: i64,
+    index: usize,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    with_session(&state, |session| session.restore_revision(id, index)).map_err(|e| e.to_string())
+}
+
+/// Saves the open vault into the file it was opened from with the key it was opened with.
+///
+/// # Arguments
+/// * `options` - Optional key derivation parameters, cipher suite, compression and chunked encryption. The values not specified are kept from the existing file.
+///
+/// # Returns
+/// A `Result<(), String>` indicating success or failure with an error message.
+///
+/// # Remarks
+/// * The amount of rotating backups kept next to the file is read from the application settings.
+/// * The data is encrypted on a blocking thread without holding the vault state lock. The saves run one at a time and the vault can't be locked while it is saved.
+/// * The vault stays changed if it was changed while it was saved.
+#[tauri::command]
+async fn save_vault(
+    options: Option<SaveOptions>,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, VaultState>,
+) -> Result<(), String> {
+    let cfg_path = get_config_path(&app_handle).await;
+    let backup_count = get_app_config(&cfg_path).await.backup_count();
+    let options = options.unwrap_or_default();
+
+    let _save = state.lock_save().await;
+
+    let pending =
+        with_session(&state, |session| session.begin_save()).map_err(|e| e.to_string())?;
+
+    write_pending(&state, pending, backup_count, options)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Writes a save of the open vault started with [`VaultSession::begin_save`] on a blocking thread and ends the save.
+///
+/// # Arguments
+/// * `state` - The vault state of the application.
+/// * `pending` - The snapshot of the vault to save.
+/// * `backup_count` - The amount of rotating backups kept next to the file.
+/// * `options` - The save options of the file.
+///
+/// # Returns
+/// A `Result<(), anyhow::Error>` indicating success or failure.
+///
+/// # Remarks
+/// * The caller holds [`VaultState::lock_save`] so the saves run one at a time.
+async fn write_pending(
+    state: &VaultState,
+    pending: PendingSave,
+    backup_count: u32,
+    options: SaveOptions,
+) -> Result<(), anyhow::Error> {
+    let session_id = pending.session_id();
+    let revision = pending.revision();
+
+    let result = tokio::task::spawn_blocking(move || pending.write(backup_count, &options))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+
+    let mut session = state.lock()?;
+    match session.as_mut().filter(|f| f.id() == session_id) {
+        Some(session) => session.end_save(revision, result, backup_count),
+        None => result,
+    }
 }
 
 /// Sets the clipboard text to an empty `String` if the specified `current_supposed_value` matches the current clipboard content as `String`.
@@ -99,10 +1504,7 @@ async fn clear_clipboard(current_supposed_value: String) -> bool {
     let mut result = false;
 
     if current_text == current_supposed_value {
-        result = match clipboard.set_text("".to_string()) {
-            Ok(_) => true,
-            Err(_) => false,
-        };
+        result = clipboard.set_text("".to_string()).is_ok();
     }
 
     result
@@ -129,7 +1531,7 @@ struct StringListResult {
 /// A `StringListResult` value with the font families and a flag indicating whether an error occurred.
 #[tauri::command]
 async fn get_font_families_data() -> StringListResult {
-    let result = match get_font_families() {
+    match get_font_families() {
         Ok(family_vec) => StringListResult {
             error: false,
             value: family_vec,
@@ -138,9 +1540,7 @@ async fn get_font_families_data() -> StringListResult {
             error: true,
             value: Vec::new(),
         },
-    };
-
-    result
+    }
 }
 
 /// Generates an OTPAuth key with the specified OTPAuth URL.
@@ -163,22 +1563,52 @@ async fn gen_otpauth(otpauth: String) -> Auth2FAResult {
 ///
 /// * `file_name` - the file to load.
 /// * `password` - the password to use for decryption.
+/// * `key_file` - an optional key file to combine with the password.
 ///
 /// # Returns
 /// A `StringResult` indicating success or failure with the file contents decrypted.
+///
+/// # Remarks
+/// * The file data saved in an earlier format is upgraded to the current format version before it is returned.
+/// * The file is opened as the vault session like with [`open_vault`] so the lock timeout and the expiry checks apply to it.
+/// * Failed attempts are handled like in [`open_vault`].
 #[tauri::command]
-async fn load_file(file_name: String, password: String) -> StringResult {
-    let result = match decrypt_small_file(&file_name, &password) {
+async fn load_file(
+    file_name: String,
+    password: String,
+    key_file: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> StringResult {
+    let password = Zeroizing::new(password);
+
+    let handle = app_handle.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let key = CompositeKey::new(&password, key_file.as_deref())?;
+        lock::load_vault(
+            &handle.state::<VaultState>(),
+            &handle.state::<LockState>(),
+            &file_name,
+            key,
+        )
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+
+    if result.is_err() {
+        exit_on_attempt_limit(&app_handle, &app_handle.state::<LockState>()).await;
+    }
+
+    match result {
         Ok(v) => StringResult {
-            value: v,
+            value: v.to_string(),
             error: false,
         },
         Err(e) => StringResult {
             value: e.to_string(),
             error: true,
         },
-    };
-    result
+    }
 }
 
 /// Loads an image file requested by the frontend.
@@ -190,6 +1620,17 @@ async fn load_file(file_name: String, password: String) -> StringResult {
 /// A `Result<Vec<u8>, String>` indicating success or failure with the file contents.
 #[tauri::command(async)]
 async fn load_image_file(file_name: String) -> Result<Vec<u8>, String> {
+    read_file_contents(&file_name).await
+}
+
+/// Reads the contents of the specified file.
+/// # Arguments
+///
+/// * `file_name` - the file to read.
+///
+/// # Returns
+/// A `Result<Vec<u8>, String>` indicating success or failure with the file contents.
+async fn read_file_contents(file_name: &str) -> Result<Vec<u8>, String> {
     let mut file = match File::open(file_name).await {
         Ok(v) => v,
         Err(e) => {
@@ -201,9 +1642,7 @@ async fn load_image_file(file_name: String) -> Result<Vec<u8>, String> {
 
     match file.read_to_end(&mut buffer).await {
         Ok(_) => Ok(buffer),
-        Err(e) => {
-            return Err(e.to_string());
-        }
+        Err(e) => Err(e.to_string()),
     }
 }
 
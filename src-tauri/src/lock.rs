@@ -0,0 +1,413 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::encryption::{CompositeKey, DecryptionError};
+use crate::vault::{VaultSession, VaultState, VaultSummary};
+
+/// The longest delay in seconds before the next unlock attempt is allowed after failed attempts.
+const MAX_BACKOFF_SECONDS: u64 = 300;
+
+/// The failed unlock attempts persisted over the application restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FailureRecord {
+    /// The amount of consecutive failed unlock attempts.
+    failed_attempts: u32,
+    /// The time as seconds since the Unix epoch before which the next unlock attempt is not allowed.
+    retry_after: u64,
+}
+
+/// The lock status of the application.
+struct LockStatus {
+    /// The time of the latest user activity.
+    last_activity: Instant,
+    /// The file of the vault which was locked or `None` if no vault is locked.
+    locked_file: Option<String>,
+    /// The failed unlock attempts.
+    record: FailureRecord,
+    /// A value indicating whether an unlock attempt is in progress.
+    attempting: bool,
+    /// The file the failed unlock attempts are persisted into.
+    record_path: Option<PathBuf>,
+}
+
+/// The lock status information for the frontend.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockInfo {
+    /// A value indicating whether a vault is locked.
+    pub locked: bool,
+    /// The file of the locked vault.
+    pub locked_file: Option<String>,
+    /// The amount of consecutive failed unlock attempts.
+    pub failed_attempts: u32,
+    /// The amount of seconds before the next unlock attempt is allowed.
+    pub retry_after_seconds: u64,
+}
+
+/// The state tracking the user activity and the failed unlock attempts of the application.
+///
+/// # Remarks
+/// * The failed unlock attempts are persisted so restarting the application doesn't reset them.
+/// * Each failed attempt doubles the delay before the next attempt is allowed, up to five minutes.
+pub struct LockState(Mutex<LockStatus>);
+
+/// An unlock attempt reserved with [`LockState::begin_attempt`].
+///
+/// # Remarks
+/// * The reservation is released when the attempt is dropped, so a failed operation must be recorded before that.
+pub struct Attempt<'a> {
+    /// The lock state the attempt was reserved from.
+    lock: &'a LockState,
+}
+
+/// Gets the current time as seconds since the Unix epoch.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|f| f.as_secs())
+        .unwrap_or_default()
+}
+
+impl LockState {
+    /// Creates a new lock state.
+    /// # Arguments
+    ///
+    /// * `record_path` - the file the failed unlock attempts are persisted into; `None` to not persist them.
+    pub fn new(record_path: Option<PathBuf>) -> Self {
+        let record = record_path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        Self(Mutex::new(LockStatus {
+            last_activity: Instant::now(),
+            locked_file: None,
+            record,
+            attempting: false,
+            record_path,
+        }))
+    }
+
+    /// Locks the status for reading or modifying it.
+    fn status(&self) -> Result<MutexGuard<'_, LockStatus>, anyhow::Error> {
+        self.0
+            .lock()
+            .map_err(|_| anyhow!("The lock state is not available."))
+    }
+
+    /// Records user activity, postponing the idle lock.
+    pub fn touch(&self) {
+        if let Ok(mut status) = self.status() {
+            status.last_activity = Instant::now();
+        }
+    }
+
+    /// Gets the time elapsed since the latest user activity.
+    pub fn idle_time(&self) -> Duration {
+        self.status()
+            .map(|status| status.last_activity.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Gets the file of the locked vault or `None` if no vault is locked.
+    pub fn locked_file(&self) -> Option<String> {
+        self.status()
+            .ok()
+            .and_then(|status| status.locked_file.clone())
+    }
+
+    /// Gets the lock status information.
+    pub fn info(&self) -> Result<LockInfo, anyhow::Error> {
+        let status = self.status()?;
+
+        Ok(LockInfo {
+            locked: status.locked_file.is_some(),
+            locked_file: status.locked_file.clone(),
+            failed_attempts: status.record.failed_attempts,
+            retry_after_seconds: status.record.retry_after.saturating_sub(unix_time()),
+        })
+    }
+
+    /// Reserves an unlock attempt if one is allowed now.
+    /// # Returns
+    /// The reserved attempt; an error telling how long to wait or that another attempt is in progress otherwise.
+    ///
+    /// # Remarks
+    /// * The delay and the attempt in progress are checked and the attempt is reserved under the same lock, so parallel attempts can't bypass the delay.
+    pub fn begin_attempt(&self) -> Result<Attempt<'_>, anyhow::Error> {
+        let mut status = self.status()?;
+
+        let wait = status.record.retry_after.saturating_sub(unix_time());
+        if wait > 0 {
+            return Err(anyhow!(
+                "Too many failed unlock attempts. Try again in {} seconds.",
+                wait
+            ));
+        }
+
+        if status.attempting {
+            return Err(anyhow!("Another unlock attempt is in progress."));
+        }
+
+        status.attempting = true;
+
+        Ok(Attempt { lock: self })
+    }
+
+    /// Records a failed unlock attempt and delays the next attempt.
+    /// # Returns
+    /// The amount of consecutive failed unlock attempts.
+    fn record_failure(&self) -> Result<u32, anyhow::Error> {
+        let mut status = self.status()?;
+
+        status.record.failed_attempts = status.record.failed_attempts.saturating_add(1);
+        let backoff = 1u64
+            .checked_shl(status.record.failed_attempts - 1)
+            .unwrap_or(u64::MAX)
+            .min(MAX_BACKOFF_SECONDS);
+        status.record.retry_after = unix_time() + backoff;
+
+        status.persist()?;
+
+        Ok(status.record.failed_attempts)
+    }
+
+    /// Resets the failed attempts after a password was accepted.
+    /// # Returns
+    /// `Ok(())` if the state was persisted; an error otherwise.
+    fn reset_failures(&self) -> Result<(), anyhow::Error> {
+        let mut status = self.status()?;
+
+        status.record = FailureRecord::default();
+
+        status.persist()
+    }
+
+    /// Records the result of an operation checking a password.
+    /// # Arguments
+    ///
+    /// * `result` - the result of the operation.
+    ///
+    /// # Returns
+    /// `Ok(())` if the state was persisted; an error otherwise.
+    ///
+    /// # Remarks
+    /// * Only an invalid password counts as a failed attempt; a success resets the failed attempts and other errors are ignored.
+    fn record_attempt<T>(&self, result: &Result<T, anyhow::Error>) -> Result<(), anyhow::Error> {
+        match result {
+            Ok(_) => self.reset_failures(),
+            Err(e)
+                if e.downcast_ref::<DecryptionError>()
+                    == Some(&DecryptionError::InvalidPassword) =>
+            {
+                self.record_failure().map(|_| ())
+            }
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Records an unlocked vault, clearing the locked vault and resetting the idle time.
+    /// # Returns
+    /// `Ok(())` if the state was updated; an error otherwise.
+    pub fn record_unlock(&self) -> Result<(), anyhow::Error> {
+        let mut status = self.status()?;
+        status.locked_file = None;
+        status.last_activity = Instant::now();
+
+        Ok(())
+    }
+}
+
+impl Attempt<'_> {
+    /// Records the result of an operation checking a password, see [`LockState::record_attempt`].
+    pub fn finish<T>(self, result: &Result<T, anyhow::Error>) -> Result<(), anyhow::Error> {
+        self.lock.record_attempt(result)
+    }
+
+    /// Records a failed attempt.
+    /// # Returns
+    /// The amount of consecutive failed unlock attempts.
+    pub fn fail(self) -> Result<u32, anyhow::Error> {
+        self.lock.record_failure()
+    }
+
+    /// Records a successful attempt, resetting the failed attempts.
+    pub fn succeed(self) -> Result<(), anyhow::Error> {
+        self.lock.reset_failures()
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut status) = self.lock.status() {
+            status.attempting = false;
+        }
+    }
+}
+
+impl LockStatus {
+    /// Saves the failed unlock attempts into the record file.
+    fn persist(&self) -> Result<(), anyhow::Error> {
+        if let Some(path) = &self.record_path {
+            fs::write(path, serde_json::to_vec(&self.record)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Locks the open vault, wiping its decrypted data and key from the memory.
+/// # Arguments
+///
+/// * `vault` - the vault state of the application.
+/// * `lock` - the lock state of the application.
+///
+/// # Returns
+/// The file of the locked vault or `None` if no vault was open.
+///
+/// # Remarks
+/// * The vault is unlocked by opening its file again with the password.
+/// * The unsaved changes are discarded; the caller saves the vault first.
+/// * A vault being saved is not locked before the save is completed.
+pub fn lock_vault(vault: &VaultState, lock: &LockState) -> Result<Option<String>, anyhow::Error> {
+    let mut vault = vault.lock()?;
+
+    let file_path = match vault.as_ref() {
+        Some(session) if session.saving() => {
+            return Err(anyhow!(
+                "The vault {} is being saved. Lock it after the save is completed.",
+                session.file_path()
+            ));
+        }
+        Some(session) => session.file_path().to_string(),
+        None => return Ok(None),
+    };
+
+    // Dropping the session zeroes the decrypted data and the key.
+    *vault = None;
+    drop(vault);
+
+    lock.status()?.locked_file = Some(file_path.clone());
+
+    Ok(Some(file_path))
+}
+
+/// Opens the specified file into the vault state if the failed unlock attempts allow it.
+/// # Arguments
+///
+/// * `vault` - the vault state of the application.
+/// * `lock` - the lock state of the application.
+/// * `file_path` - the file to open.
+/// * `key` - the key of the file.
+///
+/// # Returns
+/// The non-secret information of the opened vault.
+///
+/// # Remarks
+/// * Only an invalid password counts as a failed attempt; e.g. a missing file doesn't.
+pub fn open_vault(
+    vault: &VaultState,
+    lock: &LockState,
+    file_path: &str,
+    key: CompositeKey,
+) -> Result<VaultSummary, anyhow::Error> {
+    open_session(vault, lock, file_path, key, |session| Ok(session.summary()))
+}
+
+/// Opens the specified file into the vault state like [`open_vault`] for a frontend editing the data itself.
+/// # Returns
+/// The file data with the secrets as JSON.
+pub fn load_vault(
+    vault: &VaultState,
+    lock: &LockState,
+    file_path: &str,
+    key: CompositeKey,
+) -> Result<Zeroizing<String>, anyhow::Error> {
+    open_session(vault, lock, file_path, key, VaultSession::to_json)
+}
+
+/// Opens the specified file into the vault state if the failed unlock attempts allow it.
+/// # Arguments
+///
+/// * `vault` - the vault state of the application.
+/// * `lock` - the lock state of the application.
+/// * `file_path` - the file to open.
+/// * `key` - the key of the file.
+/// * `read` - the function reading the result from the opened session before it is stored into the vault state.
+fn open_session<T>(
+    vault: &VaultState,
+    lock: &LockState,
+    file_path: &str,
+    key: CompositeKey,
+    read: impl FnOnce(&VaultSession) -> Result<T, anyhow::Error>,
+) -> Result<T, anyhow::Error> {
+    let attempt = lock.begin_attempt()?;
+
+    let result = VaultSession::open(file_path, key);
+    attempt.finish(&result)?;
+    let session = result?;
+
+    let value = read(&session)?;
+    *vault.lock()? = Some(session);
+    lock.record_unlock()?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_attempt_is_rejected() {
+        let lock = LockState::new(None);
+
+        let attempt = lock.begin_attempt().unwrap();
+        assert!(lock.begin_attempt().is_err());
+        attempt
+            .finish::<()>(&Err(DecryptionError::InvalidPassword.into()))
+            .unwrap();
+
+        // The failed attempt delays the next one.
+        assert!(lock.begin_attempt().is_err());
+        assert_eq!(lock.info().unwrap().failed_attempts, 1);
+
+        lock.status().unwrap().record.retry_after = 0;
+        let attempt = lock.begin_attempt().unwrap();
+        attempt.succeed().unwrap();
+        assert_eq!(lock.info().unwrap().failed_attempts, 0);
+        drop(lock.begin_attempt().unwrap());
+        assert!(lock.begin_attempt().is_ok());
+    }
+}
@@ -0,0 +1,364 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::file_data::{
+    DataEntry, FileData, GeneralEntry, GeneralEntryType, TAG_SEPARATOR, UNCATEGORIZED_ID,
+    UNCATEGORIZED_NAME,
+};
+
+/// The current version of the file data format.
+pub const FILE_DATA_VERSION: u32 = 1;
+
+/// A migration step upgrading the file data from a version to the next one.
+type MigrationStep = fn(&mut FileData);
+
+/// The migration steps indexed by the version they upgrade the file data from.
+const MIGRATIONS: [MigrationStep; FILE_DATA_VERSION as usize] = [migrate_categories_to_tags];
+
+/// Parses the specified file data JSON and upgrades it to the current file data format version.
+/// # Arguments
+///
+/// * `json` - the decrypted JSON data of a file.
+///
+/// # Returns
+/// The file data in the current format version or an error if the data can't be read.
+///
+/// # Remarks
+/// * The legacy array format with the entries and the metadata in the same array and the version-less object format are both read as version 0.
+/// * The migration steps from the version of the data up to [`FILE_DATA_VERSION`] are run in order.
+/// * Data saved by a newer version of the software is rejected.
+pub fn migrate_file_data(json: &str) -> Result<FileData, anyhow::Error> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| anyhow!("Invalid JSON data: {}", e))?;
+
+    let mut file_data = match value {
+        Value::Array(items) => from_array_format(items)?,
+        Value::Object(_) => serde_json::from_value::<FileData>(value)
+            .map_err(|e| anyhow!("Invalid file data: {}", e))?,
+        _ => return Err(anyhow!("The file data is neither an object nor an array.")),
+    };
+
+    if file_data.version > FILE_DATA_VERSION {
+        return Err(anyhow!(
+            "The file data version {} is newer than the supported version {}.",
+            file_data.version,
+            FILE_DATA_VERSION
+        ));
+    }
+
+    for step in &MIGRATIONS[file_data.version as usize..] {
+        step(&mut file_data);
+        file_data.version += 1;
+    }
+
+    Ok(file_data)
+}
+
+/// Reads the legacy array format where the entries and the metadata are stored in the same array.
+/// # Arguments
+///
+/// * `items` - the entries and the metadata of the file.
+///
+/// # Returns
+/// The version 0 file data or an error if an item is invalid.
+fn from_array_format(items: Vec<Value>) -> Result<FileData, anyhow::Error> {
+    let mut entries = Vec::new();
+    let mut meta_data = Vec::new();
+
+    for (index, item) in items.into_iter().enumerate() {
+        // The metadata items are told apart from the entries by their type.
+        if item.get("type").is_some() {
+            meta_data.push(
+                serde_json::from_value(item)
+                    .map_err(|e| anyhow!("Metadata item {} is invalid: {}", index, e))?,
+            );
+        } else {
+            entries.push(
+                serde_json::from_value(item)
+                    .map_err(|e| anyhow!("Entry {} is invalid: {}", index, e))?,
+            );
+        }
+    }
+
+    Ok(FileData {
+        entries,
+        meta_data: Some(meta_data),
+        data_options: None,
+        version: 0,
+        trash: None,
+        extra: serde_json::Map::new(),
+    })
+}
+
+/// Upgrades the file data from version 0 to version 1.
+///
+/// # Remarks
+/// * The category names are added into the tags and the entries are tagged with the name of their category.
+/// * The tags used by the entries are added into the tags metadata if missing.
+/// * The category for the entries without a category is added.
+/// * The duplicate categories are merged; see [`clean_duplicates`].
+fn migrate_categories_to_tags(file_data: &mut FileData) {
+    let meta_data = file_data.meta_data.get_or_insert_with(Vec::new);
+    if !meta_data
+        .iter()
+        .any(|f| f.entry_type == GeneralEntryType::Tags)
+    {
+        meta_data.push(GeneralEntry {
+            entry_type: GeneralEntryType::Tags,
+            values: Vec::new(),
+        });
+    }
+
+    let category_names: HashMap<i64, String> = file_data
+        .entries
+        .iter()
+        .filter(|f| f.is_category())
+        .map(|f| (f.id, f.name.clone()))
+        .collect();
+
+    for entry in file_data.entries.iter_mut() {
+        let Some(category_name) = category_names.get(&entry.parent_id) else {
+            continue;
+        };
+
+        if !entry.tag_list().any(|tag| tag == category_name) {
+            entry.tags = Some(match entry.tags.as_deref() {
+                Some(tags) if !tags.is_empty() => {
+                    format!("{}{}{}", category_name, TAG_SEPARATOR, tags)
+                }
+                _ => category_name.clone(),
+            });
+        }
+    }
+
+    let mut used_tags: Vec<String> = file_data
+        .entries
+        .iter()
+        .filter(|f| f.is_category())
+        .map(|f| f.name.clone())
+        .collect();
+    for entry in &file_data.entries {
+        used_tags.extend(
+            entry
+                .tag_list()
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    if let Some(tags) = file_data
+        .meta_data
+        .iter_mut()
+        .flatten()
+        .find(|f| f.entry_type == GeneralEntryType::Tags)
+    {
+        for tag in used_tags {
+            if !tags.values.contains(&tag) {
+                tags.values.push(tag);
+            }
+        }
+    }
+
+    if !file_data.entries.iter().any(|f| f.id == UNCATEGORIZED_ID) {
+        file_data.entries.push(DataEntry::new_category(
+            UNCATEGORIZED_ID,
+            UNCATEGORIZED_NAME,
+        ));
+    }
+
+    // The categories seem to have been able to replicate in the previous format.
+    clean_duplicates(&mut file_data.entries);
+}
+
+/// Removes the duplicate categories from the data by merging them into the first category with the same name.
+/// # Arguments
+///
+/// * `entries` - the entries and categories to clean.
+fn clean_duplicates(entries: &mut Vec<DataEntry>) {
+    let mut first_ids: HashMap<String, i64> = HashMap::new();
+    let mut parent_id_updates: HashMap<i64, i64> = HashMap::new();
+
+    for category in entries.iter().filter(|f| f.is_category()) {
+        let first_id = *first_ids
+            .entry(category.name.clone())
+            .or_insert(category.id);
+        if first_id != category.id {
+            parent_id_updates.insert(category.id, first_id);
+        }
+    }
+
+    for entry in entries.iter_mut() {
+        if let Some(new_id) = parent_id_updates.get(&entry.parent_id) {
+            entry.parent_id = *new_id;
+        }
+    }
+
+    entries.retain(|f| !(f.is_category() && parent_id_updates.contains_key(&f.id)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::{decrypt_small_file, CompositeKey};
+
+    /// Decrypts the sample file shipped with the repository.
+    fn sample_json() -> String {
+        let file_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../Sample/Sample File.pkd");
+        let key = CompositeKey::new("abc", None).unwrap();
+        decrypt_small_file(file_path, &key).unwrap()
+    }
+
+    /// Finds an entry by its identifier.
+    fn entry(file_data: &FileData, id: i64) -> &DataEntry {
+        file_data.entries.iter().find(|f| f.id == id).unwrap()
+    }
+
+    #[test]
+    fn sample_file_is_migrated() {
+        let json = sample_json();
+        let original = FileData::from_json(&json).unwrap();
+        assert_eq!(original.version, 0);
+
+        let file_data = migrate_file_data(&json).unwrap();
+
+        assert_eq!(file_data.version, FILE_DATA_VERSION);
+        assert_eq!(file_data.entries.len(), original.entries.len() + 1);
+        file_data.validate().unwrap();
+
+        let uncategorized = entry(&file_data, UNCATEGORIZED_ID);
+        assert_eq!(uncategorized.name, UNCATEGORIZED_NAME);
+        assert!(uncategorized.is_category());
+
+        // "X (formerly Twitter)" is in the "Social Media" category and tagged "Social media".
+        assert_eq!(
+            entry(&file_data, 2).tags.as_deref(),
+            Some("Social Media|Social media")
+        );
+
+        // "Github" already had its category name as a tag.
+        assert_eq!(entry(&file_data, 6).tags, entry(&original, 6).tags);
+
+        let tags = &file_data.tags().unwrap().values;
+        for category in original.entries.iter().filter(|f| f.is_category()) {
+            assert!(tags.contains(&category.name));
+        }
+        assert!(!tags.iter().any(|f| f == UNCATEGORIZED_NAME));
+    }
+
+    #[test]
+    fn migration_is_idempotent() {
+        let file_data = migrate_file_data(&sample_json()).unwrap();
+        let json = serde_json::to_string(&file_data).unwrap();
+
+        let migrated = migrate_file_data(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&migrated).unwrap(), json);
+    }
+
+    #[test]
+    fn array_format_is_migrated() {
+        let json = r#"[
+            { "id": 1, "parentId": -1, "name": "Mail" },
+            { "id": 2, "parentId": 1, "name": "Provider", "password": "secret", "tags": "Work" },
+            { "type": "tags", "values": ["Work"] }
+        ]"#;
+
+        let file_data = migrate_file_data(json).unwrap();
+
+        assert_eq!(file_data.version, FILE_DATA_VERSION);
+        assert_eq!(entry(&file_data, 2).tags.as_deref(), Some("Mail|Work"));
+        assert_eq!(entry(&file_data, 2).password.as_deref(), Some("secret"));
+        assert_eq!(file_data.tags().unwrap().values, vec!["Work", "Mail"]);
+        assert!(file_data.entries.iter().any(|f| f.id == UNCATEGORIZED_ID));
+        file_data.validate().unwrap();
+    }
+
+    #[test]
+    fn duplicate_categories_are_merged() {
+        let json = r#"{
+            "entries": [
+                { "id": 1, "parentId": -1, "name": "Mail" },
+                { "id": 2, "parentId": 1, "name": "First" },
+                { "id": 3, "parentId": -1, "name": "Mail" },
+                { "id": 4, "parentId": 3, "name": "Second", "tags": "" },
+                { "id": 5, "parentId": -1, "name": "Mail" },
+                { "id": 6, "parentId": 5, "name": "Third" }
+            ]
+        }"#;
+
+        let file_data = migrate_file_data(json).unwrap();
+
+        let categories: Vec<i64> = file_data
+            .entries
+            .iter()
+            .filter(|f| f.is_category())
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(categories, vec![1, UNCATEGORIZED_ID]);
+
+        for id in [2, 4, 6] {
+            assert_eq!(entry(&file_data, id).parent_id, 1);
+            assert_eq!(entry(&file_data, id).tags.as_deref(), Some("Mail"));
+        }
+
+        assert_eq!(file_data.tags().unwrap().values, vec!["Mail"]);
+        file_data.validate().unwrap();
+    }
+
+    #[test]
+    fn current_version_is_not_changed() {
+        let json = r#"{
+            "entries": [
+                { "id": 1, "parentId": -1, "name": "Mail" },
+                { "id": 2, "parentId": 1, "name": "Provider" }
+            ],
+            "metaData": [{ "type": "tags", "values": [] }],
+            "version": 1
+        }"#;
+
+        let file_data = migrate_file_data(json).unwrap();
+
+        assert_eq!(file_data.entries.len(), 2);
+        assert_eq!(entry(&file_data, 2).tags, None);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let json = r#"{ "entries": [], "version": 99 }"#;
+
+        assert!(migrate_file_data(json).is_err());
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        assert!(migrate_file_data("not json").is_err());
+        assert!(migrate_file_data("42").is_err());
+        assert!(migrate_file_data(r#"[{ "id": "1" }]"#).is_err());
+    }
+}
@@ -0,0 +1,1143 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// The maximum amount of characters analyzed; the rest of a longer password is ignored.
+const MAX_PASSWORD_LENGTH: usize = 100;
+
+/// The amount of guesses per character of an unmatched part of a password.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// The guesses added for each additional part in a password of several parts.
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+
+/// The minimum guesses of a single character part of a password.
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+
+/// The minimum guesses of a multi-character part of a password.
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+
+/// The minimum distance in years of a year or a date from the current year.
+const MIN_YEAR_SPACE: i32 = 20;
+
+/// The amount of keys on the keyboard a pattern can start from.
+const KEYBOARD_STARTING_POSITIONS: f64 = 94.0;
+
+/// The average amount of neighbours of a key on the keyboard.
+const KEYBOARD_AVERAGE_DEGREE: f64 = 4.6;
+
+/// The tolerance of the guess thresholds of the score.
+const SCORE_DELTA: f64 = 5.0;
+
+/// The keyboard rows of the US QWERTY layout.
+const KEYBOARD_ROWS: [&str; 4] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+/// The keyboard rows of the US QWERTY layout with the shift key pressed.
+const KEYBOARD_SHIFTED_ROWS: [&str; 4] = [
+    "~!@#$%^&*()_+",
+    "QWERTYUIOP{}|",
+    "ASDFGHJKL:\"",
+    "ZXCVBNM<>?",
+];
+
+/// The neighbour directions of a key on the slanted keyboard coordinates.
+const KEYBOARD_DIRECTIONS: [(i32, i32); 6] = [(-1, 0), (1, 0), (0, -1), (1, -1), (0, 1), (-1, 1)];
+
+/// The l33t substitutions; the characters with two meanings have an alternative.
+const L33T_TABLE: [(char, char, char); 20] = [
+    ('4', 'a', 'a'),
+    ('@', 'a', 'a'),
+    ('8', 'b', 'b'),
+    ('(', 'c', 'c'),
+    ('{', 'c', 'c'),
+    ('[', 'c', 'c'),
+    ('<', 'c', 'c'),
+    ('3', 'e', 'e'),
+    ('6', 'g', 'g'),
+    ('9', 'g', 'g'),
+    ('1', 'i', 'l'),
+    ('!', 'i', 'i'),
+    ('|', 'i', 'l'),
+    ('0', 'o', 'o'),
+    ('$', 's', 's'),
+    ('5', 's', 's'),
+    ('7', 't', 'l'),
+    ('+', 't', 't'),
+    ('%', 'x', 'x'),
+    ('2', 'z', 'z'),
+];
+
+/// A reduced list of the most common passwords ordered by their popularity, a few hundred instead of the 30 000 of zxcvbn.
+const COMMON_PASSWORDS: &str = "\
+    123456 password 12345678 qwerty 123456789 12345 1234 111111 1234567 dragon 123123 baseball \
+    abc123 football monkey letmein 696969 shadow master 666666 qwertyuiop 123321 mustang 1234567890 \
+    michael 654321 superman 1qaz2wsx 7777777 121212 000000 qazwsx 123qwe killer trustno1 jordan \
+    jennifer zxcvbnm asdfgh hunter buster soccer harley batman andrew tigger sunshine iloveyou \
+    charlie robert thomas hockey ranger daniel starwars klaster 112233 george computer michelle \
+    jessica pepper 1111 zxcvbn 555555 11111111 131313 freedom 777777 pass maggie 159753 aaaaaa \
+    ginger princess joshua cheese amanda summer love ashley nicole chelsea biteme matthew access \
+    yankees 987654321 dallas austin thunder taylor matrix minecraft william corvette hello martin \
+    heather secret merlin diamond 1234qwer hammer silver 222222 88888888 anthony justin test bailey \
+    q1w2e3r4t5 patrick internet scooter orange 11111 golfer cookie richard samantha bigdog guitar \
+    jackson whatever mickey chicken sparky snoopy maverick phoenix camaro peanut morgan welcome \
+    falcon cowboy ferrari samsung andrea smokey steelers joseph mercedes dakota arsenal eagles \
+    melissa boomer booboo spider nascar monster tigers yellow xxxxxx 123123123 gateway marina diablo \
+    bulldog qwer1234 compaq purple hardcore banana junior hannah 123654 porsche lakers iceman money \
+    cowboys 987654 london tennis 999999 ncc1701 coffee scooby 0000 miller boston q1w2e3r4 brandon \
+    yamaha chester mother forever johnny edward 333333 oliver redsox player nikita knight fender \
+    barney midnight please brandy chicago badboy slayer rangers charles angel flower bigdaddy rabbit \
+    wizard jasper enter rachel chris steven winner adidas victoria natasha 1q2w3e4r jasmine winter \
+    prince marine fishing cocacola casper james 232323 raiders 888888 marlboro gandalf asdfasdf \
+    crystal 87654321 12344321 golden 8675309 admin passw0rd password1 password123 qwerty123 welcome1 \
+    abc12345 changeme login letmein1 administrator root guest default qwerty1 iloveyou1 princess1 \
+    football1 monkey1 dragon1 sunshine1 shadow1";
+
+/// A reduced list of common English words ordered by their frequency, a few hundred instead of the 30 000 of zxcvbn.
+const ENGLISH_WORDS: &str = "\
+    the of and to in is you that it he was for on are as with his they at be this have from or one \
+    had by word but not what all were we when your can said there use each which she do how their if \
+    will up other about out many then them these so some her would make like him into time has look \
+    two more write go see number no way could people my than first water been call who oil its now \
+    find long down day did get come made may part over new sound take only little work know place \
+    year live me back give most very after thing our just name good sentence man think say great \
+    where help through much before line right too mean old any same tell boy follow came want show \
+    also around form three small set put end does another well large must big even such because turn \
+    here why ask went men read need land different home us move try kind hand picture again change \
+    off play spell air away animal house point page letter answer found study still learn should \
+    world high every near add food between own below country plant last school father keep tree \
+    never start city earth eye light thought head under story saw left few while along might close \
+    something seem next hard open example begin life always those both paper together got group \
+    often run important until children side feet car mile night walk white sea began grow took river \
+    four carry state once book hear stop without second later miss idea enough eat face watch far \
+    really almost let above girl sometimes mountain cut young talk soon list song being leave family \
+    horse correct battery staple spring autumn monday friday sunday dog cat red blue green black \
+    gold star moon sun fire king queen happy lucky sweet baby heart god jesus music sport beach \
+    ocean snow rain storm dream magic power office company system server bank";
+
+/// A warning about a weak password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Warning {
+    /// The password is a straight row of keys, e.g. `qwerty`.
+    StraightRowsOfKeysAreEasy,
+    /// The password is a short keyboard pattern, e.g. `qazxsw`.
+    ShortKeyboardPatternsAreEasy,
+    /// The password repeats a character, e.g. `aaa`.
+    RepeatsLikeAaaAreEasy,
+    /// The password repeats a string, e.g. `abcabc`.
+    RepeatsLikeAbcAbcAreEasy,
+    /// The password is a sequence, e.g. `abc` or `6543`.
+    SequencesLikeAbcAreEasy,
+    /// The password is a recent year.
+    RecentYearsAreEasy,
+    /// The password is a date.
+    DatesAreOftenEasy,
+    /// The password is one of the ten most common passwords.
+    TopTenPassword,
+    /// The password is one of the hundred most common passwords.
+    TopHundredPassword,
+    /// The password is a common password.
+    CommonPassword,
+    /// The password is similar to a common password.
+    SimilarToCommonPassword,
+    /// The password is a single word.
+    AWordByItselfIsEasy,
+    /// The password contains a word related to the user or the entry.
+    ContainsUserInput,
+}
+
+/// A suggestion for a stronger password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Suggestion {
+    /// Use a few words and avoid common phrases.
+    UseAFewWords,
+    /// Symbols, digits or upper-case letters are not needed.
+    NoNeedForSymbols,
+    /// Add another word or two; uncommon words are better.
+    AddAnotherWord,
+    /// Capitalization does not help very much.
+    CapitalizationDoesntHelp,
+    /// All upper-case is almost as easy to guess as all lower-case.
+    AllUppercaseIsAlmostAsEasy,
+    /// Reversed words are not much harder to guess.
+    ReversedWordsArentMuchHarder,
+    /// Predictable substitutions like `@` instead of `a` do not help very much.
+    PredictableSubstitutions,
+    /// Use a longer keyboard pattern with more turns.
+    UseLongerKeyboardPattern,
+    /// Avoid repeated words and characters.
+    AvoidRepeatedWordsAndCharacters,
+    /// Avoid sequences.
+    AvoidSequences,
+    /// Avoid recent years.
+    AvoidRecentYears,
+    /// Avoid years associated with the user.
+    AvoidYearsAssociatedWithYou,
+    /// Avoid dates and years associated with the user.
+    AvoidDatesAndYearsAssociatedWithYou,
+}
+
+/// The estimated times in seconds to crack a password in different attack scenarios.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrackTimes {
+    /// An online attack against a service limiting the attempts to 100 per hour.
+    pub online_throttling: f64,
+    /// An online attack against a service allowing 10 attempts per second.
+    pub online_no_throttling: f64,
+    /// An offline attack against a slow password hash, 10 000 guesses per second.
+    pub offline_slow_hashing: f64,
+    /// An offline attack against a fast hash, 10 billion guesses per second.
+    pub offline_fast_hashing: f64,
+}
+
+/// The feedback about a weak password.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feedback {
+    /// The main weakness of the password if any.
+    pub warning: Option<Warning>,
+    /// The suggestions for a stronger password.
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// The estimated strength of a password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrengthEstimate {
+    /// The score from 0 (too guessable) to 4 (very unguessable).
+    pub score: u8,
+    /// The estimated amount of guesses needed to crack the password.
+    pub guesses: f64,
+    /// The base 10 logarithm of the guesses.
+    pub guesses_log10: f64,
+    /// The estimated times in seconds to crack the password.
+    pub crack_times_seconds: CrackTimes,
+    /// The warning and the suggestions; empty for the strong passwords.
+    pub feedback: Feedback,
+}
+
+/// The dictionary a word was found from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dictionary {
+    Passwords,
+    EnglishWords,
+    UserInputs,
+}
+
+/// The kind of a guessable part of a password.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pattern {
+    Dictionary {
+        dictionary: Dictionary,
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+    },
+    Spatial {
+        turns: usize,
+    },
+    Sequence,
+    Repeat {
+        unit_length: usize,
+    },
+    Year,
+    Date,
+    Bruteforce,
+}
+
+/// A guessable part of a password from the character `i` to the character `j` inclusive.
+#[derive(Debug, Clone)]
+struct Match {
+    i: usize,
+    j: usize,
+    pattern: Pattern,
+    guesses_log10: f64,
+}
+
+impl Match {
+    fn new(i: usize, j: usize, pattern: Pattern, guesses: f64) -> Self {
+        Self {
+            i,
+            j,
+            pattern,
+            guesses_log10: guesses.max(1.0).log10(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.j - self.i + 1
+    }
+}
+
+/// Estimates the strength of a password the way the zxcvbn library does.
+/// # Arguments
+///
+/// * `password` - the password to estimate.
+/// * `user_inputs` - the words related to the user or the entry, e.g. the user name and the domain. These are treated as the most guessable words.
+///
+/// # Returns
+/// The score, the estimated guesses, the crack time estimates and the feedback.
+///
+/// # Remarks
+/// * The password is matched against common passwords, English words, the user inputs, reversed and l33t spelled words, keyboard patterns, sequences, repeats, years and dates. The least guessable combination of the matches and the unmatched characters gives the estimate.
+/// * Only the first 100 characters of a longer password are analyzed.
+/// * This is a reduced estimator: the built-in dictionaries hold only a few hundred common passwords and English words
+///   instead of the ranked lists of zxcvbn. A password made of less common passwords, words or names isn't matched
+///   against a dictionary, so its score is an upper bound and may be higher than the score of zxcvbn.
+pub fn estimate_strength(password: &str, user_inputs: &[String]) -> StrengthEstimate {
+    let chars: Vec<char> = password.chars().take(MAX_PASSWORD_LENGTH).collect();
+
+    let user_dictionary: HashMap<String, usize> = user_inputs
+        .iter()
+        .map(|input| input.trim().to_lowercase())
+        .filter(|input| !input.is_empty())
+        .enumerate()
+        .fold(HashMap::new(), |mut dictionary, (index, input)| {
+            dictionary.entry(input).or_insert(index + 1);
+            dictionary
+        });
+
+    let (guesses_log10, sequence) = most_guessable(&chars, &user_dictionary);
+    let guesses = 10f64.powf(guesses_log10);
+
+    let score = if guesses < 1e3 + SCORE_DELTA {
+        0
+    } else if guesses < 1e6 + SCORE_DELTA {
+        1
+    } else if guesses < 1e8 + SCORE_DELTA {
+        2
+    } else if guesses < 1e10 + SCORE_DELTA {
+        3
+    } else {
+        4
+    };
+
+    StrengthEstimate {
+        score,
+        guesses,
+        guesses_log10,
+        crack_times_seconds: CrackTimes {
+            online_throttling: guesses / (100.0 / 3600.0),
+            online_no_throttling: guesses / 10.0,
+            offline_slow_hashing: guesses / 1e4,
+            offline_fast_hashing: guesses / 1e10,
+        },
+        feedback: feedback(&chars, score, &sequence),
+    }
+}
+
+/// Finds the least guessable combination of matches covering the password.
+///
+/// # Returns
+/// The base 10 logarithm of the guesses and the matches of the combination.
+fn most_guessable(chars: &[char], user_dictionary: &HashMap<String, usize>) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+
+    let mut matches = omnimatch(chars, user_dictionary);
+    for m in matches.iter_mut() {
+        if m.len() < n {
+            let min_guesses = if m.len() == 1 {
+                MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+            } else {
+                MIN_SUBMATCH_GUESSES_MULTI_CHAR
+            };
+            m.guesses_log10 = m.guesses_log10.max(min_guesses.log10());
+        }
+    }
+
+    for i in 0..n {
+        for j in i..n {
+            let length = j - i + 1;
+            let min_guesses = if length == 1 {
+                MIN_SUBMATCH_GUESSES_SINGLE_CHAR + 1.0
+            } else {
+                MIN_SUBMATCH_GUESSES_MULTI_CHAR + 1.0
+            };
+            matches.push(Match {
+                i,
+                j,
+                pattern: Pattern::Bruteforce,
+                guesses_log10: (length as f64 * BRUTEFORCE_CARDINALITY.log10())
+                    .max(min_guesses.log10()),
+            });
+        }
+    }
+
+    let mut by_end: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (index, m) in matches.iter().enumerate() {
+        by_end[m.j].push(index);
+    }
+
+    // best[k][l] is the smallest product of guesses of `l` matches covering the characters 0..=k.
+    let mut best: Vec<Vec<Option<(f64, usize)>>> = vec![vec![None; n + 1]; n];
+    for k in 0..n {
+        for &index in &by_end[k] {
+            let m = &matches[index];
+            let mut candidates = Vec::new();
+            if m.i == 0 {
+                candidates.push((1, m.guesses_log10));
+            } else {
+                for (l, previous) in best[m.i - 1].iter().enumerate() {
+                    if let Some((product, _)) = previous {
+                        candidates.push((l + 1, product + m.guesses_log10));
+                    }
+                }
+            }
+
+            for (l, product) in candidates {
+                if best[k][l].is_none_or(|(current, _)| product < current) {
+                    best[k][l] = Some((product, index));
+                }
+            }
+        }
+    }
+
+    let (length, guesses_log10) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(l, entry)| entry.map(|(product, _)| (l, total_guesses_log10(l, product))))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((1, n as f64));
+
+    let mut sequence = Vec::with_capacity(length);
+    let mut k = n;
+    let mut l = length;
+    while k > 0 && l > 0 {
+        let Some((_, index)) = best[k - 1][l] else {
+            break;
+        };
+        let m = matches[index].clone();
+        k = m.i;
+        l -= 1;
+        sequence.push(m);
+    }
+    sequence.reverse();
+
+    (guesses_log10, sequence)
+}
+
+/// Calculates the base 10 logarithm of `l! * product + 10000^(l - 1)` for a sequence of `l` matches.
+fn total_guesses_log10(length: usize, product_log10: f64) -> f64 {
+    let factorial_log10: f64 = (2..=length).map(|i| (i as f64).log10()).sum();
+    let sequence_log10 = factorial_log10 + product_log10;
+    let growing_log10 = (length as f64 - 1.0) * MIN_GUESSES_BEFORE_GROWING_SEQUENCE.log10();
+    let (high, low) = if sequence_log10 > growing_log10 {
+        (sequence_log10, growing_log10)
+    } else {
+        (growing_log10, sequence_log10)
+    };
+
+    high + (1.0 + 10f64.powf(low - high)).log10()
+}
+
+/// Finds all the guessable parts of a password.
+fn omnimatch(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<Match> {
+    let mut matches = dictionary_matches(chars, user_dictionary);
+    matches.extend(spatial_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(repeat_matches(chars, user_dictionary));
+    matches.extend(date_matches(chars));
+    matches
+}
+
+/// Gets the built-in dictionaries as maps from the word to its rank.
+fn ranked_dictionaries() -> &'static [(Dictionary, HashMap<String, usize>); 2] {
+    static DICTIONARIES: OnceLock<[(Dictionary, HashMap<String, usize>); 2]> = OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        let rank = |words: &str| {
+            words.split_whitespace().enumerate().fold(
+                HashMap::new(),
+                |mut dictionary, (index, word)| {
+                    dictionary.entry(word.to_string()).or_insert(index + 1);
+                    dictionary
+                },
+            )
+        };
+
+        [
+            (Dictionary::Passwords, rank(COMMON_PASSWORDS)),
+            (Dictionary::EnglishWords, rank(ENGLISH_WORDS)),
+        ]
+    })
+}
+
+/// Finds the words of the dictionaries in lower-case characters.
+///
+/// # Returns
+/// The start and the end of the words with their dictionary and rank.
+fn find_words(
+    lower: &[char],
+    user_dictionary: &HashMap<String, usize>,
+) -> Vec<(usize, usize, Dictionary, usize)> {
+    let dictionaries = ranked_dictionaries()
+        .iter()
+        .map(|(dictionary, words)| (*dictionary, words))
+        .chain(std::iter::once((Dictionary::UserInputs, user_dictionary)));
+
+    let mut found = Vec::new();
+    for (dictionary, words) in dictionaries {
+        for i in 0..lower.len() {
+            let mut word = String::new();
+            for (j, c) in lower.iter().enumerate().skip(i) {
+                word.push(*c);
+                if let Some(rank) = words.get(&word) {
+                    found.push((i, j, dictionary, *rank));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Finds the dictionary words including the reversed and the l33t spelled ones.
+fn dictionary_matches(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<Match> {
+    let n = chars.len();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    if lower.len() != n {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    for (i, j, dictionary, rank) in find_words(&lower, user_dictionary) {
+        let pattern = Pattern::Dictionary {
+            dictionary,
+            rank,
+            reversed: false,
+            l33t: false,
+        };
+        matches.push(Match::new(
+            i,
+            j,
+            pattern,
+            rank as f64 * uppercase_variations(&chars[i..=j]),
+        ));
+    }
+
+    let reversed: Vec<char> = lower.iter().rev().copied().collect();
+    for (i, j, dictionary, rank) in find_words(&reversed, user_dictionary) {
+        let (i, j) = (n - 1 - j, n - 1 - i);
+        let pattern = Pattern::Dictionary {
+            dictionary,
+            rank,
+            reversed: true,
+            l33t: false,
+        };
+        let guesses = rank as f64 * uppercase_variations(&chars[i..=j]) * 2.0;
+        matches.push(Match::new(i, j, pattern, guesses));
+    }
+
+    for alternative in [false, true] {
+        let translated: Vec<char> = lower
+            .iter()
+            .map(|c| {
+                L33T_TABLE.iter().find(|(symbol, _, _)| symbol == c).map_or(
+                    *c,
+                    |(_, primary, secondary)| {
+                        if alternative {
+                            *secondary
+                        } else {
+                            *primary
+                        }
+                    },
+                )
+            })
+            .collect();
+
+        if translated == lower {
+            continue;
+        }
+
+        for (i, j, dictionary, rank) in find_words(&translated, user_dictionary) {
+            if i == j || lower[i..=j] == translated[i..=j] {
+                continue;
+            }
+
+            let pattern = Pattern::Dictionary {
+                dictionary,
+                rank,
+                reversed: false,
+                l33t: true,
+            };
+            let guesses = rank as f64
+                * uppercase_variations(&chars[i..=j])
+                * l33t_variations(&lower[i..=j], &translated[i..=j]);
+            matches.push(Match::new(i, j, pattern, guesses));
+        }
+    }
+
+    matches
+}
+
+/// Calculates the amount of ways to capitalize a word the same way as the specified one.
+fn uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+
+    let first_upper = word.first().is_some_and(|c| c.is_uppercase());
+    let last_upper = word.last().is_some_and(|c| c.is_uppercase());
+    if lower == 0 || (upper == 1 && (first_upper || last_upper)) {
+        return 2.0;
+    }
+
+    (1..=upper.min(lower))
+        .map(|k| binomial(upper + lower, k))
+        .sum()
+}
+
+/// Calculates the amount of ways to l33t spell a word the same way as the specified one.
+fn l33t_variations(lower: &[char], translated: &[char]) -> f64 {
+    let mut letters: Vec<char> = lower
+        .iter()
+        .zip(translated)
+        .filter(|(original, letter)| original != letter)
+        .map(|(_, letter)| *letter)
+        .collect();
+    letters.sort_unstable();
+    letters.dedup();
+
+    letters
+        .iter()
+        .map(|letter| {
+            let substituted = lower
+                .iter()
+                .zip(translated)
+                .filter(|(original, translated)| translated == &letter && original != translated)
+                .count();
+            let unsubstituted = lower.iter().filter(|c| *c == letter).count();
+            if unsubstituted == 0 {
+                2.0
+            } else {
+                (1..=substituted.min(unsubstituted))
+                    .map(|k| binomial(substituted + unsubstituted, k))
+                    .sum()
+            }
+        })
+        .product()
+}
+
+/// Gets the keyboard positions of the keys as slanted coordinates with a value indicating whether the shift key is pressed.
+fn keyboard_positions() -> &'static HashMap<char, (i32, i32, bool)> {
+    static POSITIONS: OnceLock<HashMap<char, (i32, i32, bool)>> = OnceLock::new();
+    POSITIONS.get_or_init(|| {
+        let mut positions = HashMap::new();
+        for (shifted, rows) in [(false, KEYBOARD_ROWS), (true, KEYBOARD_SHIFTED_ROWS)] {
+            for (y, row) in rows.iter().enumerate() {
+                let offset = if y == 0 { 0 } else { 1 };
+                for (x, key) in row.chars().enumerate() {
+                    positions.insert(key, (x as i32 + offset, y as i32, shifted));
+                }
+            }
+        }
+        positions
+    })
+}
+
+/// Gets the direction from a key to its neighbouring key or `None` if the keys are not neighbours.
+fn keyboard_direction(from: char, to: char) -> Option<usize> {
+    let positions = keyboard_positions();
+    let (x1, y1, _) = positions.get(&from)?;
+    let (x2, y2, _) = positions.get(&to)?;
+    KEYBOARD_DIRECTIONS
+        .iter()
+        .position(|(dx, dy)| x1 + dx == *x2 && y1 + dy == *y2)
+}
+
+/// Finds the keyboard patterns of at least three keys, e.g. `qwerty` or `zaq1`.
+fn spatial_matches(chars: &[char]) -> Vec<Match> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + 1 < n {
+        let mut j = i;
+        let mut turns = 0;
+        let mut last_direction = None;
+        while j + 1 < n {
+            match keyboard_direction(chars[j], chars[j + 1]) {
+                Some(direction) => {
+                    if last_direction != Some(direction) {
+                        turns += 1;
+                        last_direction = Some(direction);
+                    }
+                    j += 1;
+                }
+                None => break,
+            }
+        }
+
+        if j - i >= 2 {
+            let shifted = chars[i..=j]
+                .iter()
+                .filter(|c| {
+                    keyboard_positions()
+                        .get(c)
+                        .is_some_and(|(_, _, shifted)| *shifted)
+                })
+                .count();
+            matches.push(Match::new(
+                i,
+                j,
+                Pattern::Spatial { turns },
+                spatial_guesses(j - i + 1, turns, shifted),
+            ));
+        }
+
+        i = j.max(i + 1);
+    }
+
+    matches
+}
+
+/// Estimates the guesses of a keyboard pattern.
+fn spatial_guesses(length: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        for j in 1..=turns.min(i - 1) {
+            guesses += binomial(i - 1, j - 1)
+                * KEYBOARD_STARTING_POSITIONS
+                * KEYBOARD_AVERAGE_DEGREE.powi(j as i32);
+        }
+    }
+
+    let unshifted = length - shifted;
+    if shifted > 0 {
+        guesses *= if unshifted == 0 {
+            2.0
+        } else {
+            (1..=shifted.min(unshifted))
+                .map(|k| binomial(shifted + unshifted, k))
+                .sum()
+        };
+    }
+
+    guesses
+}
+
+/// Finds the sequences of at least three characters with a constant step, e.g. `abc`, `9753` or `ace`.
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + 2 < n {
+        let delta = chars[i + 1] as i64 - chars[i] as i64;
+        if delta == 0 || delta.abs() > 5 {
+            i += 1;
+            continue;
+        }
+
+        let mut j = i + 1;
+        while j + 1 < n && chars[j + 1] as i64 - chars[j] as i64 == delta {
+            j += 1;
+        }
+
+        if j - i < 2 {
+            i += 1;
+            continue;
+        }
+
+        let first = chars[i];
+        let mut base = if "aAzZ019".contains(first) {
+            4.0
+        } else if first.is_ascii_digit() {
+            10.0
+        } else {
+            26.0
+        };
+        if delta < 0 {
+            base *= 2.0;
+        }
+
+        matches.push(Match::new(
+            i,
+            j,
+            Pattern::Sequence,
+            base * (j - i + 1) as f64,
+        ));
+        i = j;
+    }
+
+    matches
+}
+
+/// Finds the repeated characters and strings, e.g. `aaa` or `abcabc`.
+fn repeat_matches(chars: &[char], user_dictionary: &HashMap<String, usize>) -> Vec<Match> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + 1 < n {
+        let unit_length = (1..=(n - i) / 2).find(|u| chars[i..i + u] == chars[i + u..i + 2 * u]);
+        let Some(unit_length) = unit_length else {
+            i += 1;
+            continue;
+        };
+
+        let unit = &chars[i..i + unit_length];
+        let mut count = 2;
+        while i + (count + 1) * unit_length <= n
+            && chars[i + count * unit_length..i + (count + 1) * unit_length] == *unit
+        {
+            count += 1;
+        }
+
+        let (unit_guesses_log10, _) = most_guessable(unit, user_dictionary);
+        let j = i + count * unit_length - 1;
+        let guesses = 10f64.powf(unit_guesses_log10) * count as f64;
+        matches.push(Match::new(i, j, Pattern::Repeat { unit_length }, guesses));
+        i = j + 1;
+    }
+
+    matches
+}
+
+/// Finds the recent years and the dates with or without separators, e.g. `1987`, `13.5.1987` or `130587`.
+fn date_matches(chars: &[char]) -> Vec<Match> {
+    let n = chars.len();
+    let reference_year = time::OffsetDateTime::now_utc().year();
+    let year_space = |year: i32| ((year - reference_year).abs().max(MIN_YEAR_SPACE)) as f64;
+    let mut matches = Vec::new();
+
+    for i in 0..n {
+        for j in i + 3..n.min(i + 10) {
+            let token = &chars[i..=j];
+            let length = token.len();
+
+            if token.iter().all(|c| c.is_ascii_digit()) {
+                let digits: String = token.iter().collect();
+                if length == 4 {
+                    if let Ok(year) = digits.parse::<i32>() {
+                        if (1900..=2049).contains(&year) {
+                            matches.push(Match::new(i, j, Pattern::Year, year_space(year)));
+                        }
+                    }
+                }
+
+                let splits: &[(usize, usize)] = match length {
+                    4 => &[(1, 2), (2, 3)],
+                    5 => &[(1, 3), (2, 3)],
+                    6 => &[(1, 2), (2, 4), (4, 5)],
+                    7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+                    8 => &[(2, 4), (4, 6)],
+                    _ => &[],
+                };
+
+                let year = splits
+                    .iter()
+                    .filter_map(|(k, l)| {
+                        let part =
+                            |range: std::ops::Range<usize>| digits[range].parse::<i32>().ok();
+                        to_date([part(0..*k)?, part(*k..*l)?, part(*l..length)?])
+                    })
+                    .min_by_key(|year| (year - reference_year).abs());
+
+                if let Some(year) = year {
+                    matches.push(Match::new(i, j, Pattern::Date, 365.0 * year_space(year)));
+                }
+            } else if length >= 6 {
+                if let Some(year) = separated_date(token) {
+                    matches.push(Match::new(
+                        i,
+                        j,
+                        Pattern::Date,
+                        365.0 * year_space(year) * 4.0,
+                    ));
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+/// Parses a date with separators, e.g. `13.5.1987` or `87-05-13`.
+///
+/// # Returns
+/// The year of the date or `None` if the token is not a date.
+fn separated_date(token: &[char]) -> Option<i32> {
+    let is_separator = |c: &char| " /\\_.-".contains(*c);
+    let first_separator = token.iter().position(is_separator)?;
+    let separator = token[first_separator];
+    let parts: Vec<&[char]> = token.split(|c| *c == separator).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let lengths_valid = (1..=4).contains(&parts[0].len())
+        && (1..=2).contains(&parts[1].len())
+        && (1..=4).contains(&parts[2].len());
+    if !lengths_valid
+        || !parts
+            .iter()
+            .all(|part| part.iter().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let number = |part: &[char]| part.iter().collect::<String>().parse::<i32>().ok();
+    to_date([number(parts[0])?, number(parts[1])?, number(parts[2])?])
+}
+
+/// Interprets three integers as a day, a month and a year in any common order.
+///
+/// # Returns
+/// The four-digit year or `None` if the integers are not a date.
+fn to_date(integers: [i32; 3]) -> Option<i32> {
+    if integers[1] > 31 || integers[1] <= 0 {
+        return None;
+    }
+
+    let mut over_12 = 0;
+    let mut over_31 = 0;
+    let mut under_1 = 0;
+    for integer in integers {
+        if (100..1000).contains(&integer) || integer > 2050 {
+            return None;
+        }
+        if integer > 31 {
+            over_31 += 1;
+        }
+        if integer > 12 {
+            over_12 += 1;
+        }
+        if integer <= 0 {
+            under_1 += 1;
+        }
+    }
+
+    if over_31 >= 2 || over_12 == 3 || under_1 >= 2 {
+        return None;
+    }
+
+    let candidates = [
+        (integers[2], [integers[0], integers[1]]),
+        (integers[0], [integers[1], integers[2]]),
+    ];
+
+    let is_day_month = |[a, b]: [i32; 2]| {
+        ((1..=31).contains(&a) && (1..=12).contains(&b))
+            || ((1..=31).contains(&b) && (1..=12).contains(&a))
+    };
+
+    for (year, rest) in candidates {
+        if (1000..=2050).contains(&year) {
+            return is_day_month(rest).then_some(year);
+        }
+    }
+
+    for (year, rest) in candidates {
+        if is_day_month(rest) {
+            return Some(match year {
+                100.. => year,
+                51..=99 => year + 1900,
+                _ => year + 2000,
+            });
+        }
+    }
+
+    None
+}
+
+/// Calculates the binomial coefficient `n` choose `k`.
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+
+    (1..=k.min(n - k)).fold(1.0, |result, i| result * (n - i + 1) as f64 / i as f64)
+}
+
+/// Gives the feedback for a password based on its weakest part.
+fn feedback(chars: &[char], score: u8, sequence: &[Match]) -> Feedback {
+    if chars.is_empty() {
+        return Feedback {
+            warning: None,
+            suggestions: vec![Suggestion::UseAFewWords, Suggestion::NoNeedForSymbols],
+        };
+    }
+
+    if score > 2 {
+        return Feedback::default();
+    }
+
+    let mut suggestions = vec![Suggestion::AddAnotherWord];
+    let Some(longest) = sequence
+        .iter()
+        .reduce(|longest, m| if m.len() > longest.len() { m } else { longest })
+    else {
+        return Feedback {
+            warning: None,
+            suggestions,
+        };
+    };
+
+    let is_sole_match = sequence.len() == 1;
+    let warning = match longest.pattern {
+        Pattern::Dictionary {
+            dictionary,
+            rank,
+            reversed,
+            l33t,
+        } => {
+            let word = &chars[longest.i..=longest.j];
+            let first_upper = word.first().is_some_and(|c| c.is_uppercase());
+            let rest_lower = word.iter().skip(1).all(|c| !c.is_uppercase());
+            let all_upper =
+                word.iter().any(|c| c.is_uppercase()) && word.iter().all(|c| !c.is_lowercase());
+            if first_upper && rest_lower {
+                suggestions.push(Suggestion::CapitalizationDoesntHelp);
+            } else if all_upper {
+                suggestions.push(Suggestion::AllUppercaseIsAlmostAsEasy);
+            }
+            if reversed && word.len() >= 4 {
+                suggestions.push(Suggestion::ReversedWordsArentMuchHarder);
+            }
+            if l33t {
+                suggestions.push(Suggestion::PredictableSubstitutions);
+            }
+
+            match dictionary {
+                Dictionary::Passwords if is_sole_match && !reversed && !l33t => Some(match rank {
+                    0..=10 => Warning::TopTenPassword,
+                    11..=100 => Warning::TopHundredPassword,
+                    _ => Warning::CommonPassword,
+                }),
+                Dictionary::Passwords if longest.guesses_log10 <= 4.0 => {
+                    Some(Warning::SimilarToCommonPassword)
+                }
+                Dictionary::Passwords => None,
+                Dictionary::EnglishWords => is_sole_match.then_some(Warning::AWordByItselfIsEasy),
+                Dictionary::UserInputs => Some(Warning::ContainsUserInput),
+            }
+        }
+        Pattern::Spatial { turns } => {
+            suggestions.push(Suggestion::UseLongerKeyboardPattern);
+            Some(if turns == 1 {
+                Warning::StraightRowsOfKeysAreEasy
+            } else {
+                Warning::ShortKeyboardPatternsAreEasy
+            })
+        }
+        Pattern::Sequence => {
+            suggestions.push(Suggestion::AvoidSequences);
+            Some(Warning::SequencesLikeAbcAreEasy)
+        }
+        Pattern::Repeat { unit_length } => {
+            suggestions.push(Suggestion::AvoidRepeatedWordsAndCharacters);
+            Some(if unit_length == 1 {
+                Warning::RepeatsLikeAaaAreEasy
+            } else {
+                Warning::RepeatsLikeAbcAbcAreEasy
+            })
+        }
+        Pattern::Year => {
+            suggestions.push(Suggestion::AvoidRecentYears);
+            suggestions.push(Suggestion::AvoidYearsAssociatedWithYou);
+            Some(Warning::RecentYearsAreEasy)
+        }
+        Pattern::Date => {
+            suggestions.push(Suggestion::AvoidDatesAndYearsAssociatedWithYou);
+            Some(Warning::DatesAreOftenEasy)
+        }
+        Pattern::Bruteforce => None,
+    };
+
+    Feedback {
+        warning,
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_passwords_are_too_guessable() {
+        let password = estimate_strength("password", &[]);
+        assert_eq!(password.score, 0);
+        assert_eq!(password.feedback.warning, Some(Warning::TopTenPassword));
+
+        let qwerty = estimate_strength("qwerty", &[]);
+        assert_eq!(qwerty.score, 0);
+        assert!(qwerty.feedback.warning.is_some());
+    }
+
+    #[test]
+    fn passphrase_is_very_unguessable() {
+        let estimate = estimate_strength("correcthorsebatterystaple", &[]);
+
+        assert_eq!(estimate.score, 4);
+        assert!(estimate.feedback.warning.is_none());
+    }
+
+    #[test]
+    fn keyboard_pattern_is_detected() {
+        let estimate = estimate_strength("zxcvbnm,./", &[]);
+
+        assert!(estimate.score <= 1);
+        assert_eq!(
+            estimate.feedback.warning,
+            Some(Warning::StraightRowsOfKeysAreEasy)
+        );
+    }
+
+    #[test]
+    fn user_inputs_are_guessable() {
+        let password = "Kristiansand1987";
+        let without = estimate_strength(password, &[]);
+        let with = estimate_strength(password, &["kristiansand".to_string()]);
+
+        assert!(with.guesses < without.guesses);
+        assert!(with.guesses_log10 <= without.guesses_log10);
+    }
+
+    #[test]
+    fn crack_times_follow_guesses() {
+        let estimate = estimate_strength("Tr0ub4dour&3", &[]);
+
+        assert!(
+            (estimate.crack_times_seconds.online_no_throttling - estimate.guesses / 10.0).abs()
+                < 1e-6
+        );
+        assert!(
+            estimate.crack_times_seconds.offline_fast_hashing
+                < estimate.crack_times_seconds.offline_slow_hashing
+        );
+    }
+}
@@ -0,0 +1,1154 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::{BTreeSet, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde_derive::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::attachments::{remove_unreferenced_blobs, AttachmentInfo};
+use crate::audit::{audit_entries, AuditReport};
+use crate::breach::{entry_hashes, EntryHash};
+use crate::encryption::{decrypt_small_file, encrypt_small_file, CompositeKey, SaveOptions};
+use crate::expiry::{expiry_report, newly_expired, ExpiryInfo, ExpiryReport, EXPIRY_WARNING_DAYS};
+use crate::file_data::{
+    Attachment, DataEntry, FileData, FileOptions, GeneralEntry, GeneralEntryType, TrashItem,
+    TAG_SEPARATOR, UNCATEGORIZED_ID, UNCATEGORIZED_NAME,
+};
+use crate::history::{
+    diff_entries, entry_at, list_revisions, record_revision, FieldDiff, RevisionInfo,
+};
+use crate::migration::migrate_file_data;
+
+/// The non-secret information of an open vault.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultSummary {
+    /// The file the vault was opened from.
+    pub file_name: String,
+    /// The tags used for the entries in the vault.
+    pub tags: Vec<String>,
+    /// Global file options.
+    pub data_options: Option<FileOptions>,
+    /// The version of the file data.
+    pub version: u32,
+    /// A value indicating whether the vault has unsaved changes.
+    pub changed: bool,
+    /// The entries expired or expiring within [`EXPIRY_WARNING_DAYS`] days.
+    pub expiry: ExpiryReport,
+}
+
+/// The non-secret information of an item in the recycle bin.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashInfo {
+    /// The identifier of the item in the recycle bin.
+    pub id: i64,
+    /// The time of the deletion as seconds since the Unix epoch.
+    pub deleted: u64,
+    /// The identifier the entry or the category had before the deletion.
+    pub entry_id: i64,
+    /// The name of the entry or the category.
+    pub name: String,
+    /// A value indicating whether the item is a category.
+    pub is_category: bool,
+}
+
+/// The identifier of the next vault session.
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A vault decrypted into the backend memory.
+///
+/// # Remarks
+/// * The secrets are kept in memory which is zeroed when the session is closed.
+/// * The entries are handed to the frontend without the secrets; the secrets are fetched one at a time on demand.
+pub struct VaultSession {
+    /// The identifier of the session, unique within the process, so a save is completed only into the session it was started from.
+    id: u64,
+    /// The file the vault was opened from.
+    file_path: String,
+    /// The key the vault was opened with and is saved with.
+    key: CompositeKey,
+    /// The decrypted file data.
+    file_data: FileData,
+    /// The revision of the file data, incremented on each change.
+    revision: u64,
+    /// The revision of the file data last saved into the file.
+    saved_revision: u64,
+    /// A value indicating whether a save started with [`VaultSession::begin_save`] is in progress.
+    saving: bool,
+    /// The identifiers of the expired entries already reported.
+    notified_expired: HashSet<i64>,
+}
+
+/// A snapshot of the vault data to encrypt into the vault file without holding the vault state lock.
+pub struct PendingSave {
+    /// The identifier of the session the snapshot was taken from.
+    session_id: u64,
+    /// The revision of the file data in the snapshot.
+    revision: u64,
+    /// The file the vault was opened from.
+    file_path: String,
+    /// The key the vault was opened with.
+    key: CompositeKey,
+    /// The serialized file data.
+    json: Zeroizing<String>,
+}
+
+impl PendingSave {
+    /// Gets the identifier of the session the snapshot was taken from.
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    /// Gets the revision of the file data in the snapshot.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Encrypts the snapshot into the vault file.
+    /// # Arguments
+    ///
+    /// * `backup_count` - the amount of rotating backups of the previous file versions to keep.
+    /// * `options` - the key derivation parameters, the cipher suite, the compression and the chunked encryption to use.
+    pub fn write(&self, backup_count: u32, options: &SaveOptions) -> Result<(), anyhow::Error> {
+        encrypt_small_file(
+            &self.file_path,
+            &self.key,
+            &self.json,
+            backup_count,
+            options,
+        )
+    }
+}
+
+/// The state holding the open vault session of the application.
+#[derive(Default)]
+pub struct VaultState {
+    /// The open vault session.
+    session: Mutex<Option<VaultSession>>,
+    /// The lock held over a save so the saves run one at a time.
+    save: tokio::sync::Mutex<()>,
+}
+
+impl VaultState {
+    /// Locks the state for accessing the vault session.
+    pub fn lock(&self) -> Result<MutexGuard<'_, Option<VaultSession>>, anyhow::Error> {
+        self.session
+            .lock()
+            .map_err(|_| anyhow!("The vault session is not available."))
+    }
+
+    /// Waits for a save in progress to complete and reserves the saving until the returned guard is dropped.
+    pub async fn lock_save(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.save.lock().await
+    }
+}
+
+/// Runs the specified function with the open vault session.
+/// # Arguments
+///
+/// * `state` - the vault state of the application.
+/// * `function` - the function to run with the session.
+///
+/// # Returns
+/// The value returned by the function or an error if no vault is open.
+pub fn with_session<T>(
+    state: &VaultState,
+    function: impl FnOnce(&mut VaultSession) -> Result<T, anyhow::Error>,
+) -> Result<T, anyhow::Error> {
+    let mut session = state.lock()?;
+    let session = session
+        .as_mut()
+        .ok_or_else(|| anyhow!("No vault is open."))?;
+    function(session)
+}
+
+impl VaultSession {
+    /// Opens the specified file into a vault session.
+    /// # Arguments
+    ///
+    /// * `file_path` - the file to open.
+    /// * `key` - the password and / or the key file or a recovery key of the file.
+    ///
+    /// # Returns
+    /// The vault session or an error if the file couldn't be decrypted or read.
+    ///
+    /// # Remarks
+    /// * The file data saved in an earlier format is upgraded to the current format version.
+    /// * The expired items are purged from the recycle bin.
+    pub fn open(file_path: &str, key: CompositeKey) -> Result<Self, anyhow::Error> {
+        let json = Zeroizing::new(decrypt_small_file(file_path, &key)?);
+        let mut file_data = migrate_file_data(&json)?;
+        file_data.purge_expired_trash(unix_time());
+
+        Ok(Self::new(file_path, key, file_data))
+    }
+
+    /// Creates a vault session of the specified file data, e.g. just saved into the file.
+    /// # Arguments
+    ///
+    /// * `file_path` - the file the data is saved in.
+    /// * `key` - the key of the file.
+    /// * `file_data` - the file data in the current format version.
+    pub fn new(file_path: &str, key: CompositeKey, file_data: FileData) -> Self {
+        Self {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            file_path: file_path.to_string(),
+            key,
+            notified_expired: expiry_report(&file_data.entries, unix_time(), 0)
+                .expired
+                .iter()
+                .map(|f| f.id)
+                .collect(),
+            file_data,
+            revision: 0,
+            saved_revision: 0,
+            saving: false,
+        }
+    }
+
+    /// Gets the identifier of the session.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Gets the file the vault was opened from.
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// Gets a value indicating whether the vault has changes not yet saved into its file.
+    pub fn changed(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    /// Serializes the file data with the secrets for a frontend editing the data itself.
+    pub fn to_json(&self) -> Result<Zeroizing<String>, anyhow::Error> {
+        Ok(Zeroizing::new(serde_json::to_string(&self.file_data)?))
+    }
+
+    /// Gets a value indicating whether the vault is being saved.
+    pub fn saving(&self) -> bool {
+        self.saving
+    }
+
+    /// Gets the non-secret information of the vault.
+    pub fn summary(&self) -> VaultSummary {
+        VaultSummary {
+            file_name: self.file_path.clone(),
+            tags: self
+                .file_data
+                .tags()
+                .map(|tags| tags.values.clone())
+                .unwrap_or_default(),
+            data_options: self.file_data.data_options.clone(),
+            version: self.file_data.version,
+            changed: self.changed(),
+            expiry: self.expiry_report(EXPIRY_WARNING_DAYS),
+        }
+    }
+
+    /// Gets the global file options of the vault.
+    pub fn data_options(&self) -> Option<&FileOptions> {
+        self.file_data.data_options.as_ref()
+    }
+
+    /// Replaces the global file options of the vault.
+    /// # Arguments
+    ///
+    /// * `options` - the new file options.
+    pub fn set_data_options(&mut self, options: FileOptions) -> Result<(), anyhow::Error> {
+        self.modify(|file_data| {
+            file_data.data_options = Some(options);
+            Ok(())
+        })
+    }
+
+    /// Reports the expired entries and the entries expiring soon.
+    /// # Arguments
+    ///
+    /// * `warning_days` - the amount of days before the expiry an entry is reported as expiring soon.
+    pub fn expiry_report(&self, warning_days: u32) -> ExpiryReport {
+        expiry_report(&self.file_data.entries, unix_time(), warning_days)
+    }
+
+    /// Audits the security of the entries.
+    /// # Arguments
+    ///
+    /// * `min_score` - the minimum strength score of a password not reported as weak.
+    pub fn audit(&self, min_score: u8) -> AuditReport {
+        audit_entries(&self.file_data.entries, min_score)
+    }
+
+    /// Computes the SHA-1 hashes of the passwords of the entries for the breach check.
+    pub fn password_hashes(&self) -> Vec<EntryHash> {
+        entry_hashes(&self.file_data.entries)
+    }
+
+    /// Finds the entries expired since the vault was opened or last checked.
+    /// # Returns
+    /// The newly expired entries; an entry is reported once unless its expiry is postponed.
+    pub fn check_expired(&mut self) -> Vec<ExpiryInfo> {
+        newly_expired(
+            &self.file_data.entries,
+            unix_time(),
+            &mut self.notified_expired,
+        )
+    }
+
+    /// Continues the expiry checks of a previous session of the same file, e.g. replaced with the data just saved into the file,
+    /// so the expired entries already reported are not reported again.
+    /// # Arguments
+    ///
+    /// * `previous` - the previous session; ignored if it is of another file.
+    pub fn continue_expiry_checks(&mut self, previous: &VaultSession) {
+        if previous.file_path == self.file_path {
+            self.notified_expired = previous.notified_expired.clone();
+        }
+    }
+
+    /// Lists the entries and the categories of the vault without the passwords, the notes, the OTP keys, the protected field values
+    /// and the revision histories.
+    pub fn list_entries(&self) -> Vec<DataEntry> {
+        self.file_data
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut result = entry.clone();
+                result.password = None;
+                result.notes = None;
+                result.otp_auth_key = None;
+                result.history = None;
+                result.strip_protected_fields();
+                result
+            })
+            .collect()
+    }
+
+    /// Gets the specified entry without the password, the protected field values and the revision history.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    pub fn entry(&self, id: i64) -> Result<DataEntry, anyhow::Error> {
+        let mut result = self.find(id)?.clone();
+        result.password = None;
+        result.history = None;
+        result.strip_protected_fields();
+        Ok(result)
+    }
+
+    /// Gets the value of a custom field of the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `name` - the name of the field.
+    pub fn field_value(&self, id: i64, name: &str) -> Result<Zeroizing<String>, anyhow::Error> {
+        let field = self
+            .find(id)?
+            .fields
+            .iter()
+            .flatten()
+            .find(|f| f.name == name)
+            .ok_or_else(|| anyhow!("Entry {} has no field {}.", id, name))?;
+
+        Ok(Zeroizing::new(field.value.clone().unwrap_or_default()))
+    }
+
+    /// Searches the entries matching the specified search text.
+    /// # Arguments
+    ///
+    /// * `search` - the words to search for separated with spaces; the search is case-insensitive.
+    /// * `all_words` - a value indicating whether an entry must contain all the words instead of any of them.
+    /// * `include_protected` - a value indicating whether to search from the passwords, the OTP keys and the protected fields.
+    ///
+    /// # Returns
+    /// The identifiers of the matching entries.
+    pub fn search(&self, search: &str, all_words: bool, include_protected: bool) -> Vec<i64> {
+        let words: Vec<String> = search.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        self.file_data
+            .entries
+            .iter()
+            .filter(|f| !f.is_category())
+            .filter(|entry| {
+                let text = entry.search_text(include_protected);
+                if all_words {
+                    words.iter().all(|word| text.contains(word.as_str()))
+                } else {
+                    words.iter().any(|word| text.contains(word.as_str()))
+                }
+            })
+            .map(|f| f.id)
+            .collect()
+    }
+
+    /// Exports the vault data as plain JSON.
+    /// # Arguments
+    ///
+    /// * `include_protected` - a value indicating whether to include the values of the protected fields.
+    ///
+    /// # Returns
+    /// The file data as JSON.
+    pub fn export(&self, include_protected: bool) -> Result<Zeroizing<String>, anyhow::Error> {
+        let mut file_data = self.file_data.clone();
+
+        if !include_protected {
+            file_data
+                .entries
+                .iter_mut()
+                .chain(file_data.trash.iter_mut().flatten().map(|f| &mut f.entry))
+                .for_each(DataEntry::strip_protected_fields);
+        }
+
+        Ok(Zeroizing::new(serde_json::to_string_pretty(&file_data)?))
+    }
+
+    /// Gets the password of the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    pub fn password(&self, id: i64) -> Result<Zeroizing<String>, anyhow::Error> {
+        Ok(Zeroizing::new(
+            self.find(id)?.password.clone().unwrap_or_default(),
+        ))
+    }
+
+    /// Adds a new entry or updates an existing one.
+    /// # Arguments
+    ///
+    /// * `entry` - the entry to add or update; a new entry is added if no entry has its identifier.
+    ///
+    /// # Returns
+    /// The identifier of the entry.
+    ///
+    /// # Remarks
+    /// * The password of an existing entry is kept if the entry has no password; an empty password clears it.
+    /// * The changed fields of an existing entry are recorded into its revision history; a history in the entry is ignored.
+    /// * The value of a protected custom field of an existing entry is kept if the field has no value.
+    /// * The attachments are managed separately; the attachments in the entry are ignored.
+    /// * The password change time is updated when the password changes and tracked from now on if only the rotation interval is set.
+    /// * The entry is placed into the category named by its first tag, creating the category if needed. An entry without tags is placed into the category for the entries without a category.
+    /// * The tags of the vault are regenerated from the tags of the entries.
+    /// * The change is rejected if the resulting data is inconsistent.
+    pub fn update_entry(&mut self, mut entry: DataEntry) -> Result<i64, anyhow::Error> {
+        self.modify(|file_data| {
+            let index = file_data
+                .entries
+                .iter()
+                .position(|f| f.id == entry.id && !f.is_category());
+
+            if entry
+                .tag_list()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .is_empty()
+            {
+                entry.parent_id = UNCATEGORIZED_ID;
+                ensure_uncategorized(&mut file_data.entries);
+            }
+
+            match index {
+                Some(index) => {
+                    if entry.password.is_none() {
+                        entry.password = file_data.entries[index].password.clone();
+                    }
+                    keep_protected_fields(&file_data.entries[index], &mut entry);
+                    entry.attachments = file_data.entries[index].attachments.clone();
+                    entry.password_changed = if entry.password == file_data.entries[index].password
+                    {
+                        file_data.entries[index].password_changed
+                    } else {
+                        Some(unix_time())
+                    };
+                    record_revision(&file_data.entries[index], &mut entry);
+                    file_data.entries[index] = entry.clone();
+                }
+                None => {
+                    entry.id = new_id(&file_data.entries);
+                    entry.history = None;
+                    entry.attachments = None;
+                    entry.password_changed = Some(unix_time());
+                    file_data.entries.push(entry.clone());
+                }
+            }
+
+            if entry.rotation_days.is_some() && entry.password_changed.is_none() {
+                if let Some(stored) = file_data.entries.iter_mut().find(|f| f.id == entry.id) {
+                    stored.password_changed = Some(unix_time());
+                }
+            }
+
+            ensure_first_tags(&mut file_data.entries);
+
+            Ok(entry.id)
+        })
+    }
+
+    /// Lists the attachments of the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    pub fn attachments(&self, id: i64) -> Result<Vec<AttachmentInfo>, anyhow::Error> {
+        Ok(self
+            .find(id)?
+            .attachments
+            .iter()
+            .flatten()
+            .map(|f| AttachmentInfo {
+                id: f.id,
+                name: f.name.clone(),
+                size: f.size,
+                added: f.added,
+            })
+            .collect())
+    }
+
+    /// Gets the specified attachment of the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `attachment_id` - the identifier of the attachment.
+    pub fn attachment(&self, id: i64, attachment_id: i64) -> Result<Attachment, anyhow::Error> {
+        self.find(id)?
+            .attachments
+            .iter()
+            .flatten()
+            .find(|f| f.id == attachment_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Entry {} has no attachment {}.", id, attachment_id))
+    }
+
+    /// Finds an attachment with the specified contents from the entries and the recycle bin for sharing its blob.
+    /// # Arguments
+    ///
+    /// * `hash` - the SHA-256 hash of the contents.
+    pub fn find_attachment_by_hash(&self, hash: &str) -> Option<Attachment> {
+        self.all_attachments().find(|f| f.hash == hash).cloned()
+    }
+
+    /// Adds an attachment whose contents are already stored into a blob into the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `attachment` - the attachment; a new identifier is assigned to it.
+    ///
+    /// # Returns
+    /// The identifier of the attachment.
+    pub fn add_attachment(
+        &mut self,
+        id: i64,
+        mut attachment: Attachment,
+    ) -> Result<i64, anyhow::Error> {
+        if self.find(id)?.is_category() {
+            return Err(anyhow!("A category can't have attachments."));
+        }
+
+        self.modify(|file_data| {
+            let entry = file_data
+                .entries
+                .iter_mut()
+                .find(|f| f.id == id)
+                .ok_or_else(|| anyhow!("Entry {} was not found.", id))?;
+
+            let attachments = entry.attachments.get_or_insert_with(Vec::new);
+            attachment.id = attachments.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+            attachments.push(attachment.clone());
+
+            Ok(attachment.id)
+        })
+    }
+
+    /// Removes an attachment from the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `attachment_id` - the identifier of the attachment.
+    ///
+    /// # Remarks
+    /// * The blob of the attachment is removed when the vault is saved if no other attachment shares it.
+    pub fn remove_attachment(&mut self, id: i64, attachment_id: i64) -> Result<(), anyhow::Error> {
+        self.attachment(id, attachment_id)?;
+
+        self.modify(|file_data| {
+            if let Some(entry) = file_data.entries.iter_mut().find(|f| f.id == id) {
+                let attachments = entry.attachments.get_or_insert_with(Vec::new);
+                attachments.retain(|f| f.id != attachment_id);
+                if attachments.is_empty() {
+                    entry.attachments = None;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Lists the revisions of the specified entry without the previous values.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    pub fn revisions(&self, id: i64) -> Result<Vec<RevisionInfo>, anyhow::Error> {
+        Ok(list_revisions(self.find(id)?))
+    }
+
+    /// Compares two versions of the specified entry.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `from` - the index of the revision of the older version; the version is the entry before the change of the revision.
+    /// * `to` - the index of the revision of the newer version; `None` for the current entry.
+    ///
+    /// # Returns
+    /// The fields differing between the versions with their values.
+    pub fn diff_revisions(
+        &self,
+        id: i64,
+        from: usize,
+        to: Option<usize>,
+    ) -> Result<Vec<FieldDiff>, anyhow::Error> {
+        let entry = self.find(id)?;
+
+        let old = entry_at(entry, from)?;
+        let new = match to {
+            Some(to) => entry_at(entry, to)?,
+            None => entry.clone(),
+        };
+
+        Ok(diff_entries(&old, &new))
+    }
+
+    /// Restores the specified entry to the version before the change of the specified revision.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry.
+    /// * `index` - the index of the revision.
+    ///
+    /// # Remarks
+    /// * The restore is recorded as a new revision so it can be undone.
+    pub fn restore_revision(&mut self, id: i64, index: usize) -> Result<(), anyhow::Error> {
+        let mut entry = entry_at(self.find(id)?, index)?;
+
+        // An entry without a password would keep the current password on update.
+        entry.password.get_or_insert_with(String::new);
+
+        self.update_entry(entry)?;
+
+        Ok(())
+    }
+
+    /// Deletes the specified entry or category into the recycle bin.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the entry or the category.
+    ///
+    /// # Remarks
+    /// * The first tag is removed from the entries of a deleted category and the entries are moved into the category of their next tag.
+    ///   The entries without tags left are moved into the category for the entries without a category.
+    /// * The category for the entries without a category can't be deleted.
+    pub fn delete_entry(&mut self, id: i64) -> Result<(), anyhow::Error> {
+        if id == UNCATEGORIZED_ID {
+            return Err(anyhow!(
+                "The category for the entries without a category can't be deleted."
+            ));
+        }
+
+        self.find(id)?;
+
+        self.modify(|file_data| {
+            let index = file_data
+                .entries
+                .iter()
+                .position(|f| f.id == id)
+                .ok_or_else(|| anyhow!("Entry {} was not found.", id))?;
+
+            let mut entry_ids = Vec::new();
+            if file_data.entries[index].is_category() {
+                for entry in file_data.entries.iter_mut().filter(|f| f.parent_id == id) {
+                    let tags: Vec<&str> = entry.tag_list().skip(1).collect();
+                    let tags = tags.join(&TAG_SEPARATOR.to_string());
+                    if tags.trim().is_empty() {
+                        entry.parent_id = UNCATEGORIZED_ID;
+                    }
+                    entry.tags = Some(tags);
+                    entry_ids.push(entry.id);
+                }
+
+                ensure_uncategorized(&mut file_data.entries);
+            }
+
+            let entry = file_data.entries.remove(index);
+
+            let trash = file_data.trash.get_or_insert_with(Vec::new);
+            trash.push(TrashItem {
+                id: trash.iter().map(|f| f.id).max().unwrap_or(0) + 1,
+                deleted: unix_time(),
+                entry,
+                entry_ids,
+            });
+
+            ensure_first_tags(&mut file_data.entries);
+
+            Ok(())
+        })
+    }
+
+    /// Lists the items in the recycle bin without the secrets.
+    pub fn list_trash(&self) -> Vec<TrashInfo> {
+        self.file_data
+            .trash
+            .iter()
+            .flatten()
+            .map(|item| TrashInfo {
+                id: item.id,
+                deleted: item.deleted,
+                entry_id: item.entry.id,
+                name: item.entry.name.clone(),
+                is_category: item.entry.is_category(),
+            })
+            .collect()
+    }
+
+    /// Restores the specified item from the recycle bin.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the item in the recycle bin.
+    ///
+    /// # Returns
+    /// The identifier of the restored entry or category.
+    ///
+    /// # Remarks
+    /// * The item gets a new identifier if its identifier was taken after the deletion.
+    /// * An entry is placed into the category named by its first tag like on update.
+    /// * A category is merged into an existing category of the same name. Its name is added back as the first tag
+    ///   of the remaining entries it was removed from.
+    pub fn restore_trash_item(&mut self, id: i64) -> Result<i64, anyhow::Error> {
+        self.modify(|file_data| {
+            let trash = file_data.trash.get_or_insert_with(Vec::new);
+            let index = trash
+                .iter()
+                .position(|f| f.id == id)
+                .ok_or_else(|| anyhow!("Item {} was not found in the recycle bin.", id))?;
+            let item = trash.remove(index);
+            if trash.is_empty() {
+                file_data.trash = None;
+            }
+
+            let mut entry = item.entry.clone();
+            let entries = &mut file_data.entries;
+
+            if entry.is_category() {
+                let existing = entries
+                    .iter()
+                    .find(|f| f.is_category() && f.name == entry.name)
+                    .map(|f| f.id);
+
+                entry.id = match existing {
+                    Some(existing) => existing,
+                    None => {
+                        if entries.iter().any(|f| f.id == entry.id) {
+                            entry.id = new_id(entries);
+                        }
+                        entries.push(entry.clone());
+                        entry.id
+                    }
+                };
+
+                for restored in entries
+                    .iter_mut()
+                    .filter(|f| !f.is_category() && item.entry_ids.contains(&f.id))
+                {
+                    if restored.tag_list().next() == Some(entry.name.as_str()) {
+                        continue;
+                    }
+
+                    let tags = restored.tags.as_deref().unwrap_or_default();
+                    restored.tags = Some(if tags.trim().is_empty() {
+                        entry.name.clone()
+                    } else {
+                        format!("{}{}{}", entry.name, TAG_SEPARATOR, tags)
+                    });
+                }
+            } else {
+                if entries.iter().any(|f| f.id == entry.id) {
+                    entry.id = new_id(entries);
+                }
+
+                if entry
+                    .tag_list()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .is_empty()
+                {
+                    entry.parent_id = UNCATEGORIZED_ID;
+                    ensure_uncategorized(entries);
+                }
+
+                entries.push(entry.clone());
+            }
+
+            ensure_first_tags(entries);
+
+            Ok(entry.id)
+        })
+    }
+
+    /// Purges items from the recycle bin permanently.
+    /// # Arguments
+    ///
+    /// * `id` - the identifier of the item in the recycle bin; `None` to empty the recycle bin.
+    pub fn purge_trash(&mut self, id: Option<i64>) -> Result<(), anyhow::Error> {
+        self.modify(|file_data| {
+            let trash = file_data.trash.get_or_insert_with(Vec::new);
+
+            match id {
+                Some(id) => {
+                    let index = trash
+                        .iter()
+                        .position(|f| f.id == id)
+                        .ok_or_else(|| anyhow!("Item {} was not found in the recycle bin.", id))?;
+                    trash.remove(index);
+                }
+                None => trash.clear(),
+            }
+
+            if trash.is_empty() {
+                file_data.trash = None;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Starts saving the vault into the file it was opened from by taking a snapshot of the data to save with [`PendingSave::write`].
+    ///
+    /// # Returns
+    /// The snapshot or an error if the data is invalid or the vault is already being saved.
+    ///
+    /// # Remarks
+    /// * The expired items are purged from the recycle bin before saving.
+    /// * The vault stays changed until the save is completed with [`VaultSession::end_save`].
+    pub fn begin_save(&mut self) -> Result<PendingSave, anyhow::Error> {
+        if self.saving {
+            return Err(anyhow!("The vault is already being saved."));
+        }
+
+        self.file_data.purge_expired_trash(unix_time());
+        self.file_data.validate()?;
+
+        let json = Zeroizing::new(serde_json::to_string(&self.file_data)?);
+        self.saving = true;
+
+        Ok(PendingSave {
+            session_id: self.id,
+            revision: self.revision,
+            file_path: self.file_path.clone(),
+            key: self.key.clone(),
+            json,
+        })
+    }
+
+    /// Completes a save started with [`VaultSession::begin_save`].
+    /// # Arguments
+    ///
+    /// * `revision` - the revision of the saved snapshot, see [`PendingSave::revision`].
+    /// * `result` - the result of [`PendingSave::write`].
+    /// * `backup_count` - the amount of rotating backups of the previous file versions to keep.
+    ///
+    /// # Remarks
+    /// * The vault is marked as saved only if the save succeeded and the vault wasn't changed after the snapshot was taken.
+    /// * The attachment blobs no longer referenced by the vault or the backups are removed after saving, see [`remove_unreferenced_blobs`].
+    pub fn end_save(
+        &mut self,
+        revision: u64,
+        result: Result<(), anyhow::Error>,
+        backup_count: u32,
+    ) -> Result<(), anyhow::Error> {
+        self.saving = false;
+        result?;
+        self.saved_revision = revision;
+
+        let blobs: HashSet<&str> = self.all_attachments().map(|f| f.blob.as_str()).collect();
+        remove_unreferenced_blobs(&self.file_path, &blobs, backup_count)
+    }
+
+    /// Iterates the attachments of the entries and the entries in the recycle bin.
+    fn all_attachments(&self) -> impl Iterator<Item = &Attachment> {
+        self.file_data
+            .entries
+            .iter()
+            .chain(self.file_data.trash.iter().flatten().map(|f| &f.entry))
+            .flat_map(|f| f.attachments.iter().flatten())
+    }
+
+    /// Finds the specified entry or category.
+    fn find(&self, id: i64) -> Result<&DataEntry, anyhow::Error> {
+        self.file_data
+            .entries
+            .iter()
+            .find(|f| f.id == id)
+            .ok_or_else(|| anyhow!("Entry {} was not found.", id))
+    }
+
+    /// Modifies the file data, regenerates the tags and validates the result.
+    /// # Arguments
+    ///
+    /// * `function` - the function modifying the file data.
+    ///
+    /// # Returns
+    /// The value returned by the function or an error. On an error the file data is left unchanged.
+    fn modify<T>(
+        &mut self,
+        function: impl FnOnce(&mut FileData) -> Result<T, anyhow::Error>,
+    ) -> Result<T, anyhow::Error> {
+        let mut file_data = self.file_data.clone();
+
+        let result = function(&mut file_data)?;
+
+        let tags = generate_tags(&file_data.entries);
+        let meta_data = file_data.meta_data.get_or_insert_with(Vec::new);
+        match meta_data
+            .iter_mut()
+            .find(|f| f.entry_type == GeneralEntryType::Tags)
+        {
+            Some(metadata) => metadata.values = tags,
+            None => meta_data.push(GeneralEntry {
+                entry_type: GeneralEntryType::Tags,
+                values: tags,
+            }),
+        }
+
+        file_data.validate()?;
+
+        self.file_data = file_data;
+        self.revision += 1;
+
+        Ok(result)
+    }
+}
+
+/// Gets a new identifier for an entry or a category.
+fn new_id(entries: &[DataEntry]) -> i64 {
+    entries.iter().map(|f| f.id).max().unwrap_or(0).max(0) + 1
+}
+
+/// Copies the values of the protected custom fields without a value from the previous version of an entry.
+fn keep_protected_fields(previous: &DataEntry, entry: &mut DataEntry) {
+    for field in entry
+        .fields
+        .iter_mut()
+        .flatten()
+        .filter(|f| f.is_protected() && f.value.is_none())
+    {
+        field.value = previous
+            .fields
+            .iter()
+            .flatten()
+            .find(|f| f.name == field.name)
+            .and_then(|f| f.value.clone());
+    }
+}
+
+/// Gets the current time as seconds since the Unix epoch.
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|f| f.as_secs())
+        .unwrap_or_default()
+}
+
+/// Generates a distinct sorted list of the tags used by the specified entries.
+fn generate_tags(entries: &[DataEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .flat_map(DataEntry::tag_list)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Adds the category for the entries without a category if it doesn't exist.
+fn ensure_uncategorized(entries: &mut Vec<DataEntry>) {
+    if !entries.iter().any(|f| f.id == UNCATEGORIZED_ID) {
+        entries.push(DataEntry::new_category(
+            UNCATEGORIZED_ID,
+            UNCATEGORIZED_NAME,
+        ));
+    }
+}
+
+/// Places the entries with tags into the categories named by their first tags.
+/// # Arguments
+///
+/// * `entries` - the entries and the categories.
+///
+/// # Remarks
+/// * The missing categories are created.
+fn ensure_first_tags(entries: &mut Vec<DataEntry>) {
+    for index in 0..entries.len() {
+        if entries[index].is_category() {
+            continue;
+        }
+
+        let first_tag = match entries[index].tag_list().next() {
+            Some(tag) if !tag.trim().is_empty() => tag.to_string(),
+            _ => continue,
+        };
+
+        let parent_id = match entries
+            .iter()
+            .find(|f| f.is_category() && f.name == first_tag)
+        {
+            Some(category) => category.id,
+            None => {
+                let id = new_id(entries);
+                entries.push(DataEntry::new_category(id, &first_tag));
+                id
+            }
+        };
+
+        entries[index].parent_id = parent_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A copy of the sample file removed on drop.
+    struct SampleFile(std::path::PathBuf);
+
+    impl SampleFile {
+        /// Copies the sample file shipped with the repository into a file unique to the test.
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "password_keeper_{}_{}.pkd",
+                name,
+                std::process::id()
+            ));
+            std::fs::copy(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/../Sample/Sample File.pkd"),
+                &path,
+            )
+            .unwrap();
+            Self(path)
+        }
+
+        /// Opens the copy into a vault session.
+        fn open(&self) -> VaultSession {
+            VaultSession::open(
+                self.0.to_str().unwrap(),
+                CompositeKey::new("abc", None).unwrap(),
+            )
+            .unwrap()
+        }
+    }
+
+    impl Drop for SampleFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn saved_vault_is_unchanged() {
+        let file = SampleFile::new("save");
+        let mut session = file.open();
+        session.delete_entry(2).unwrap();
+
+        let pending = session.begin_save().unwrap();
+        assert!(session.changed());
+        assert!(session.begin_save().is_err());
+        let result = pending.write(0, &SaveOptions::default());
+        session.end_save(pending.revision(), result, 0).unwrap();
+
+        assert!(!session.changed());
+        assert!(!session.saving());
+        assert!(file.open().entry(2).is_err());
+    }
+
+    #[test]
+    fn changes_during_save_are_kept_unsaved() {
+        let file = SampleFile::new("save_changes");
+        let mut session = file.open();
+        session.delete_entry(2).unwrap();
+
+        let pending = session.begin_save().unwrap();
+        session.delete_entry(3).unwrap();
+        let result = pending.write(0, &SaveOptions::default());
+        session.end_save(pending.revision(), result, 0).unwrap();
+
+        assert!(session.changed());
+        let saved = file.open();
+        assert!(saved.entry(2).is_err());
+        assert!(saved.entry(3).is_ok());
+    }
+
+    #[test]
+    fn failed_save_is_kept_unsaved() {
+        let file = SampleFile::new("save_failure");
+        let mut session = file.open();
+        session.delete_entry(2).unwrap();
+
+        let pending = session.begin_save().unwrap();
+        let result = session.end_save(pending.revision(), Err(anyhow!("The disk is full.")), 0);
+
+        assert!(result.is_err());
+        assert!(session.changed());
+        assert!(!session.saving());
+    }
+    #[test]
+    fn replaced_session_continues_expiry_checks() {
+        let file = SampleFile::new("expiry_checks");
+        let mut session = file.open();
+        let mut entry = session.entry(3).unwrap();
+        entry.expiry_date = Some("2000-01-01".into());
+        session.update_entry(entry).unwrap();
+        assert_eq!(session.check_expired().len(), 1);
+
+        // An entry expired before the replacement but not yet reported is reported by the replacing session.
+        let mut entry = session.entry(4).unwrap();
+        entry.expiry_date = Some("2000-01-01".into());
+        session.update_entry(entry).unwrap();
+
+        let key = CompositeKey::new("abc", None).unwrap();
+        let mut replaced =
+            VaultSession::new(file.0.to_str().unwrap(), key, session.file_data.clone());
+        replaced.continue_expiry_checks(&session);
+
+        let expired = replaced.check_expired();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, 4);
+    }
+}
@@ -0,0 +1,115 @@
+/*
+MIT License
+
+Copyright (c) 2023 Petteri Kautonen
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::encryption::{check_file, CompositeKey};
+use crate::migration::migrate_file_data;
+
+/// The maximum size of the decrypted contents checked against the file data format.
+const MAX_SCHEMA_CHECK_SIZE: u64 = 256 * 1024 * 1024;
+
+/// A structured report of a file integrity verification.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// The file which was verified.
+    pub file_name: String,
+    /// The container format version of the file; 0 for the legacy format. `None` if the header couldn't be read.
+    pub format_version: Option<u16>,
+    /// A value indicating whether the container header was read and its checksum matched.
+    pub structure_valid: bool,
+    /// A value indicating whether the length of the encrypted data in the header matches the file size.
+    pub length_consistent: bool,
+    /// A value indicating whether the password unlocked the file and the authentication tag of the data matched.
+    pub authenticated: bool,
+    /// A value indicating whether the password was rejected as invalid.
+    pub invalid_password: bool,
+    /// A value indicating whether the decrypted data is valid JSON of the expected file data format with consistent entries.
+    pub schema_valid: bool,
+    /// A value indicating whether all the checks passed.
+    pub valid: bool,
+    /// The problems found in the file.
+    pub errors: Vec<String>,
+}
+
+/// Verifies the integrity of the specified file without returning its contents.
+/// # Arguments
+///
+/// * `file_path` - the file to verify.
+/// * `key` - the password and / or the key file or a recovery key of the file.
+///
+/// # Returns
+/// A [`VerificationReport`] of the checks. Each check is run only if the previous ones passed.
+///
+/// # Remarks
+/// * The file is authenticated as a stream; the decrypted contents are kept in memory for the format check only up to 256 MiB.
+pub fn verify_file(file_path: &str, key: &CompositeKey) -> VerificationReport {
+    let check = check_file(file_path, key, MAX_SCHEMA_CHECK_SIZE);
+
+    let mut report = VerificationReport {
+        file_name: file_path.to_string(),
+        format_version: check.format_version,
+        structure_valid: check.structure_valid,
+        length_consistent: check.length_consistent,
+        authenticated: check.authenticated,
+        invalid_password: check.invalid_password,
+        schema_valid: false,
+        valid: false,
+        errors: check.errors,
+    };
+
+    match check.content {
+        Some(content) => match validate_schema(&content) {
+            Ok(()) => report.schema_valid = true,
+            Err(e) => report.errors.push(e),
+        },
+        None if check.authenticated => report.errors.push(format!(
+            "The decrypted data is larger than {} bytes, so its format was not checked.",
+            MAX_SCHEMA_CHECK_SIZE
+        )),
+        None => {}
+    }
+
+    report.valid = report.schema_valid;
+
+    report
+}
+
+/// Validates the decrypted file contents against the file data format.
+/// # Arguments
+///
+/// * `content` - the decrypted file contents.
+///
+/// # Returns
+/// `Ok(())` if the contents are valid; an error message otherwise.
+///
+/// # Remarks
+/// * The data saved in an earlier format is upgraded with [`migrate_file_data`] before the entries are checked with [`FileData::validate`](crate::file_data::FileData::validate).
+fn validate_schema(content: &[u8]) -> Result<(), String> {
+    let json = std::str::from_utf8(content).map_err(|e| format!("Invalid JSON data: {}", e))?;
+
+    migrate_file_data(json)
+        .and_then(|file_data| file_data.validate())
+        .map_err(|e| e.to_string())
+}
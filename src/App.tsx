@@ -25,6 +25,7 @@ SOFTWARE.
 import * as React from "react";
 import "./App.css";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { ask } from "@tauri-apps/plugin-dialog";
 import { exit } from "@tauri-apps/plugin-process";
@@ -57,6 +58,17 @@ import { DialogButtons, DialogResult, FileQueryMode, ModifyType, PopupType } fro
 import type { DataEntry, FileData, FileOptions, GeneralEntry } from "./types/PasswordEntry";
 import { generateTags, loadFile, saveFile } from "./utilities/app/Files";
 import { type Settings, useSettings } from "./utilities/app/Settings";
+import {
+    type ExpiryInfo,
+    closeVault,
+    entryExpiredEvent,
+    getLockStatus,
+    lockVault,
+    openVault,
+    reportActivity,
+    vaultLockedEvent,
+    vaultSaveFailedEvent,
+} from "./utilities/app/TauriBackend";
 const appWindow = getCurrentWebviewWindow();
 
 /**
@@ -95,8 +107,6 @@ const App = ({ className }: AppProps) => {
     const [aboutVisible, setAboutVisible] = React.useState(false);
     const [viewLocked, setViewLocked] = React.useState(false);
     const [lockPasswordQueryVisible, setLockPasswordQueryVisible] = React.useState(false);
-    const [timeOut, setTimeOut] = React.useState(10);
-    const [passwordFailedCount, setPasswordFailedCount] = React.useState(0);
     const [saveChangedFileQueryVisible, setSaveChangedFileQueryVisible] = React.useState(false);
     const [fileCloseRequested, setFileCloseRequested] = React.useState(false);
     const [isNewFile, setIsNewFile] = React.useState(true);
@@ -112,6 +122,7 @@ const App = ({ className }: AppProps) => {
 
     const expandedKeysRef = React.useRef<Array<string>>([]);
     const selectedItemRef = React.useRef<DataEntry | null>(null);
+    const lastActivityRef = React.useRef(0);
 
     // Securely store the file password (to be able to save the file without querying the password) to the application local storage.
     const [setFilePassword, getFilePassword, clearFilePassword] = useSecureStorage<string>("filePassword", "");
@@ -159,21 +170,51 @@ const App = ({ className }: AppProps) => {
         setExpandedKeys([]);
     }, [entry, expandedKeys]);
 
-    // A callback to lock the view after a time interval has elapsed.
-    const onViewLockTimeout = React.useCallback(() => {
-        lockView();
-    }, [lockView]);
+    // The backend locks the vault after the lock timeout has elapsed, lock the view as well.
+    // The backend also checks the entries of the saved file for the expiry while it is open.
+    React.useEffect(() => {
+        const unlistenLocked = listen<string>(vaultLockedEvent, () => {
+            lockView();
+        });
+        const unlistenSaveFailed = listen<string>(vaultSaveFailedEvent, event => {
+            notification("error", lm("vaultSaveFailLocked", undefined, { msg: event.payload }), 5);
+        });
+        const unlistenExpired = listen<ExpiryInfo[]>(entryExpiredEvent, event => {
+            for (const expired of event.payload) {
+                notification("warning", lm("entryExpired", undefined, { name: expired.name }), 10);
+            }
+        });
 
-    const [setTimeoutEnabled, resetTimeOut] = useTimeout(timeOut, onViewLockTimeout, TimeInterval.Minutes);
+        return () => {
+            void unlistenLocked.then(unlisten => unlisten());
+            void unlistenSaveFailed.then(unlisten => unlisten());
+            void unlistenExpired.then(unlisten => unlisten());
+        };
+    }, [lm, lockView, notification]);
+
+    // Report the user activity to the backend postponing the lock timeout, at most once in five seconds.
+    const onUserActivity = React.useCallback(() => {
+        const now = Date.now();
+        if (now - lastActivityRef.current >= 5000) {
+            lastActivityRef.current = now;
+            void reportActivity();
+        }
+    }, []);
 
     React.useEffect(() => {
         if (settings && setTheme) {
             void setLocale(settings.locale);
             setTheme(settings.dark_mode ? "dark" : "light");
-            setTimeOut(settings.lock_timeout);
-            setTimeoutEnabled(settings.lock_timeout > 0);
         }
-    }, [setTheme, setTimeoutEnabled, settings]);
+    }, [setTheme, settings]);
+
+    // Close the vault session of the backend and reload the window.
+    // Reloading the window will do the same as re-setting multiple state variables.
+    const closeVaultAndReload = React.useCallback(() => {
+        void closeVault().finally(() => {
+            globalThis.location.reload();
+        });
+    }, []);
 
     // The file was requested to be closed. If the file has changes display a query popup
     // for to select the action what to do with the changes, otherwise just reload the view.
@@ -185,16 +226,8 @@ const App = ({ className }: AppProps) => {
         }
 
         clearFilePassword();
-        // Reloading the window will do the same as re-setting multiple state variables.
-        globalThis.location.reload();
-    }, [clearFilePassword, fileChanged]);
-
-    // Enable the time out hook if the view was unlocked.
-    React.useEffect(() => {
-        if (settings && !viewLocked) {
-            setTimeoutEnabled(settings.lock_timeout > 0);
-        }
-    }, [setTimeoutEnabled, viewLocked, settings]);
+        closeVaultAndReload();
+    }, [clearFilePassword, closeVaultAndReload, fileChanged]);
 
     // Save the file "as new".
     const saveFileAsCallback = React.useCallback(() => {
@@ -224,18 +257,18 @@ const App = ({ className }: AppProps) => {
                         setCurrentFile(f.fileName);
                         setFileChanged(false);
                         if (fileCloseRequested) {
-                            // File was requested to be closed after saving,
-                            // reloading the window will do the same as re-setting multiple state variables.
-                            globalThis.location.reload();
+                            // File was requested to be closed after saving.
+                            closeVaultAndReload();
                         }
                     } else {
                         // Something went wrong with the file save. Display the error message.
-                        notification("error", lm("fileSaveFail", undefined, f.errorMessage), 5);
+                        notification("error", lm("fileSaveFail", undefined, { msg: f.errorMessage }), 5);
                     }
                 });
             }
         }
     }, [
+        closeVaultAndReload,
         currentFile,
         dataSource,
         dataTags,
@@ -333,25 +366,21 @@ const App = ({ className }: AppProps) => {
         return currentFile === undefined ? "Password Keeper" : `Password Keeper [${currentFile}${fileChangeIndicator}]`;
     }, [currentFile, fileChanged]);
 
-    // Increase the failed password count, display a warning message and close the program
-    // if the failed password counter reached its predefined limit.
-    const increaseFileLockFail = React.useCallback(() => {
+    // Display a warning message of the remaining unlock attempts after a failed attempt.
+    // The backend counts the failed attempts and closes the program if the count reached its predefined limit.
+    const warnFailedAttempts = React.useCallback(async () => {
         // If the setting is disabled, do nothing.
         if ((settings?.failed_unlock_attempts ?? 0) === 0) {
             return;
         }
 
-        const failed = passwordFailedCount + 1;
-        setPasswordFailedCount(failed);
-        const lockAfter = (settings?.failed_unlock_attempts ?? 0) - failed;
-        if (lockAfter > 0) {
+        const status = await getLockStatus();
+        const lockAfter = (settings?.failed_unlock_attempts ?? 0) - status.failedAttempts;
+        if (lockAfter > 0 && status.failedAttempts > 0) {
             // Notify about the erroneous password.
             notification("warning", lm("passwordFailLockWarning", undefined, { lockAfter: lockAfter }), 5);
-        } else {
-            // The count exceeded, exit the software.
-            void exit(0);
         }
-    }, [lm, notification, passwordFailedCount, settings]);
+    }, [lm, notification, settings]);
 
     // The file popup was closed. If the user accepted the popup.
     // Depending on the popup mode and user input either open an existing file or save new file.
@@ -368,7 +397,6 @@ const App = ({ className }: AppProps) => {
                             setCurrentFile(fileName);
                             setFileChanged(false);
                             setIsNewFile(false);
-                            setPasswordFailedCount(0);
                             setDataTags(f.tags);
                             setFileOptions(f.dataOptions);
                             expandedKeysRef.current = [];
@@ -377,8 +405,7 @@ const App = ({ className }: AppProps) => {
                         } else {
                             // The file load failed with most probable reason being an invalid password.
                             notification("error", lm("fileOpenFail", undefined, { msg: f.errorMessage }), 5);
-                            // Increase the failed password count, whatever the failure reason is.
-                            increaseFileLockFail();
+                            void warnFailedAttempts();
                         }
                     });
                     // The file mode is save as, so save the file.
@@ -388,7 +415,8 @@ const App = ({ className }: AppProps) => {
                         metaData: [dataTags],
                         version: 1,
                     };
-                    void saveFile(data, password, fileName).then(f => {
+                    // The user confirmed replacing an existing file in the save dialog.
+                    void saveFile(data, password, fileName, undefined, { overwrite: true }).then(f => {
                         if (f.ok) {
                             // Set the state data for the successfully saved file.
                             setFilePassword(password);
@@ -397,9 +425,8 @@ const App = ({ className }: AppProps) => {
                             setFileChanged(false);
 
                             if (fileCloseRequested) {
-                                // File was requested to be closed after saving,
-                                // reloading the window will do the same as re-setting multiple state variables.
-                                globalThis.location.reload();
+                                // File was requested to be closed after saving.
+                                closeVaultAndReload();
                             }
                         } else {
                             // Some error occurred saving the file, report the save failure.
@@ -411,14 +438,15 @@ const App = ({ className }: AppProps) => {
             setFileSaveOpenQueryOpen(false);
         },
         [
+            closeVaultAndReload,
             dataSource,
             dataTags,
             fileCloseRequested,
             filePopupMode,
-            increaseFileLockFail,
             lm,
             notification,
             setFilePassword,
+            warnFailedAttempts,
         ]
     );
 
@@ -502,11 +530,10 @@ const App = ({ className }: AppProps) => {
                 setSaveChangedFileQueryVisible(false);
                 setFileCloseRequested(false);
             } else {
-                // Reloading the window will do the same as re-setting multiple state variables.
-                globalThis.location.reload();
+                closeVaultAndReload();
             }
         },
-        [saveFileCallback]
+        [closeVaultAndReload, saveFileCallback]
     );
 
     // Display the about popup.
@@ -537,10 +564,13 @@ const App = ({ className }: AppProps) => {
         }
     }, [expandTreeListSelection, isNewFile]);
 
-    // Locks the view via user interaction.
+    // Locks the view and the vault of the backend via user interaction.
     const lockViewClick = React.useCallback(() => {
         lockView();
-    }, [lockView]);
+        void lockVault().catch(error => {
+            notification("error", lm("fileSaveFail", undefined, { msg: String(error) }), 5);
+        });
+    }, [lm, lockView, notification]);
 
     // Open the Help from github.io to an external browser.
     const onHelpClick = React.useCallback(() => {
@@ -569,25 +599,26 @@ const App = ({ className }: AppProps) => {
     const queryUnlockPassword = React.useCallback(
         (userAccepted: boolean, password?: string) => {
             setLockPasswordQueryVisible(false);
-            if (userAccepted) {
-                // Validate the unlock password against the given password.
-                if (password === getFilePassword()) {
-                    // The password validation was successful, unlock the view.
-                    setViewLocked(false);
-                    void expandTreeListSelection();
-                    setPasswordFailedCount(0);
-                } else {
-                    // The password validation failed, keep the view locked and update the
-                    // invalid password counter.
-                    increaseFileLockFail();
-                    setViewLocked(true);
-                }
+            if (userAccepted && password !== undefined) {
+                // Keep the view locked while the backend validates the password by opening the file.
+                setViewLocked(true);
+                void openVault(currentFile, password)
+                    .then(() => {
+                        // The password validation was successful, unlock the view.
+                        setViewLocked(false);
+                        void expandTreeListSelection();
+                    })
+                    .catch(error => {
+                        // The password validation failed, keep the view locked.
+                        notification("error", lm("unlockFail", undefined, { msg: String(error) }), 5);
+                        void warnFailedAttempts();
+                    });
             } else {
                 // The popup querying the password was not accepted, keep the view locked.
                 setViewLocked(true);
             }
         },
-        [expandTreeListSelection, getFilePassword, increaseFileLockFail]
+        [currentFile, expandTreeListSelection, lm, notification, warnFailedAttempts]
     );
 
     // Restore the window state when the settings have been loaded.
@@ -657,7 +688,7 @@ const App = ({ className }: AppProps) => {
             <StyledTitle //
                 title={title}
                 onClose={fileSaveQueryAbortCloseCallback}
-                onUserInteraction={resetTimeOut}
+                onUserInteraction={onUserActivity}
                 darkMode={previewDarkMode ?? settings.dark_mode ?? false}
             />
             <StyledAppMenuToolbar //
@@ -684,11 +715,11 @@ const App = ({ className }: AppProps) => {
             />
             <div //
                 className={classNames(App.name, className)}
-                onMouseDown={resetTimeOut}
-                onMouseUp={resetTimeOut}
-                onMouseMove={resetTimeOut}
-                onKeyDown={resetTimeOut}
-                onKeyUp={resetTimeOut}
+                onMouseDown={onUserActivity}
+                onMouseUp={onUserActivity}
+                onMouseMove={onUserActivity}
+                onKeyDown={onUserActivity}
+                onKeyUp={onUserActivity}
             >
                 <div id="mainView" className="App-itemsView">
                     <StyledPasswordList //
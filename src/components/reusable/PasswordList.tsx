@@ -220,6 +220,18 @@ const isGroup = (entry: DataEntry) => {
     return entry.parentId === -1;
 };
 
+/**
+ * Gets the searchable text of the custom fields of the specified {@link DataEntry}. The protected fields are excluded.
+ * @param {DataEntry} entry The entry to get the text for.
+ * @returns {string} The values of the non-protected custom fields separated with spaces.
+ */
+const fieldsText = (entry: DataEntry) => {
+    return (entry.fields ?? [])
+        .filter(f => f.type !== "protected")
+        .map(f => f.value ?? "")
+        .join(" ");
+};
+
 const filterAnd = (value: DataEntry, search: string) => {
     const findStrings = search.split(" ").map(f => f.trim().toLowerCase());
     if (findStrings.length === 0) {
@@ -228,7 +240,7 @@ const filterAnd = (value: DataEntry, search: string) => {
 
     let result = true;
     const searchLower =
-        `${value.address} ${value.domain} ${value.name} ${value.notes} ${value.otpAuthKey} ${value.password} ${value.tags} ${value.userName}`.toLowerCase();
+        `${value.address} ${value.domain} ${value.name} ${value.notes} ${value.otpAuthKey} ${value.password} ${value.tags} ${value.userName} ${fieldsText(value)}`.toLowerCase();
     for (const searchPart of findStrings) {
         if (searchLower.includes(searchPart)) {
             continue;
@@ -245,7 +257,7 @@ const filterOr = (value: DataEntry, search: string) => {
     const findStrings = search.split(" ").map(f => f.trim().toLowerCase());
 
     const searchLower =
-        `${value.address} ${value.domain} ${value.name} ${value.notes} ${value.otpAuthKey} ${value.password} ${value.tags} ${value.userName}`.toLowerCase();
+        `${value.address} ${value.domain} ${value.name} ${value.notes} ${value.otpAuthKey} ${value.password} ${value.tags} ${value.userName} ${fieldsText(value)}`.toLowerCase();
     return findStrings.some(f => searchLower.includes(f));
 };
 
@@ -111,6 +111,16 @@ const PreferencesPopup = ({
         [settings, settingsInternal]
     );
 
+    // Save the backup count NumberBox value into the internal state.
+    const setBackupCount = React.useCallback(
+        (value: number | null) => {
+            if (value !== null) {
+                setSettingsInternal({ ...(settingsInternal ?? settings), backup_count: value });
+            }
+        },
+        [settings, settingsInternal]
+    );
+
     const setSaveWindowState = React.useCallback(
         (e: CheckboxChangeEvent) => {
             setSettingsInternal({ ...(settingsInternal ?? settings), save_window_state: e.target.checked === true });
@@ -187,6 +197,19 @@ const PreferencesPopup = ({
                                 />
                             </td>
                         </tr>
+                        <tr>
+                            <td>
+                                <div>{ls("fileBackupCount")}</div>
+                            </td>
+                            <td>
+                                <InputNumber //
+                                    value={settingsInternal?.backup_count}
+                                    min={0}
+                                    max={20}
+                                    onChange={setBackupCount}
+                                />
+                            </td>
+                        </tr>
 
                         <tr>
                             <td>
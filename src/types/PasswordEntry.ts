@@ -34,6 +34,22 @@ type FileData = {
     dataOptions?: FileOptions;
     /** The version of the file. */
     version: number;
+    /** The deleted entries and categories. */
+    trash?: TrashItem[];
+};
+
+/**
+ * A deleted entry or category kept in the recycle bin.
+ */
+type TrashItem = {
+    /** An unique identifier for the item in the recycle bin. */
+    id: number;
+    /** The time of the deletion as seconds since the Unix epoch. */
+    deleted: number;
+    /** The deleted entry or category. */
+    entry: DataEntry;
+    /** In case of a category the entries the category was removed from as their first tag. */
+    entryIds?: number[];
 };
 
 /**
@@ -109,6 +125,71 @@ type DataEntry = {
     useMarkdown?: boolean;
     /** A value indicating whether to use monospaced font for the {@link DataEntry.notes} rendering. */
     useMonospacedFont?: boolean;
+    /** The additional user-defined fields of the entry in their display order. */
+    fields?: CustomField[];
+    /** The date the entry expires on in the `YYYY-MM-DD` format. */
+    expiryDate?: string;
+    /** The amount of days after which the password should be changed. */
+    rotationDays?: number;
+    /** The time the password was last changed as seconds since the Unix epoch. */
+    passwordChanged?: number;
+    /** The files attached to the entry. The contents are stored encrypted in the blob store next to the vault file. */
+    attachments?: Attachment[];
+    /** The previous versions of the entry, the oldest first. */
+    history?: EntryRevision[];
+};
+
+/**
+ * A file attached to an entry.
+ */
+type Attachment = {
+    /** The identifier of the attachment within the entry. */
+    id: number;
+    /** The file name of the attachment. */
+    name: string;
+    /** The size of the attachment in bytes. */
+    size: number;
+    /** The SHA-256 hash of the contents; the attachments with the same contents share a blob. */
+    hash: string;
+    /** The name of the encrypted blob holding the contents. */
+    blob: string;
+    /** The random key the blob is encrypted with. */
+    key?: string;
+    /** The time the attachment was added as seconds since the Unix epoch. */
+    added: number;
+};
+
+/**
+ * The types of the {@link CustomField} values. A `protected` value is hidden and excluded from the searches and
+ * from the exports unless asked.
+ */
+type CustomFieldType = "text" | "protected" | "url" | "email" | "date" | "number";
+
+/**
+ * An additional user-defined field of an entry.
+ */
+type CustomField = {
+    /** The name of the field. */
+    name: string;
+    /** The type of the field value. */
+    type: CustomFieldType;
+    /** The value of the field. A `date` is in the `YYYY-MM-DD` format. */
+    value?: string;
+};
+
+/**
+ * A change of an entry: the fields changed and their values before the change.
+ */
+type EntryRevision = {
+    /** The time of the change as seconds since the Unix epoch. */
+    modified: number;
+    /** The changed fields with their previous values. */
+    changes: {
+        /** The name of the field, e.g. `userName`. */
+        field: string;
+        /** The value of the field before the change. */
+        oldValue?: string;
+    }[];
 };
 
 /**
@@ -133,6 +214,48 @@ type FileOptions = {
     useMonospacedFont?: boolean;
     /** A value indicating whether to use HTML on entry editing and rendering. */
     useHtmlOnNotes?: boolean;
+    /** The amount of days the deleted entries are kept in the recycle bin before they are purged automatically. */
+    trashRetentionDays?: number;
+    /** The named password generator policies of the file. These take precedence over the ones in the settings. */
+    passwordProfiles?: PasswordProfile[];
+};
+
+/**
+ * The rules for generating a password.
+ */
+type PasswordPolicy = {
+    /** The length of the password. */
+    length: number;
+    /** A value indicating whether to use lower-case letters. */
+    lowercase: boolean;
+    /** A value indicating whether to use upper-case letters. */
+    uppercase: boolean;
+    /** A value indicating whether to use digits. */
+    digits: boolean;
+    /** A value indicating whether to use symbols. */
+    symbols: boolean;
+    /** The symbols to use instead of the default ones. */
+    symbolSet?: string;
+    /** A value indicating whether to exclude the characters easily mistaken for each other, e.g. `0` and `O`. */
+    excludeLookAlikes: boolean;
+    /** The minimum amount of lower-case letters. */
+    minLowercase: number;
+    /** The minimum amount of upper-case letters. */
+    minUppercase: number;
+    /** The minimum amount of digits. */
+    minDigits: number;
+    /** The minimum amount of symbols. */
+    minSymbols: number;
+};
+
+/**
+ * A named password policy.
+ */
+type PasswordProfile = {
+    /** The name of the profile. */
+    name: string;
+    /** The password policy of the profile. */
+    policy: PasswordPolicy;
 };
 
 /**
@@ -154,4 +277,17 @@ const isGeneralEntry = <T>(value: DataEntry | GeneralEntry<T>): value is General
 };
 
 export { isDataEntry, isGeneralEntry, makeFont };
-export type { DataEntry, GeneralEntry, FileData, FileOptions, CssFont };
+export type {
+    DataEntry,
+    Attachment,
+    CustomField,
+    CustomFieldType,
+    EntryRevision,
+    GeneralEntry,
+    FileData,
+    FileOptions,
+    CssFont,
+    TrashItem,
+    PasswordPolicy,
+    PasswordProfile,
+};
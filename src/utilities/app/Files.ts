@@ -24,15 +24,14 @@ SOFTWARE.
 
 import { invoke } from "@tauri-apps/api/core";
 import { open, save } from "@tauri-apps/plugin-dialog";
-import { generalId } from "../../misc/DataUtils";
 import {
     type DataEntry,
     type FileData,
     type FileOptions,
     type GeneralEntry,
     isDataEntry,
-    isGeneralEntry,
 } from "../../types/PasswordEntry";
+import type { SaveOptions } from "./TauriBackend";
 
 /**
  * A result type for the {@link loadFile} and {@link saveFile} functions.
@@ -113,15 +112,16 @@ const selectFileToSave = async (extensionName: string, extension = "pkd") => {
 };
 
 /**
- * Loads the specified file, decrypts the data and returns the decrypted data.
+ * Loads the specified file, decrypts the data and returns the decrypted data in the current file data format.
  * @param password The password used in the encryption.
  * @param fileName The file name to decrypt the data from.
+ * @param keyFile An optional key file used in the encryption along with the password.
  * @returns A {@link FileResult} value with the loaded data or indicating failure.
  */
-const loadFile = async (password: string, fileName: string) => {
+const loadFile = async (password: string, fileName: string, keyFile?: string) => {
     let fileData: BackendResult = { error: false, value: "" };
     try {
-        fileData = await invoke("load_file", { fileName: fileName, password: password });
+        fileData = await invoke("load_file", { fileName: fileName, password: password, keyFile: keyFile });
         if (fileData.error) {
             return { ...failed, errorMessage: fileData.value };
         }
@@ -129,123 +129,54 @@ const loadFile = async (password: string, fileName: string) => {
         return failed;
     }
 
-    const items = JSON.parse(fileData.value) as (DataEntry | GeneralEntry<string>)[];
+    // The backend upgrades the data saved in the earlier formats to the current format.
+    const data = JSON.parse(fileData.value) as FileData;
 
-    // In the old file "format" everything was in same array with different types.
-    if (Array.isArray(items)) {
-        const itemsGeneral: GeneralEntry<string>[] = items.filter((f: DataEntry | GeneralEntry<string>) =>
-            isGeneralEntry(f)
-        ) as GeneralEntry<string>[];
-        const itemTag = itemsGeneral.filter(f => f.type === "tags");
+    const itemTag = (data.metaData ?? []).filter(f => f.type === "tags");
 
-        return {
-            fileName: fileName,
-            fileData: items.filter((f: DataEntry | GeneralEntry<string>) => isDataEntry(f)),
-            tags: itemTag.length > 0 ? itemTag[0] : EmptyGeneralEntryString,
-            ok: true,
-        } as FileResult;
-    } else {
-        // The new format.
-        const data = items as FileData;
-
-        const itemTag = (data.metaData ?? []).filter(f => f.type === "tags");
-        const tags = itemTag.length > 0 ? itemTag[0] : EmptyGeneralEntryString;
-
-        // The latest format will have a version
-        if (!data.version) {
-            // Convert the data to add the parent items as tags also
-
-            for (const f of data.entries.filter(f => f.parentId === -1)) {
-                if (!tags.values.includes(f.name)) {
-                    tags.values.push(f.name);
-                }
-
-                for (const g of data.entries.filter(g => g.parentId === f.id)) {
-                    if (!g.tags?.split("|").includes(f.name)) {
-                        g.tags = g.tags ? `${f.name}|` + g.tags : f.name;
-                    }
-                }
-            }
-
-            data.entries.push({
-                parentId: -1,
-                id: generalId,
-                name: unCategorized,
-            });
-
-            // The folder seem to have been able to replicate in the previous format
-            data.entries = cleanDuplicates(data.entries);
-
-            data.version = 1;
-        }
-
-        // The format with main tags.
-        return {
-            fileName: fileName,
-            fileData: data.entries,
-            tags: itemTag.length > 0 ? itemTag[0] : EmptyGeneralEntryString,
-            ok: true,
-            dataOptions: data.dataOptions,
-            version: data.version,
-        } as FileResult;
-    }
-};
-
-/**
- * Remove duplicate categories from the data by merging them into one with the lowest id.
- * @param entries The data to clean.
- * @returns The cleaned data.
- */
-const cleanDuplicates = (entries: DataEntry[]) => {
-    const parentIdUpdates = new Array<[number, number]>();
-
-    // Find duplicate data.entries with same name and parentId of -1
-    for (const f of entries.filter(f => f.parentId === -1)) {
-        for (const g of entries.filter(g => g.parentId === -1 && g.name === f.name && g.id !== f.id)) {
-            if (!parentIdUpdates.some(h => h[0] === f.id && h[1] === g.id)) {
-                parentIdUpdates.push([g.id, f.id]);
-            }
-        }
-    }
-
-    // Update the parent ids from the old ids to the new ids
-    for (const [oldId, newId] of parentIdUpdates) {
-        for (const f of entries.filter(f => f.parentId === oldId)) {
-            f.parentId = newId;
-        }
-    }
-
-    // Remove the old ids from the data
-    for (const [oldId] of parentIdUpdates) {
-        entries = entries.filter(f => f.id !== oldId);
-    }
-
-    return entries;
+    return {
+        fileName: fileName,
+        fileData: data.entries,
+        tags: itemTag.length > 0 ? itemTag[0] : EmptyGeneralEntryString,
+        ok: true,
+        dataOptions: data.dataOptions,
+        version: data.version,
+    } as FileResult;
 };
 
 /**
  * Saves the specified data into the specified file encrypted using the specified password.
  * @param fileData The data to save to file in encrypted form.
  * @param password The password used in the encryption.
+ * @param keyFile An optional key file to use in the encryption along with the password.
+ * @param options Optional key derivation parameters, cipher suite and compression. The values not specified are kept from the existing file. Set `overwrite` to replace an existing file the password doesn't unlock.
  * @returns A {@link FileResult} value with the the file name the data was saved into along with a success flag.
  */
-const saveFile = async (fileData: FileData, password: string, fileName: string) => {
+const saveFile = async (
+    fileData: FileData,
+    password: string,
+    fileName: string,
+    keyFile?: string,
+    options?: SaveOptions
+) => {
     const saveData = JSON.stringify(fileData);
 
     try {
-        const saved: boolean = await invoke("save_file", {
+        await invoke("save_file", {
             jsonData: saveData,
             fileName: fileName,
             password: password,
+            keyFile: keyFile,
+            options: options,
         });
         return {
             fileName: fileName,
             fileData: [],
             tags: EmptyGeneralEntryString,
-            ok: saved,
+            ok: true,
         } as FileResult;
-    } catch {
-        return failed;
+    } catch (error) {
+        return { ...failed, errorMessage: String(error) };
     }
 };
 
@@ -25,6 +25,7 @@ SOFTWARE.
 import { invoke } from "@tauri-apps/api/core";
 import * as React from "react";
 import type { Locales } from "../../I18n";
+import type { PasswordProfile } from "../../types/PasswordEntry";
 
 /**
  * The software settings returned by the Tauri app.
@@ -40,6 +41,12 @@ export type Settings = {
     save_window_state: boolean;
     /** A value indicating whether to use dark mode with the application. */
     dark_mode: boolean;
+    /** An amount of rotating encrypted backups to keep next to a saved file. 0 is disabled. */
+    backup_count: number;
+    /** The named password generator policies. */
+    password_profiles?: PasswordProfile[];
+    /** The locally downloaded Have I Been Pwned SHA-1 password hash file ordered by hash. */
+    hibp_file?: string;
     /** A value indicating whether a load error occurred. */
     error: boolean;
     /** An error message if one occurred. */
@@ -23,6 +23,349 @@ SOFTWARE.
 */
 
 import { invoke } from "@tauri-apps/api/core";
+import type { DataEntry, FileOptions, PasswordPolicy } from "../../types/PasswordEntry";
+
+/**
+ * The Argon2 key derivation function parameters of an encrypted file.
+ */
+type KdfParams = {
+    /** The Argon2 variant. */
+    variant: "argon2d" | "argon2i" | "argon2id";
+    /** The memory cost in KiB. */
+    mem_cost: number;
+    /** The amount of passes over the memory. */
+    time_cost: number;
+    /** The degree of parallelism. */
+    lanes: number;
+};
+
+/**
+ * The cipher suites an encrypted file can be encrypted with.
+ */
+type CipherSuite = "aes-256-gcm-siv" | "xchacha20-poly1305";
+
+/**
+ * The compression methods applied to the file data before the encryption.
+ */
+type Compression = "none" | "zstd" | "deflate";
+
+/**
+ * The options for saving a file. The values not specified are kept from the existing file or defaulted for a new file.
+ */
+type SaveOptions = {
+    /** The key derivation parameters for the password, e.g. from {@link calibrateKdf}. */
+    kdf?: KdfParams;
+    /** The cipher suite to encrypt the data with. */
+    cipher?: CipherSuite;
+    /** The compression to apply to the data before the encryption. */
+    compression?: Compression;
+    /** A value indicating whether to encrypt the data in 64 KiB chunks so it is streamed with a bounded memory use. */
+    chunked?: boolean;
+    /** A value indicating whether to replace an existing file the key doesn't unlock, e.g. with "save as". Such a file is kept and an error is returned otherwise. */
+    overwrite?: boolean;
+};
+
+/**
+ * A structured report of a file integrity verification.
+ */
+type VerificationReport = {
+    /** The file which was verified. */
+    file_name: string;
+    /** The container format version of the file; 0 for the legacy format. `null` if the header couldn't be read. */
+    format_version: number | null;
+    /** A value indicating whether the container header was read and its checksum matched. */
+    structure_valid: boolean;
+    /** A value indicating whether the length of the encrypted data in the header matches the file size. */
+    length_consistent: boolean;
+    /** A value indicating whether the password unlocked the file and the authentication tag of the data matched. */
+    authenticated: boolean;
+    /** A value indicating whether the password was rejected as invalid. */
+    invalid_password: boolean;
+    /** A value indicating whether the decrypted data is valid JSON of the expected file data format. */
+    schema_valid: boolean;
+    /** A value indicating whether all the checks passed. */
+    valid: boolean;
+    /** The problems found in the file. */
+    errors: string[];
+};
+
+/**
+ * The result of the key derivation parameter calibration.
+ */
+type KdfCalibration = {
+    /** The proposed key derivation parameters. */
+    params: KdfParams;
+    /** The measured time to derive a key with the proposed parameters in milliseconds. */
+    duration_ms: number;
+};
+
+/**
+ * The public information of a key slot of an encrypted file.
+ */
+type KeySlotInfo = {
+    /** The identifier of the key slot. */
+    id: number;
+    /** The kind of the key slot. */
+    kind: "password" | "recovery";
+    /** The label of the key slot. */
+    label: string;
+    /** The key derivation parameters of the key slot. */
+    kdf: KdfParams;
+};
+
+/**
+ * The options for generating a passphrase.
+ */
+type PassphraseOptions = {
+    /** The amount of words in the passphrase. */
+    wordCount?: number;
+    /** The separator between the words. */
+    separator?: string;
+    /** The capitalization of the words; `random` capitalizes the first letter of each word at random. */
+    capitalization?: "none" | "firstLetter" | "upper" | "random";
+    /** A value indicating whether to insert a random digit into a random position of a random word. */
+    insertDigit?: boolean;
+};
+
+/**
+ * A generated passphrase.
+ */
+type Passphrase = {
+    /** The passphrase. */
+    passphrase: string;
+    /** The estimated entropy of the passphrase in bits assuming the wordlist and the options are known. */
+    entropyBits: number;
+};
+
+/**
+ * The warning about a weak password.
+ */
+type StrengthWarning =
+    | "straightRowsOfKeysAreEasy"
+    | "shortKeyboardPatternsAreEasy"
+    | "repeatsLikeAaaAreEasy"
+    | "repeatsLikeAbcAbcAreEasy"
+    | "sequencesLikeAbcAreEasy"
+    | "recentYearsAreEasy"
+    | "datesAreOftenEasy"
+    | "topTenPassword"
+    | "topHundredPassword"
+    | "commonPassword"
+    | "similarToCommonPassword"
+    | "aWordByItselfIsEasy"
+    | "containsUserInput";
+
+/**
+ * The suggestion for a stronger password.
+ */
+type StrengthSuggestion =
+    | "useAFewWords"
+    | "noNeedForSymbols"
+    | "addAnotherWord"
+    | "capitalizationDoesntHelp"
+    | "allUppercaseIsAlmostAsEasy"
+    | "reversedWordsArentMuchHarder"
+    | "predictableSubstitutions"
+    | "useLongerKeyboardPattern"
+    | "avoidRepeatedWordsAndCharacters"
+    | "avoidSequences"
+    | "avoidRecentYears"
+    | "avoidYearsAssociatedWithYou"
+    | "avoidDatesAndYearsAssociatedWithYou";
+
+/**
+ * The estimated strength of a password.
+ */
+type StrengthEstimate = {
+    /** The score from 0 (too guessable) to 4 (very unguessable). */
+    score: 0 | 1 | 2 | 3 | 4;
+    /** The estimated amount of guesses needed to crack the password. */
+    guesses: number;
+    /** The base 10 logarithm of the guesses. */
+    guessesLog10: number;
+    /** The estimated times in seconds to crack the password in different attack scenarios. */
+    crackTimesSeconds: {
+        /** An online attack against a service limiting the attempts to 100 per hour. */
+        onlineThrottling: number;
+        /** An online attack against a service allowing 10 attempts per second. */
+        onlineNoThrottling: number;
+        /** An offline attack against a slow password hash, 10 000 guesses per second. */
+        offlineSlowHashing: number;
+        /** An offline attack against a fast hash, 10 billion guesses per second. */
+        offlineFastHashing: number;
+    };
+    /** The warning and the suggestions; empty for the strong passwords. */
+    feedback: {
+        /** The main weakness of the password if any. */
+        warning: StrengthWarning | null;
+        /** The suggestions for a stronger password. */
+        suggestions: StrengthSuggestion[];
+    };
+};
+
+/**
+ * An entry reported by the vault audit.
+ */
+type AuditEntry = {
+    /** The identifier of the entry. */
+    id: number;
+    /** The name of the entry. */
+    name: string;
+};
+
+/**
+ * The security audit of the entries of a vault.
+ */
+type AuditReport = {
+    /** The amount of audited entries. */
+    entryCount: number;
+    /** The entries with a weak password, the weakest first. */
+    weakPasswords: (AuditEntry & { score: number; warning: StrengthWarning | null })[];
+    /** The passwords used in more than one entry grouped by a hash salted for each audit, the most reused first. */
+    reusedPasswords: { hash: string; entries: AuditEntry[] }[];
+    /** The entries of the services supporting two-factor authentication without an OTP key. */
+    missingTwoFactor: AuditEntry[];
+    /** The entries without a password. */
+    emptyPasswords: AuditEntry[];
+    /** The groups of entries with the same site and the same user name. */
+    duplicateEntries: AuditEntry[][];
+};
+
+/**
+ * The entries of a vault with breached passwords.
+ */
+type BreachReport = {
+    /** The amount of entries with a password checked. */
+    checked: number;
+    /** The entries with a breached password with the amount of times the password appears in the breach data. */
+    breached: (AuditEntry & { count: number })[];
+};
+
+/**
+ * An entry expired or expiring soon.
+ */
+type ExpiryInfo = {
+    /** The identifier of the entry. */
+    id: number;
+    /** The name of the entry. */
+    name: string;
+    /** The time the entry expires as seconds since the Unix epoch. */
+    expires: number;
+    /** The reason for the expiry: the expiry date or the rotation interval of the password. */
+    reason: "date" | "rotation";
+};
+
+/**
+ * The entries of a vault expired or expiring soon, both the earliest expiry first.
+ */
+type ExpiryReport = {
+    /** The expired entries. */
+    expired: ExpiryInfo[];
+    /** The entries expiring within the warning period. */
+    expiringSoon: ExpiryInfo[];
+};
+
+/**
+ * The name of the event the backend emits with the newly expired entries ({@link ExpiryInfo}[]) while a vault is open.
+ */
+const entryExpiredEvent = "entry-expired";
+
+/**
+ * The non-secret information of a vault open in the backend.
+ */
+type VaultSummary = {
+    /** The file the vault was opened from. */
+    fileName: string;
+    /** The tags used for the entries in the vault. */
+    tags: string[];
+    /** Global file options. */
+    dataOptions: FileOptions | null;
+    /** The version of the file data. */
+    version: number;
+    /** A value indicating whether the vault has unsaved changes. */
+    changed: boolean;
+    /** The entries expired or expiring within 14 days. */
+    expiry: ExpiryReport;
+};
+
+/**
+ * The non-secret information of an attachment of an entry.
+ */
+type AttachmentInfo = {
+    /** The identifier of the attachment within the entry. */
+    id: number;
+    /** The file name of the attachment. */
+    name: string;
+    /** The size of the attachment in bytes. */
+    size: number;
+    /** The time the attachment was added as seconds since the Unix epoch. */
+    added: number;
+};
+
+/**
+ * The non-secret information of an item in the recycle bin of a vault.
+ */
+type TrashInfo = {
+    /** The identifier of the item in the recycle bin. */
+    id: number;
+    /** The time of the deletion as seconds since the Unix epoch. */
+    deleted: number;
+    /** The identifier the entry or the category had before the deletion. */
+    entryId: number;
+    /** The name of the entry or the category. */
+    name: string;
+    /** A value indicating whether the item is a category. */
+    isCategory: boolean;
+};
+
+/**
+ * The non-secret information of an entry revision.
+ */
+type RevisionInfo = {
+    /** The index of the revision, 0 being the oldest one. */
+    index: number;
+    /** The time of the change as seconds since the Unix epoch. */
+    modified: number;
+    /** The names of the changed fields. */
+    fields: string[];
+};
+
+/**
+ * A field differing between two versions of an entry.
+ */
+type FieldDiff = {
+    /** The name of the field, e.g. `userName`. */
+    field: string;
+    /** The value of the field in the older version. */
+    oldValue: string | null;
+    /** The value of the field in the newer version. */
+    newValue: string | null;
+};
+
+/**
+ * The lock status and the failed unlock attempts of the backend.
+ */
+type LockStatus = {
+    /** A value indicating whether a vault is locked. */
+    locked: boolean;
+    /** The file of the locked vault. */
+    lockedFile: string | null;
+    /** The amount of consecutive failed unlock attempts. */
+    failedAttempts: number;
+    /** The amount of seconds before the next unlock attempt is allowed. */
+    retryAfterSeconds: number;
+};
+
+/**
+ * The name of the event the backend emits with the file name when it locks the vault on the lock timeout.
+ */
+const vaultLockedEvent = "vault-locked";
+
+/**
+ * The name of the event the backend emits with the error message when the unsaved changes of the vault couldn't be saved before it was locked on the lock timeout.
+ * The vault is locked anyway and the unsaved changes are lost.
+ */
+const vaultSaveFailedEvent = "vault-save-failed";
 
 /**
  * Loads the image file contents from the file system into a number array.
@@ -34,4 +377,599 @@ const loadImageFile = async (fileName: string) => {
     return result;
 };
 
-export { loadImageFile };
+/**
+ * Changes the password of the specified file. The file is decrypted and re-encrypted in the backend.
+ * @param fileName The name of the file to change the password of.
+ * @param oldPassword The current password of the file.
+ * @param newPassword The new password for the file.
+ * @param newKdfParams Optional new key derivation parameters. The current ones are kept if not specified.
+ * @param oldKeyFile An optional key file the file is currently encrypted with.
+ * @param newKeyFile An optional key file to use along with the new password.
+ * @returns A promise which rejects with an error message if the password change failed.
+ **/
+const changePassword = async (
+    fileName: string,
+    oldPassword: string,
+    newPassword: string,
+    newKdfParams?: KdfParams,
+    oldKeyFile?: string,
+    newKeyFile?: string
+) => {
+    await invoke("change_password", {
+        fileName: fileName,
+        oldPassword: oldPassword,
+        newPassword: newPassword,
+        newKdfParams: newKdfParams,
+        oldKeyFile: oldKeyFile,
+        newKeyFile: newKeyFile,
+    });
+};
+
+/**
+ * Generates a new key file with random contents to be used along with or instead of a file password.
+ * @param fileName The name of the key file to create. An existing file is not overridden.
+ * @returns A promise which rejects with an error message if the key file generation failed.
+ **/
+const generateKeyFile = async (fileName: string) => {
+    await invoke("generate_key_file", { fileName: fileName });
+};
+
+/**
+ * Adds a new password key slot into the specified file so another person can open the file with their own password.
+ * @param fileName The name of the file to add the key slot into.
+ * @param password A current password of the file.
+ * @param newPassword The password for the new key slot.
+ * @param label A label for the key slot, e.g. the name of the person the slot belongs to.
+ * @param kdfParams Optional key derivation parameters for the new key slot.
+ * @param keyFile An optional key file combined with the current password.
+ * @param newKeyFile An optional key file combined with the new password.
+ * @returns The identifier of the new key slot.
+ **/
+const addPasswordSlot = async (
+    fileName: string,
+    password: string,
+    newPassword: string,
+    label: string,
+    kdfParams?: KdfParams,
+    keyFile?: string,
+    newKeyFile?: string
+) => {
+    const result: number = await invoke("add_password_slot", {
+        fileName: fileName,
+        password: password,
+        keyFile: keyFile,
+        newPassword: newPassword,
+        newKeyFile: newKeyFile,
+        label: label,
+        kdfParams: kdfParams,
+    });
+    return result;
+};
+
+/**
+ * Lists the key slots of the specified file. No password is needed.
+ * @param fileName The name of the file to list the key slots of.
+ * @returns The key slots of the file.
+ **/
+const getKeySlots = async (fileName: string) => {
+    const result: KeySlotInfo[] = await invoke("get_key_slots", { fileName: fileName });
+    return result;
+};
+
+/**
+ * Revokes a key slot of the specified file. The last key slot of a file can't be revoked.
+ * @param fileName The name of the file to revoke the key slot from.
+ * @param password A password of any key slot of the file.
+ * @param slotId The identifier of the key slot to revoke.
+ * @param keyFile An optional key file combined with the password.
+ * @returns A promise which rejects with an error message if the key slot revocation failed.
+ **/
+const removeKeySlot = async (fileName: string, password: string, slotId: number, keyFile?: string) => {
+    await invoke("remove_key_slot", { fileName: fileName, password: password, keyFile: keyFile, slotId: slotId });
+};
+
+/**
+ * Generates a recovery key and adds a key slot for it into the specified file.
+ * @param fileName The name of the file to add the recovery key slot into.
+ * @param password A current password of the file.
+ * @param label A label for the recovery key slot.
+ * @param keyFile An optional key file combined with the password.
+ * @returns The printable recovery key. The key is not stored anywhere and is shown only once.
+ **/
+const generateRecoveryKey = async (fileName: string, password: string, label: string, keyFile?: string) => {
+    const result: string = await invoke("generate_recovery_key", {
+        fileName: fileName,
+        password: password,
+        keyFile: keyFile,
+        label: label,
+    });
+    return result;
+};
+
+/**
+ * Benchmarks the key derivation on this machine and proposes Argon2id parameters hitting the specified unlock time.
+ * The parameters can be stored into a file by passing them to the file save.
+ * @param targetMs The desired unlock time in milliseconds.
+ * @returns The proposed parameters along with the measured unlock time.
+ **/
+const calibrateKdf = async (targetMs: number) => {
+    const result: KdfCalibration = await invoke("calibrate_kdf", { targetMs: targetMs });
+    return result;
+};
+
+/**
+ * Verifies the integrity of the specified file without passing the decrypted data to the frontend.
+ * @param fileName The name of the file to verify, e.g. a backup file.
+ * @param password The password of the file.
+ * @param keyFile An optional key file combined with the password.
+ * @returns A report of the container structure, length, authentication and data format checks.
+ **/
+const verifyFile = async (fileName: string, password: string, keyFile?: string) => {
+    const result: VerificationReport = await invoke("verify_file", {
+        fileName: fileName,
+        password: password,
+        keyFile: keyFile,
+    });
+    return result;
+};
+
+/**
+ * Encrypts the specified file into a container file, e.g. a large attachment. The data is streamed in chunks.
+ * @param sourceFileName The name of the file to encrypt.
+ * @param fileName The name of the container file to write the encrypted data into.
+ * @param password The password to use for the encryption.
+ * @param options Optional encryption options. The chunked encryption is used unless disabled.
+ * @param keyFile An optional key file combined with the password.
+ * @returns A promise which rejects with an error message if the encryption failed.
+ **/
+const encryptFile = async (
+    sourceFileName: string,
+    fileName: string,
+    password: string,
+    options?: SaveOptions,
+    keyFile?: string
+) => {
+    await invoke("encrypt_file", {
+        sourceFileName: sourceFileName,
+        fileName: fileName,
+        password: password,
+        keyFile: keyFile,
+        options: options,
+    });
+};
+
+/**
+ * Decrypts the specified container file into a plain file, e.g. to export a large attachment.
+ * The data is streamed in chunks.
+ * @param fileName The name of the container file to decrypt.
+ * @param targetFileName The name of the file to write the decrypted data into.
+ * @param password The password of the container file.
+ * @param keyFile An optional key file combined with the password.
+ * @returns A promise which rejects with an error message if the decryption failed.
+ **/
+const decryptFile = async (fileName: string, targetFileName: string, password: string, keyFile?: string) => {
+    await invoke("decrypt_file", {
+        fileName: fileName,
+        targetFileName: targetFileName,
+        password: password,
+        keyFile: keyFile,
+    });
+};
+
+/**
+ * Opens the specified file into a vault session kept in the backend memory. A previously open vault is closed.
+ * @param fileName The name of the file to open.
+ * @param password The password of the file.
+ * @param keyFile An optional key file combined with the password.
+ * @returns The non-secret information of the vault.
+ **/
+const openVault = async (fileName: string, password: string, keyFile?: string) => {
+    const result: VaultSummary = await invoke("open_vault", {
+        fileName: fileName,
+        password: password,
+        keyFile: keyFile,
+    });
+    return result;
+};
+
+/**
+ * Closes the open vault session. The decrypted data and the key are wiped from the backend memory.
+ * @returns A promise which rejects with an error message if the vault couldn't be closed.
+ **/
+const closeVault = async () => {
+    await invoke("close_vault");
+};
+
+/**
+ * Lists the entries and the categories of the open vault without the passwords, the notes and the OTP keys.
+ * @returns The entries and the categories of the vault.
+ **/
+const listEntries = async () => {
+    const result: DataEntry[] = await invoke("list_entries");
+    return result;
+};
+
+/**
+ * Gets an entry of the open vault without the password.
+ * @param id The identifier of the entry.
+ * @returns The entry.
+ **/
+const getEntry = async (id: number) => {
+    const result: DataEntry = await invoke("get_entry", { id: id });
+    return result;
+};
+
+/**
+ * Gets the password of an entry of the open vault.
+ * @param id The identifier of the entry.
+ * @returns The password of the entry.
+ **/
+const getPassword = async (id: number) => {
+    const result: string = await invoke("get_password", { id: id });
+    return result;
+};
+
+/**
+ * Gets the value of a custom field of an entry of the open vault.
+ * @param id The identifier of the entry.
+ * @param name The name of the field.
+ * @returns The value of the field.
+ **/
+const getFieldValue = async (id: number, name: string) => {
+    const result: string = await invoke("get_field_value", { id: id, name: name });
+    return result;
+};
+
+/**
+ * Searches the entries of the open vault.
+ * @param search The words to search for separated with spaces.
+ * @param allWords A value indicating whether an entry must contain all the words instead of any of them.
+ * @param includeProtected A value indicating whether to search from the passwords, the OTP keys and the protected
+ * fields.
+ * @returns The identifiers of the matching entries.
+ **/
+const searchEntries = async (search: string, allWords: boolean, includeProtected?: boolean) => {
+    const result: number[] = await invoke("search_entries", {
+        search: search,
+        allWords: allWords,
+        includeProtected: includeProtected,
+    });
+    return result;
+};
+
+/**
+ * Exports the open vault into a plain, unencrypted JSON file.
+ * @param targetFileName The file to write the JSON data into.
+ * @param includeProtected A value indicating whether to include the values of the protected fields.
+ * @returns A promise which rejects with an error message if the export failed.
+ **/
+const exportVault = async (targetFileName: string, includeProtected?: boolean) => {
+    await invoke("export_vault", { targetFileName: targetFileName, includeProtected: includeProtected });
+};
+
+/**
+ * Adds a new entry into the open vault or updates an existing one. The entry is placed into the category named
+ * by its first tag.
+ * @param entry The entry to add or update. The password of an existing entry is kept if not specified.
+ * @returns The identifier of the entry.
+ **/
+const updateEntry = async (entry: DataEntry) => {
+    const result: number = await invoke("update_entry", { entry: entry });
+    return result;
+};
+
+/**
+ * Deletes an entry or a category from the open vault into the recycle bin.
+ * @param id The identifier of the entry or the category.
+ * @returns A promise which rejects with an error message if the deletion failed.
+ **/
+const deleteEntry = async (id: number) => {
+    await invoke("delete_entry", { id: id });
+};
+
+/**
+ * Attaches a file to an entry of the open vault. The contents are encrypted into a blob store directory next to the
+ * vault file and become a part of the vault data once the vault is saved.
+ * @param id The identifier of the entry.
+ * @param fileName The file to attach.
+ * @returns The identifier of the attachment.
+ **/
+const attachFile = async (id: number, fileName: string) => {
+    const result: number = await invoke("attach_file", { id: id, fileName: fileName });
+    return result;
+};
+
+/**
+ * Lists the attachments of an entry of the open vault.
+ * @param id The identifier of the entry.
+ * @returns The attachments of the entry.
+ **/
+const listAttachments = async (id: number) => {
+    const result: AttachmentInfo[] = await invoke("list_attachments", { id: id });
+    return result;
+};
+
+/**
+ * Exports an attachment of an entry of the open vault into a file.
+ * @param id The identifier of the entry.
+ * @param attachmentId The identifier of the attachment.
+ * @param targetFileName The file to write the decrypted contents into.
+ * @returns A promise which rejects with an error message if the export failed.
+ **/
+const exportAttachment = async (id: number, attachmentId: number, targetFileName: string) => {
+    await invoke("export_attachment", { id: id, attachmentId: attachmentId, targetFileName: targetFileName });
+};
+
+/**
+ * Removes an attachment from an entry of the open vault. The encrypted contents are removed when the vault is saved.
+ * @param id The identifier of the entry.
+ * @param attachmentId The identifier of the attachment.
+ * @returns A promise which rejects with an error message if the removal failed.
+ **/
+const removeAttachment = async (id: number, attachmentId: number) => {
+    await invoke("remove_attachment", { id: id, attachmentId: attachmentId });
+};
+
+/**
+ * Lists the items in the recycle bin of the open vault without the secrets.
+ * @returns The items in the recycle bin.
+ **/
+const listTrash = async () => {
+    const result: TrashInfo[] = await invoke("list_trash");
+    return result;
+};
+
+/**
+ * Restores an item from the recycle bin of the open vault.
+ * @param id The identifier of the item in the recycle bin.
+ * @returns The identifier of the restored entry or category.
+ **/
+const restoreTrashItem = async (id: number) => {
+    const result: number = await invoke("restore_trash_item", { id: id });
+    return result;
+};
+
+/**
+ * Purges items from the recycle bin of the open vault permanently.
+ * @param id The identifier of the item in the recycle bin; `undefined` to empty the recycle bin.
+ * @returns A promise which rejects with an error message if the purge failed.
+ **/
+const purgeTrash = async (id?: number) => {
+    await invoke("purge_trash", { id: id });
+};
+
+/**
+ * Replaces the global file options of the open vault, e.g. the recycle bin purge policy and the password profiles.
+ * @param options The new file options.
+ * @returns A promise which rejects with an error message if the options couldn't be updated.
+ **/
+const updateFileOptions = async (options: FileOptions) => {
+    await invoke("update_file_options", { options: options });
+};
+
+/**
+ * Generates a random password. The default policy is used if neither a policy nor a profile is specified.
+ * @param policy The rules for the password.
+ * @param profile The name of a password profile to use if no policy is specified. The profiles of the open vault take
+ * precedence over the ones in the settings.
+ * @returns The generated password.
+ **/
+const generatePassword = async (policy?: PasswordPolicy, profile?: string) => {
+    const result: string = await invoke("generate_password", { policy: policy, profile: profile });
+    return result;
+};
+
+/**
+ * Generates a random passphrase of words.
+ * @param options The word count, the separator, the capitalization and the digit insertion; six words separated with
+ * a dash by default.
+ * @param wordlistFile A custom wordlist file with a word per line; the bundled EFF large wordlist by default.
+ * @returns The passphrase with its entropy estimate.
+ **/
+const generatePassphrase = async (options?: PassphraseOptions, wordlistFile?: string) => {
+    const result: Passphrase = await invoke("generate_passphrase", { options: options, wordlistFile: wordlistFile });
+    return result;
+};
+
+/**
+ * Estimates the strength of a password.
+ * @param password The password to estimate.
+ * @param userInputs The words related to the user or the entry, e.g. the user name and the domain, which make the
+ * password easier to guess.
+ * @returns The score from 0 to 4, the estimated guesses, the crack time estimates and the feedback. The estimator uses
+ * reduced dictionaries of a few hundred common passwords and words, so the score is an upper bound.
+ **/
+const estimateStrength = async (password: string, userInputs?: string[]) => {
+    const result: StrengthEstimate = await invoke("estimate_strength", { password: password, userInputs: userInputs });
+    return result;
+};
+
+/**
+ * Reports the expired entries and the entries expiring soon in the open vault.
+ * @param warningDays The amount of days before the expiry an entry is reported as expiring soon; 14 days by default.
+ * @returns The expiry report.
+ **/
+const getExpiryReport = async (warningDays?: number) => {
+    const result: ExpiryReport = await invoke("get_expiry_report", { warningDays: warningDays });
+    return result;
+};
+
+/**
+ * Audits the security of the entries of the open vault.
+ * @param minScore The minimum strength score from 0 to 4 of a password not reported as weak; 3 by default.
+ * @returns The weak, reused and empty passwords, the entries missing two-factor authentication and the duplicate
+ * entries.
+ **/
+const auditVault = async (minScore?: number) => {
+    const result: AuditReport = await invoke("audit_vault", { minScore: minScore });
+    return result;
+};
+
+/**
+ * Checks the passwords of the entries of the open vault against a locally downloaded Have I Been Pwned password hash
+ * file. Nothing is sent over the network.
+ * @param hibpFile The SHA-1 password hash file ordered by hash; the file in the settings by default.
+ * @returns The breach counts of the entries with a breached password.
+ **/
+const checkBreachedPasswords = async (hibpFile?: string) => {
+    const result: BreachReport = await invoke("check_breached_passwords", { hibpFile: hibpFile });
+    return result;
+};
+
+/**
+ * Lists the revisions of an entry of the open vault without the previous values.
+ * @param id The identifier of the entry.
+ * @returns The revisions, the oldest first.
+ **/
+const listRevisions = async (id: number) => {
+    const result: RevisionInfo[] = await invoke("list_revisions", { id: id });
+    return result;
+};
+
+/**
+ * Compares two versions of an entry of the open vault.
+ * @param id The identifier of the entry.
+ * @param from The index of the revision of the older version; the version is the entry before the change of the
+ * revision.
+ * @param to The index of the revision of the newer version; `undefined` for the current entry.
+ * @returns The differing fields and their values.
+ **/
+const diffRevisions = async (id: number, from: number, to?: number) => {
+    const result: FieldDiff[] = await invoke("diff_revisions", { id: id, from: from, to: to });
+    return result;
+};
+
+/**
+ * Restores an entry of the open vault to the version before the change of the specified revision. The restore is
+ * recorded as a new revision so it can be undone.
+ * @param id The identifier of the entry.
+ * @param index The index of the revision.
+ * @returns A promise which rejects with an error message if the restore failed.
+ **/
+const restoreRevision = async (id: number, index: number) => {
+    await invoke("restore_revision", { id: id, index: index });
+};
+
+/**
+ * Saves the open vault into the file it was opened from.
+ * @param options Optional key derivation parameters, cipher suite, compression and chunked encryption.
+ * @returns A promise which rejects with an error message if the save failed.
+ **/
+const saveVault = async (options?: SaveOptions) => {
+    await invoke("save_vault", { options: options });
+};
+
+/**
+ * Locks the open vault, wiping its decrypted data and key from the backend memory.
+ * @returns The file of the locked vault or `null` if no vault was open.
+ * @throws If the unsaved changes of the vault couldn't be saved; the vault is not locked then.
+ **/
+const lockVault = async () => {
+    const result: string | null = await invoke("lock_vault");
+    return result;
+};
+
+/**
+ * Unlocks the vault locked with {@link lockVault} or by the lock timeout.
+ * @param password The password of the vault file.
+ * @param keyFile An optional key file combined with the password.
+ * @returns The non-secret information of the vault.
+ **/
+const unlockVault = async (password: string, keyFile?: string) => {
+    const result: VaultSummary = await invoke("unlock_vault", {
+        password: password,
+        keyFile: keyFile,
+    });
+    return result;
+};
+
+/**
+ * Records user activity in the backend, postponing the lock timeout.
+ * @returns A promise which resolves when the activity is recorded.
+ **/
+const reportActivity = async () => {
+    await invoke("report_activity");
+};
+
+/**
+ * Gets the lock status and the failed unlock attempts from the backend.
+ * @returns The lock status.
+ **/
+const getLockStatus = async () => {
+    const result: LockStatus = await invoke("get_lock_status");
+    return result;
+};
+
+export {
+    loadImageFile,
+    changePassword,
+    generateKeyFile,
+    addPasswordSlot,
+    getKeySlots,
+    removeKeySlot,
+    generateRecoveryKey,
+    calibrateKdf,
+    verifyFile,
+    encryptFile,
+    decryptFile,
+    openVault,
+    closeVault,
+    listEntries,
+    getEntry,
+    getPassword,
+    getFieldValue,
+    searchEntries,
+    exportVault,
+    updateEntry,
+    deleteEntry,
+    attachFile,
+    listAttachments,
+    exportAttachment,
+    removeAttachment,
+    listTrash,
+    restoreTrashItem,
+    purgeTrash,
+    getExpiryReport,
+    entryExpiredEvent,
+    auditVault,
+    checkBreachedPasswords,
+    listRevisions,
+    diffRevisions,
+    restoreRevision,
+    saveVault,
+    updateFileOptions,
+    generatePassword,
+    generatePassphrase,
+    estimateStrength,
+    lockVault,
+    unlockVault,
+    reportActivity,
+    getLockStatus,
+    vaultLockedEvent,
+    vaultSaveFailedEvent,
+};
+export type {
+    KdfParams,
+    KeySlotInfo,
+    KdfCalibration,
+    CipherSuite,
+    Compression,
+    SaveOptions,
+    VerificationReport,
+    VaultSummary,
+    LockStatus,
+    RevisionInfo,
+    FieldDiff,
+    TrashInfo,
+    AttachmentInfo,
+    ExpiryInfo,
+    ExpiryReport,
+    PassphraseOptions,
+    Passphrase,
+    StrengthWarning,
+    StrengthSuggestion,
+    StrengthEstimate,
+    AuditEntry,
+    AuditReport,
+    BreachReport,
+};